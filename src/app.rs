@@ -1,11 +1,12 @@
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     error::Error,
     fmt::Display,
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
 use indexmap::IndexMap;
 use ratatui::{
     backend::Backend,
@@ -19,29 +20,53 @@ use tokio::{sync::mpsc, task::AbortHandle};
 use crate::widget::captcha::CaptchaPopup;
 
 use crate::{
-    client::{Client, DownloadClientResult, SingleDownloadResult},
+    client::{
+        self, Client, ClientConfig, DownloadClientResult, DownloadResponse, SingleDownloadResult,
+        TorrentAction, TorrentStatus,
+    },
     clip::ClipboardManager,
+    collection::{CollectionInfo, CollectionOp, COLLECTIONS_DIR},
+    command::Command,
     config::{Config, ConfigManager},
+    history::{
+        DownloadEntry, DownloadHistory, PendingDownloads, SearchHistory, DOWNLOAD_HISTORY_FILE,
+        PENDING_DOWNLOADS_FILE, SEARCH_HISTORY_FILE,
+    },
+    keymap::{Action, Keymap},
     results::Results,
     source::{
-        nyaa_html::NyaaHtmlSource, request_client, Item, Source, SourceInfo, SourceResults, Sources,
+        federated, nyaa_html::NyaaHtmlSource, request_client, Item, ItemDetails, Source,
+        SourceExtraConfig, SourceInfo, SourceResults, Sources,
     },
     sync::{EventSync, ReloadType, SearchQuery},
     theme::{self, Theme},
-    util::{conv::key_to_string, strings::minimal_magnet_link},
+    util::{
+        conv::{get_hash, key_to_string},
+        filter::LocalFilter,
+        strings::minimal_magnet_link,
+    },
     widget::{
         batch::BatchWidget,
         category::CategoryPopup,
         clients::ClientsPopup,
+        collections::CollectionsPopup,
+        command::CommandPopup,
+        details::DetailsWidget,
+        download_options::DownloadOptionsPopup,
         filter::FilterPopup,
         help::HelpPopup,
+        history::DownloadHistoryPopup,
+        local_filter::LocalFilterPopup,
         notifications::{Notification, NotificationWidget},
         page::PagePopup,
+        pending::PendingPopup,
         results::ResultsWidget,
+        saved::{SavedSearch, SavedSearchesPopup},
         search::SearchWidget,
         sort::{SortDir, SortPopup},
         sources::SourcesPopup,
         themes::ThemePopup,
+        torrents::TorrentsPopup,
         user::UserPopup,
         Widget,
     },
@@ -61,16 +86,25 @@ pub static APP_NAME: &str = "nyaa";
 // To ensure that other events will get a chance to be received
 static ANIMATE_SLEEP_MILLIS: u64 = 5;
 
+// How often the torrent status view asks the download client for updates
+static TORRENT_REFRESH_MILLIS: u64 = 2000;
+
+// How often failed downloads are checked to see if they're due to be retried
+static RETRY_CHECK_MILLIS: u64 = 5000;
+
 #[derive(PartialEq, Clone)]
 pub enum LoadType {
     Sourcing,
     Searching,
+    /// Searching again without reading the cache
+    Reloading,
     SolvingCaptcha(String),
     Sorting,
     Filtering,
     Categorizing,
     Batching,
     Downloading,
+    Details,
 }
 
 #[derive(PartialEq, Clone)]
@@ -82,12 +116,21 @@ pub enum Mode {
     Category,
     Sort(SortDir),
     Batch,
+    Details,
     Filter,
     Theme,
     Sources,
     Clients,
     Page,
     User,
+    SavedSearches,
+    DownloadHistory,
+    Torrents,
+    DownloadOptions,
+    PendingDownloads,
+    Collections,
+    LocalFilter,
+    Command,
     Help,
     #[cfg(feature = "captcha")]
     Captcha,
@@ -96,6 +139,7 @@ pub enum Mode {
 widgets! {
     Widgets;
     batch: [Mode::Batch] => BatchWidget,
+    details: [Mode::Details] => DetailsWidget,
     search: [Mode::Search] => SearchWidget,
     results: [Mode::Normal] => ResultsWidget,
     notification: NotificationWidget,
@@ -108,6 +152,14 @@ widgets! {
         clients: [Mode::Clients]  => ClientsPopup,
         page: [Mode::Page]  => PagePopup,
         user: [Mode::User] => UserPopup,
+        saved: [Mode::SavedSearches] => SavedSearchesPopup,
+        download_history: [Mode::DownloadHistory] => DownloadHistoryPopup,
+        torrents: [Mode::Torrents] => TorrentsPopup,
+        download_options: [Mode::DownloadOptions] => DownloadOptionsPopup,
+        pending: [Mode::PendingDownloads] => PendingPopup,
+        collections: [Mode::Collections] => CollectionsPopup,
+        local_filter: [Mode::LocalFilter] => LocalFilterPopup,
+        command: [Mode::Command] => CommandPopup,
         help: [Mode::Help] => HelpPopup,
         #[cfg(feature = "captcha")]
         captcha: [Mode::Captcha] => CaptchaPopup,
@@ -119,12 +171,14 @@ impl Display for LoadType {
         let s = match self {
             LoadType::Sourcing => "Sourcing",
             LoadType::Searching => "Searching",
+            LoadType::Reloading => "Reloading",
             LoadType::SolvingCaptcha(_) => "Solving",
             LoadType::Sorting => "Sorting",
             LoadType::Filtering => "Filtering",
             LoadType::Categorizing => "Categorizing",
             LoadType::Batching => "Downloading Batch",
             LoadType::Downloading => "Downloading",
+            LoadType::Details => "Loading Details",
         };
         write!(f, "{}", s)
     }
@@ -135,6 +189,7 @@ impl Display for Mode {
         let s = match self {
             Mode::Normal | Mode::KeyCombo(_) => "Normal",
             Mode::Batch => "Batch",
+            Mode::Details => "Details",
             Mode::Search => "Search",
             Mode::Category => "Category",
             Mode::Sort(_) => "Sort",
@@ -145,6 +200,14 @@ impl Display for Mode {
             Mode::Loading(_) => "Loading",
             Mode::Page => "Page",
             Mode::User => "User",
+            Mode::SavedSearches => "Saved Searches",
+            Mode::DownloadHistory => "Download History",
+            Mode::Torrents => "Torrents",
+            Mode::DownloadOptions => "Download Options",
+            Mode::PendingDownloads => "Pending Downloads",
+            Mode::Collections => "Collections",
+            Mode::LocalFilter => "Filter Results",
+            Mode::Command => "Command",
             Mode::Help => "Help",
             #[cfg(feature = "captcha")]
             Mode::Captcha => "Captcha",
@@ -168,19 +231,47 @@ pub struct Context {
     pub theme: Theme,
     pub config: Config,
     pub page: usize,
+    /// The last page shown, past `page` when more were appended by `infinite_scroll`
+    pub end_page: usize,
+    /// The page after `end_page`, loaded in the background
+    prefetched: Option<Results>,
     pub user: Option<String>,
     pub src: Sources,
+    /// Search every source in `[federated]` at once, instead of just `src`
+    pub federated: bool,
     pub client: Client,
     pub batch: Vec<Item>,
     pub last_key: String,
+    /// Key bindings for the results and batch, from the `[keys]` config section
+    pub keymap: Keymap,
     pub results: Results,
+    /// Filters the loaded results without searching again
+    pub local_filter: Option<LocalFilter>,
+    /// Every result from the last search, before `local_filter` is applied
+    all_results: Vec<Item>,
+    pub download_history: DownloadHistory,
+    pub pending: PendingDownloads,
+    pub collections: Vec<CollectionInfo>,
     pub deltatime: f64,
     //errors: Vec<String>,
     notifications: Vec<Notification>,
+    saved_search: Option<SavedSearch>,
+    command: Option<Command>,
+    redownload: Option<(Sources, Item)>,
+    torrent_action: Option<(TorrentAction, Vec<String>)>,
+    collection_op: Option<CollectionOp>,
+    /// Whether the download options popup should be opened for the batch
+    download_options: Option<bool>,
+    client_override: Option<ClientConfig>,
     failed_config_load: bool,
+    should_save_search_history: bool,
     should_quit: bool,
     should_dismiss_notifications: bool,
+    should_refresh_torrents: bool,
+    should_save_pending: bool,
+    should_retry_pending: bool,
     should_save_config: bool,
+    should_load_more: bool,
     skip_reload: bool,
 }
 
@@ -215,6 +306,179 @@ impl Context {
         Ok(())
     }
 
+    pub fn load_saved_search(&mut self, saved: SavedSearch) {
+        self.saved_search = Some(saved);
+        self.mode = Mode::Loading(LoadType::Searching);
+    }
+
+    /// Run a command typed after `:`
+    pub fn run_command(&mut self, command: Command) {
+        self.command = Some(command);
+    }
+
+    /// Send an item that isn't in the current results, like one from the download history
+    pub fn download(&mut self, src: Sources, item: Item) {
+        self.redownload = Some((src, item));
+        self.mode = Mode::Loading(LoadType::Downloading);
+    }
+
+    /// Ask the current download client for the status of its torrents
+    pub fn refresh_torrents(&mut self) {
+        self.should_refresh_torrents = true;
+    }
+
+    /// Every client a download is sent to. Picking a different client than
+    /// the first in `download_client` sends to just that one.
+    pub fn download_clients(&self) -> Vec<Client> {
+        match self.config.download_client.primary() == self.client {
+            true => self.config.download_client.to_vec(),
+            false => vec![self.client],
+        }
+    }
+
+    /// Change the client's settings before downloading the selected item or batch
+    pub fn configure_download(&mut self, batch: bool) {
+        self.download_options = Some(batch);
+    }
+
+    /// Download the selected item or batch with a different client config
+    pub fn download_with(&mut self, config: ClientConfig, batch: bool) {
+        self.client_override = Some(config);
+        self.mode = match batch {
+            true => Mode::Loading(LoadType::Batching),
+            false => Mode::Loading(LoadType::Downloading),
+        };
+    }
+
+    /// Pause, resume, recheck or remove torrents in the current download client
+    pub fn manage_torrents(&mut self, action: TorrentAction, hashes: Vec<String>) {
+        self.torrent_action = Some((action, hashes));
+    }
+
+    /// The source an item came from, which can differ from `src` for merged results
+    pub fn item_source(&self, item: &Item) -> Sources {
+        item.source.unwrap_or(self.src)
+    }
+
+    /// Add a result to the batch, remembering which source it came from
+    pub fn add_to_batch(&mut self, item: Item) {
+        let source = self.item_source(&item);
+        self.batch.push(Item {
+            source: Some(source),
+            ..item
+        });
+    }
+
+    /// Rebuild the results table, like after the theme changes or merged results are re-sorted
+    pub fn format_results(&mut self) {
+        self.results.table = match self.federated {
+            true => {
+                let fed = &self.config.federated;
+                federated::sort_items(&mut self.results.response.items, fed.sort, fed.sort_dir);
+                federated::format_table(&self.results.response.items, fed, &self.theme)
+            }
+            false => self.src.format_table(
+                &self.results.response.items,
+                &self.results.search,
+                &self.config.sources,
+                &self.theme,
+            ),
+        };
+    }
+
+    /// Replace the results with those from a new search, keeping the local filter
+    pub fn show_results(&mut self, results: Results) {
+        self.all_results.clone_from(&results.response.items);
+        self.results = results;
+        self.end_page = self.page;
+        self.prefetched = None;
+        self.should_load_more = false;
+        if self.local_filter.is_some() {
+            self.apply_local_filter();
+        }
+    }
+
+    /// Append the page after `end_page` to the results once it's loaded
+    pub fn load_more(&mut self) {
+        if self.config.infinite_scroll && self.end_page < self.results.response.last_page {
+            self.should_load_more = true;
+        }
+    }
+
+    pub fn loading_more(&self) -> bool {
+        self.should_load_more
+    }
+
+    /// The search for the page after `end_page`, which `infinite_scroll` loads next
+    pub fn next_search(&self) -> SearchQuery {
+        SearchQuery {
+            page: self.end_page + 1,
+            ..self.results.search.clone()
+        }
+    }
+
+    /// Add the next page to the end of the results, skipping anything already shown
+    fn append_results(&mut self, results: Results) {
+        let hash = |i: &Item| get_hash(i.magnet_link.clone()).map(|h| h.to_lowercase());
+        let mut ids: HashSet<(Option<Sources>, String)> = self
+            .all_results
+            .iter()
+            .map(|i| (i.source, i.id.clone()))
+            .collect();
+        let mut hashes: HashSet<String> = self.all_results.iter().filter_map(hash).collect();
+        for item in results.response.items {
+            // The same torrent can be listed under another id once the pages shift
+            let new_hash = hash(&item).map_or(true, |h| hashes.insert(h));
+            if ids.insert((item.source, item.id.clone())) && new_hash {
+                self.all_results.push(item);
+            }
+        }
+        self.end_page = results.search.page;
+        self.results.response.last_page = results.response.last_page;
+        self.results.response.total_results = results.response.total_results;
+        self.apply_local_filter();
+    }
+
+    pub fn all_results(&self) -> &[Item] {
+        &self.all_results
+    }
+
+    pub fn set_local_filter(&mut self, filter: Option<LocalFilter>) {
+        self.local_filter = filter.filter(|f| !f.is_empty());
+        self.apply_local_filter();
+    }
+
+    fn apply_local_filter(&mut self) {
+        self.results.response.items = match self.local_filter.as_ref() {
+            Some(filter) => self
+                .all_results
+                .iter()
+                .filter(|i| filter.matches(i))
+                .cloned()
+                .collect(),
+            None => self.all_results.clone(),
+        };
+        self.format_results();
+    }
+
+    /// Save, load or export the batch using the collections folder
+    pub fn collection(&mut self, op: CollectionOp) {
+        self.collection_op = Some(op);
+    }
+
+    pub fn save_pending(&mut self) {
+        self.should_save_pending = true;
+    }
+
+    /// Check for failed downloads to retry now, instead of waiting for the next check
+    pub fn retry_pending(&mut self) {
+        self.should_retry_pending = true;
+    }
+
+    pub fn save_search_history(&mut self) {
+        self.should_save_search_history = true;
+    }
+
     pub fn quit(&mut self) {
         self.should_quit = true;
     }
@@ -230,18 +494,39 @@ impl Default for Context {
             theme: Theme::default(),
             config: Config::default(),
             notifications: Vec::new(),
+            saved_search: None,
+            command: None,
+            redownload: None,
+            torrent_action: None,
+            federated: false,
+            keymap: Keymap::default(),
+            local_filter: None,
+            all_results: vec![],
+            collection_op: None,
+            download_options: None,
+            client_override: None,
+            should_save_search_history: false,
             page: 1,
+            end_page: 1,
+            prefetched: None,
             user: None,
             src: Sources::Nyaa,
             client: Client::Cmd,
             batch: vec![],
             last_key: "".to_owned(),
             results: Results::default(),
+            download_history: DownloadHistory::default(),
+            pending: PendingDownloads::default(),
+            collections: vec![],
             deltatime: 0.0,
             failed_config_load: true,
             should_quit: false,
             should_dismiss_notifications: false,
+            should_refresh_torrents: false,
+            should_save_pending: false,
+            should_retry_pending: false,
             should_save_config: false,
+            should_load_more: false,
             skip_reload: false,
         }
     }
@@ -258,12 +543,27 @@ impl App {
 
         let timer = tokio::time::sleep(Duration::from_millis(ANIMATE_SLEEP_MILLIS));
         tokio::pin!(timer);
+        let status_timer = tokio::time::sleep(Duration::from_millis(TORRENT_REFRESH_MILLIS));
+        tokio::pin!(status_timer);
+        let retry_timer = tokio::time::sleep(Duration::from_millis(RETRY_CHECK_MILLIS));
+        tokio::pin!(retry_timer);
 
         let (tx_res, mut rx_res) =
             mpsc::channel::<Result<SourceResults, Box<dyn Error + Send + Sync>>>(32);
+        // Pages appended to the results by `infinite_scroll`
+        let (tx_more, mut rx_more) =
+            mpsc::channel::<Result<SourceResults, Box<dyn Error + Send + Sync>>>(32);
         let (tx_evt, mut rx_evt) = mpsc::channel::<Event>(100);
-        let (tx_dl, mut rx_dl) = mpsc::channel::<DownloadClientResult>(100);
+        let (tx_dl, mut rx_dl) = mpsc::channel::<DownloadResponse>(100);
+        let (tx_details, mut rx_details) =
+            mpsc::channel::<Result<ItemDetails, Box<dyn Error + Send + Sync>>>(1);
         let (tx_cfg, mut rx_cfg) = mpsc::channel::<ReloadType>(1);
+        let (tx_status, mut rx_status) =
+            mpsc::channel::<Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>>(1);
+        let (tx_action, mut rx_action) =
+            mpsc::channel::<Result<String, Box<dyn Error + Send + Sync>>>(10);
+        let (tx_export, mut rx_export) =
+            mpsc::channel::<Result<String, Box<dyn Error + Send + Sync>>>(1);
 
         tokio::task::spawn(sync.clone().read_event_loop(tx_evt));
         tokio::task::spawn(sync.clone().watch_config_loop(tx_cfg));
@@ -286,6 +586,37 @@ impl App {
                 }
             }
         }
+        if !TEST {
+            if let Err(e) = term::set_mouse_capture(ctx.config.mouse) {
+                ctx.notify_error(format!("Failed to capture the mouse:\n{}", e));
+            }
+        }
+
+        let search_history_path = config_manager.path().join(SEARCH_HISTORY_FILE);
+        if !TEST {
+            match SearchHistory::load(&search_history_path) {
+                Ok(history) => self.widgets.search.history = history,
+                Err(e) => ctx.notify_error(format!("Failed to load search history:\n{}", e)),
+            }
+        }
+        let download_history_path = config_manager.path().join(DOWNLOAD_HISTORY_FILE);
+        if !TEST {
+            match DownloadHistory::load(&download_history_path) {
+                Ok(history) => ctx.download_history = history,
+                Err(e) => ctx.notify_error(format!("Failed to load download history:\n{}", e)),
+            }
+        }
+        let pending_path = config_manager.path().join(PENDING_DOWNLOADS_FILE);
+        if !TEST {
+            match PendingDownloads::load(&pending_path) {
+                Ok(pending) => ctx.pending = pending,
+                Err(e) => ctx.notify_error(format!("Failed to load pending downloads:\n{}", e)),
+            }
+        }
+        let collections_path = config_manager.path().join(COLLECTIONS_DIR);
+        // Downloads sent to a client, by id, so they can be recorded once they succeed.
+        // The flag marks attempts made by the retry timer rather than by the user.
+        let mut in_flight: HashMap<String, (Sources, Item, bool)> = HashMap::new();
 
         let jar = Arc::new(Jar::default());
         let source_rqclient =
@@ -293,6 +624,10 @@ impl App {
         // Don't use proxy for clients
         let client_rqclient = request_client(&jar, ctx.config.timeout, None)?;
         let mut last_load_abort: Option<AbortHandle> = None;
+        let mut last_more_abort: Option<AbortHandle> = None;
+        let mut prefetch = false;
+        let mut last_details_abort: Option<AbortHandle> = None;
+        let mut last_status_abort: Option<(Client, AbortHandle)> = None;
         let mut last_time: Option<Instant> = None;
 
         let (clipboard, err) = &mut if TEST {
@@ -304,6 +639,39 @@ impl App {
             ctx.notify_error(err);
         }
 
+        let search_task =
+            |tx: &mpsc::Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
+             ctx: &Context,
+             search: SearchQuery,
+             load_type: LoadType| {
+                let mut extra: SourceExtraConfig = ctx.config.clone().into();
+                if matches!(load_type, LoadType::Reloading) {
+                    extra.cache = extra.cache.refreshing();
+                }
+                // Captchas are solved for a single source, so they always go to `src`
+                match ctx.federated && !matches!(load_type, LoadType::SolvingCaptcha(_)) {
+                    true => tokio::spawn(sync.clone().federated_search(
+                        tx.clone(),
+                        source_rqclient.clone(),
+                        search,
+                        ctx.config.sources.clone(),
+                        ctx.theme.clone(),
+                        extra,
+                        ctx.config.federated.clone(),
+                    )),
+                    false => tokio::spawn(sync.clone().load_results(
+                        tx.clone(),
+                        load_type,
+                        ctx.src,
+                        source_rqclient.clone(),
+                        search,
+                        ctx.config.sources.clone(),
+                        ctx.theme.clone(),
+                        extra,
+                    )),
+                }
+            };
+
         while !ctx.should_quit {
             if ctx.should_save_config && ctx.config.save_config_on_change {
                 if let Err(e) = config_manager.store(&ctx.config) {
@@ -311,6 +679,26 @@ impl App {
                 }
                 ctx.should_save_config = false;
             }
+            if ctx.should_save_pending {
+                if !TEST {
+                    if let Err(e) = ctx.pending.store(&pending_path) {
+                        ctx.notify_error(format!("Failed to save pending downloads:\n{}", e));
+                    }
+                }
+                ctx.should_save_pending = false;
+            }
+            if ctx.should_retry_pending {
+                retry_timer.as_mut().reset(tokio::time::Instant::now());
+                ctx.should_retry_pending = false;
+            }
+            if ctx.should_save_search_history {
+                if !TEST {
+                    if let Err(e) = self.widgets.search.history.store(&search_history_path) {
+                        ctx.notify_error(format!("Failed to save search history:\n{}", e));
+                    }
+                }
+                ctx.should_save_search_history = false;
+            }
             if !ctx.notifications.is_empty() {
                 ctx.notifications
                     .clone()
@@ -332,6 +720,89 @@ impl App {
                 self.widgets.notification.dismiss_all();
                 ctx.should_dismiss_notifications = false;
             }
+            if let Some(batch) = ctx.download_options.take() {
+                let options = ctx.config.client.download_options(ctx.client);
+                if options.is_empty() {
+                    ctx.notify_warn(format!("{} has no download options to change", ctx.client));
+                } else {
+                    self.widgets
+                        .download_options
+                        .open(ctx.client, options, batch);
+                    ctx.mode = Mode::DownloadOptions;
+                }
+            }
+            match ctx.command.take() {
+                Some(Command::Yank(action)) => self.copy(ctx, clipboard, action),
+                Some(command) => command.run(ctx, &mut self.widgets),
+                None => {}
+            }
+            if let Some((action, hashes)) = ctx.torrent_action.take() {
+                tokio::spawn(sync.clone().torrent_action(
+                    tx_action.clone(),
+                    action,
+                    hashes,
+                    ctx.config.client.clone(),
+                    client_rqclient.clone(),
+                    ctx.client,
+                ));
+            }
+            match ctx.collection_op.take() {
+                Some(CollectionOp::ExportTorrents(dir)) => {
+                    ctx.notify_info(format!("Exporting {} torrents…", ctx.batch.len()));
+                    tokio::spawn(sync.clone().export_torrents(
+                        tx_export.clone(),
+                        ctx.batch.clone(),
+                        dir,
+                        client_rqclient.clone(),
+                    ));
+                }
+                Some(op) if !TEST => {
+                    if let Err(e) = op.run(ctx, &collections_path) {
+                        ctx.notify_error(e);
+                    }
+                }
+                _ => {}
+            }
+            if ctx.should_refresh_torrents {
+                if let Some((_, handle)) = last_status_abort.take() {
+                    handle.abort();
+                }
+                self.widgets.torrents.loading(ctx.client);
+                let task = tokio::spawn(sync.clone().torrent_status(
+                    tx_status.clone(),
+                    ctx.config.client.clone(),
+                    client_rqclient.clone(),
+                    ctx.client,
+                ));
+                last_status_abort = Some((ctx.client, task.abort_handle()));
+                status_timer.as_mut().reset(
+                    tokio::time::Instant::now() + Duration::from_millis(TORRENT_REFRESH_MILLIS),
+                );
+                ctx.should_refresh_torrents = false;
+            }
+            if ctx.should_load_more {
+                match ctx.prefetched.take() {
+                    Some(results) => {
+                        ctx.should_load_more = false;
+                        ctx.append_results(results);
+                        prefetch = true;
+                    }
+                    // The next page wasn't prefetched, so load it now
+                    None if last_more_abort.is_none() => prefetch = true,
+                    None => {}
+                }
+            }
+            if prefetch {
+                prefetch = false;
+                let page = ctx.end_page + 1;
+                if ctx.config.infinite_scroll && page <= ctx.results.response.last_page {
+                    if let Some(handle) = last_more_abort.take() {
+                        handle.abort();
+                    }
+                    let task = search_task(&tx_more, ctx, ctx.next_search(), LoadType::Searching);
+                    last_more_abort = Some(task.abort_handle());
+                }
+            }
             if ctx.mode == Mode::Batch && ctx.batch.is_empty() {
                 ctx.mode = Mode::Normal;
             }
@@ -342,39 +813,100 @@ impl App {
                 ctx.mode = Mode::Normal;
                 match load_type {
                     LoadType::Downloading => {
-                        if let Some(i) = self
-                            .widgets
-                            .results
-                            .table
-                            .selected()
-                            .and_then(|i| ctx.results.response.items.get(i))
-                        {
+                        let selected = ctx.redownload.take().or_else(|| {
+                            self.widgets
+                                .results
+                                .table
+                                .selected()
+                                .and_then(|i| ctx.results.response.items.get(i))
+                                .map(|i| (ctx.item_source(i), i.to_owned()))
+                        });
+                        if let Some((src, i)) = selected {
+                            // Options picked by hand skip the [[client.rules]]
+                            let (clients, config) = match ctx.client_override.take() {
+                                Some(config) => (ctx.download_clients(), config),
+                                None => ctx.config.client.routed(
+                                    ctx.config.client.find_rule(src, &i, &ctx.config.sources),
+                                    ctx.download_clients(),
+                                ),
+                            };
+                            in_flight.insert(i.id.clone(), (src, i.clone(), false));
+                            ctx.notify_info(format!(
+                                "Downloading torrent with {}",
+                                client::client_names(&clients)
+                            ));
                             tokio::spawn(sync.clone().download(
                                 tx_dl.clone(),
                                 false,
-                                vec![i.to_owned()],
-                                ctx.config.client.clone(),
+                                vec![i],
+                                config,
                                 client_rqclient.clone(),
-                                ctx.client,
+                                clients,
                             ));
-                            ctx.notify_info(format!("Downloading torrent with {}", ctx.client));
                         }
                         continue;
                     }
                     LoadType::Batching => {
-                        tokio::spawn(sync.clone().download(
-                            tx_dl.clone(),
-                            true,
-                            ctx.batch.clone(),
-                            ctx.config.client.clone(),
-                            client_rqclient.clone(),
-                            ctx.client,
-                        ));
-                        ctx.notify_info(format!(
-                            "Downloading {} torrents with {}",
-                            ctx.batch.len(),
-                            ctx.client
-                        ));
+                        let mut groups: BTreeMap<Option<usize>, Vec<Item>> = BTreeMap::new();
+                        let override_config = ctx.client_override.take();
+                        for i in ctx.batch.iter() {
+                            let rule = match override_config {
+                                Some(_) => None,
+                                None => ctx.config.client.find_rule(
+                                    ctx.item_source(i),
+                                    i,
+                                    &ctx.config.sources,
+                                ),
+                            };
+                            groups.entry(rule).or_default().push(i.clone());
+                        }
+                        for (rule, items) in groups {
+                            let (clients, config) = match override_config.clone() {
+                                Some(config) => (ctx.download_clients(), config),
+                                None => ctx.config.client.routed(rule, ctx.download_clients()),
+                            };
+                            for i in items.iter() {
+                                in_flight
+                                    .insert(i.id.clone(), (ctx.item_source(i), i.clone(), false));
+                            }
+                            ctx.notify_info(format!(
+                                "Downloading {} torrents with {}",
+                                items.len(),
+                                client::client_names(&clients)
+                            ));
+                            tokio::spawn(sync.clone().download(
+                                tx_dl.clone(),
+                                true,
+                                items,
+                                config,
+                                client_rqclient.clone(),
+                                clients,
+                            ));
+                        }
+                        continue;
+                    }
+                    LoadType::Details => {
+                        if let Some(i) = self
+                            .widgets
+                            .results
+                            .table
+                            .selected()
+                            .and_then(|i| ctx.results.response.items.get(i))
+                        {
+                            if let Some(handle) = last_details_abort.take() {
+                                handle.abort();
+                            }
+                            self.widgets.details.load(i.to_owned());
+                            let task = tokio::spawn(sync.clone().load_details(
+                                tx_details.clone(),
+                                ctx.item_source(i),
+                                source_rqclient.clone(),
+                                i.to_owned(),
+                                ctx.config.sources.clone(),
+                            ));
+                            last_details_abort = Some(task.abort_handle());
+                            ctx.mode = Mode::Details;
+                        }
                         continue;
                     }
                     LoadType::Sourcing => {
@@ -383,12 +915,18 @@ impl App {
                     }
                     _ => {}
                 }
+                if let Some(saved) = ctx.saved_search.take() {
+                    saved.apply(ctx, &mut self.widgets);
+                }
 
                 ctx.load_type = Some(load_type.clone());
 
                 if let Some(handle) = last_load_abort.as_ref() {
                     handle.abort();
                 }
+                if let Some(handle) = last_more_abort.take() {
+                    handle.abort();
+                }
 
                 let search = SearchQuery {
                     query: self.widgets.search.input.input.clone(),
@@ -399,16 +937,7 @@ impl App {
                     user: ctx.user.clone(),
                 };
 
-                let task = tokio::spawn(sync.clone().load_results(
-                    tx_res.clone(),
-                    load_type.clone(),
-                    ctx.src,
-                    source_rqclient.clone(),
-                    search,
-                    ctx.config.sources.clone(),
-                    ctx.theme.clone(),
-                    ctx.config.clone().into(),
-                ));
+                let task = search_task(&tx_res, ctx, search, load_type.clone());
                 last_load_abort = Some(task.abort_handle());
                 continue; // Redraw
             }
@@ -442,18 +971,19 @@ impl App {
                         match rt {
                             Ok(SourceResults::Results(rt)) => {
                                 self.widgets.results.reset();
-                                ctx.results = rt;
+                                ctx.show_results(rt);
+                                prefetch = true;
                             }
                             #[cfg(feature = "captcha")]
                             Ok(SourceResults::Captcha(c)) => {
-                                ctx.results = Results::default();
+                                ctx.show_results(Results::default());
                                 ctx.mode = Mode::Captcha;
                                 self.widgets.captcha.image = Some(c);
                                 self.widgets.captcha.input.clear();
                             }
                             Err(e) => {
                                 // Clear results on error
-                                ctx.results = Results::default();
+                                ctx.show_results(Results::default());
                                 ctx.notify_error(e);
                             },
                         }
@@ -461,23 +991,157 @@ impl App {
                         last_load_abort = None;
                         break;
                     },
-                    Some(dl) = rx_dl.recv() => {
-                        match dl {
-                            DownloadClientResult::Single(sr) => {
-                                match sr {
-                                    SingleDownloadResult::Success(suc) => {
-                                        ctx.notify(suc.msg);
-                                    },
-                                    SingleDownloadResult::Error(err) => {
-                                        ctx.notify(err.msg);
-                                    },
-                                };
+                    Some(rt) = rx_more.recv() => {
+                        match rt {
+                            // Ignore pages from a search that's been replaced since
+                            Ok(SourceResults::Results(rt)) if rt.search == ctx.next_search() => {
+                                ctx.prefetched = Some(rt);
+                            }
+                            Err(e) if ctx.should_load_more => {
+                                ctx.should_load_more = false;
+                                ctx.notify_error(format!("Failed to load page {}:\n{}", ctx.end_page + 1, e));
+                            }
+                            _ => {}
+                        }
+                        last_more_abort = None;
+                        break;
+                    },
+                    Some(details) = rx_details.recv() => {
+                        match details {
+                            Ok(details) => self.widgets.details.details = Some(details),
+                            Err(e) => {
+                                ctx.notify_error(format!("Failed to load details:\n{}", e));
+                                if ctx.mode == Mode::Details {
+                                    ctx.mode = Mode::Normal;
+                                }
+                            }
+                        }
+                        last_details_abort = None;
+                        break;
+                    },
+                    () = &mut status_timer, if ctx.mode == Mode::Torrents && last_status_abort.is_none() => {
+                        ctx.refresh_torrents();
+                        break;
+                    },
+                    Some(status) = rx_status.recv() => {
+                        if let Some((client, _)) = last_status_abort.take() {
+                            self.widgets.torrents.update(client, status);
+                        }
+                        break;
+                    },
+                    Some(res) = rx_action.recv() => {
+                        match res {
+                            Ok(msg) => ctx.notify_success(msg),
+                            Err(e) => ctx.notify_error(format!("Failed to manage torrents:\n{}", e)),
+                        }
+                        ctx.refresh_torrents();
+                        break;
+                    },
+                    Some(res) = rx_export.recv() => {
+                        match res {
+                            Ok(msg) => ctx.notify_success(msg),
+                            Err(e) => ctx.notify_error(e),
+                        }
+                        break;
+                    },
+                    () = &mut retry_timer, if !ctx.pending.entries.is_empty() => {
+                        retry_timer.as_mut().reset(tokio::time::Instant::now() + Duration::from_millis(RETRY_CHECK_MILLIS));
+                        let due = ctx.pending.due();
+                        if due.is_empty() {
+                            continue;
+                        }
+                        for pending in due {
+                            // Still waiting on a response from the last attempt
+                            if in_flight.contains_key(&pending.entry.id) {
+                                continue;
+                            }
+                            let (src, item) = (pending.entry.source, pending.item());
+                            let rule = ctx.config.client.find_rule(src, &item, &ctx.config.sources);
+                            let (clients, config) = ctx.config.client.routed(rule, pending.clients);
+                            in_flight.insert(item.id.clone(), (src, item.clone(), true));
+                            tokio::spawn(sync.clone().download(
+                                tx_dl.clone(),
+                                false,
+                                vec![item],
+                                config,
+                                client_rqclient.clone(),
+                                clients,
+                            ));
+                        }
+                        ctx.save_pending();
+                        break;
+                    },
+                    Some(DownloadResponse { ids: sent, result, outcomes }) = rx_dl.recv() => {
+                        let errors = match result {
+                            DownloadClientResult::Single(SingleDownloadResult::Success(suc)) => {
+                                ctx.notify(suc.msg);
+                                vec![]
                             }
+                            DownloadClientResult::Single(SingleDownloadResult::Error(err)) => vec![err.msg],
                             DownloadClientResult::Batch(br) => {
                                 if !br.ids.is_empty() {
                                     ctx.notify(br.msg);
                                 }
-                                br.errors.into_iter().for_each(|e| ctx.notify(e));
+                                br.errors
+                            }
+                        };
+                        // Background retries that fail again are already shown in the pending
+                        // downloads, but anything the user sent themselves is always reported
+                        let retry = sent
+                            .iter()
+                            .all(|id| in_flight.get(id).is_some_and(|(_, _, retry)| *retry));
+                        if !retry {
+                            errors.into_iter().for_each(|e| ctx.notify(e));
+                        }
+
+                        let (mut recorded, mut queued) = (false, 0);
+                        for id in sent {
+                            let Some((src, item, _)) = in_flight.remove(&id) else {
+                                continue;
+                            };
+                            let took = outcomes
+                                .iter()
+                                .filter(|o| o.ids.contains(&id))
+                                .map(|o| o.client)
+                                .collect::<Vec<Client>>();
+                            // Only remote clients are worth retrying, the others fail the same way again
+                            let failed = outcomes
+                                .iter()
+                                .filter(|o| o.client.is_remote())
+                                .filter_map(|o| {
+                                    let (_, e) = o.failed.iter().find(|(i, _)| *i == id)?;
+                                    Some((o.client, e.clone()))
+                                })
+                                .collect::<Vec<(Client, String)>>();
+                            if !took.is_empty() {
+                                ctx.download_history.push(DownloadEntry::new(src, &took, &item));
+                                recorded = true;
+                                if ctx.pending.sent(src, &id, &took) {
+                                    ctx.save_pending();
+                                }
+                                // The clients are reachable again, so flush anything waiting on them
+                                for c in took {
+                                    ctx.pending.retry_now(Some(c));
+                                }
+                            }
+                            if !failed.is_empty() {
+                                if !ctx.pending.contains(src, &id) {
+                                    queued += 1;
+                                }
+                                ctx.pending.fail(src, failed, &item);
+                                ctx.save_pending();
+                            }
+                        }
+                        if queued > 0 {
+                            let msg = format!("Queued {} failed download(s) to retry", queued);
+                            match ctx.keymap.key(&Mode::Normal, Action::PendingDownloads) {
+                                Some(key) => ctx.notify_warn(format!("{}, press {} to see them", msg, key)),
+                                None => ctx.notify_warn(msg),
+                            }
+                        }
+                        if recorded && !TEST {
+                            if let Err(e) = ctx.download_history.store(&download_history_path) {
+                                ctx.notify_error(format!("Failed to save download history:\n{}", e));
                             }
                         }
                         break;
@@ -495,6 +1159,9 @@ impl App {
                                             Ok(()) => ctx.notify_info("Reloaded config".to_owned()),
                                             Err(e) => ctx.notify_error(e),
                                         }
+                                        if let Err(e) = term::set_mouse_capture(ctx.config.mouse) {
+                                            ctx.notify_error(format!("Failed to capture the mouse:\n{}", e));
+                                        }
                                     }
                                     Err(e) => ctx.notify_error(e),
                                 }
@@ -527,8 +1194,19 @@ impl App {
         .split(f.area());
 
         self.widgets.search.draw(f, ctx, layout_vertical[0]);
-        // Dont draw batch pane if empty
-        if ctx.batch.is_empty() {
+        // Keep the details pane open behind the help popup
+        let show_details = ctx.mode == Mode::Details
+            || (ctx.mode == Mode::Help && self.widgets.help.prev_mode == Mode::Details);
+        if show_details {
+            let layout_horizontal = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)],
+            )
+            .split(layout_vertical[1]);
+            self.widgets.results.draw(f, ctx, layout_horizontal[0]);
+            self.widgets.details.draw(f, ctx, layout_horizontal[1]);
+        } else if ctx.batch.is_empty() {
+            // Dont draw batch pane if empty
             self.widgets.results.draw(f, ctx, layout_vertical[1]);
         } else {
             let layout_horizontal = Layout::new(
@@ -574,6 +1252,7 @@ impl App {
                 if let Err(e) = term::continue_self(terminal) {
                     panic!("Failed to continue program:\n{}", e);
                 }
+                let _ = term::set_mouse_capture(ctx.config.mouse);
                 return;
             }
             match ctx.mode.to_owned() {
@@ -583,9 +1262,17 @@ impl App {
                 _ => ctx.last_key = key_to_string(*code, *modifiers),
             };
         }
+        if let Event::Mouse(m) = evt {
+            self.on_mouse(ctx, m, evt);
+            return;
+        }
         match ctx.mode.to_owned() {
             Mode::KeyCombo(keys) => self.on_combo(ctx, clipboard, keys, evt),
             Mode::Loading(_) => {}
+            Mode::Normal => match ctx.keymap.action(&Mode::Normal, &ctx.last_key) {
+                Some(action) if action.is_copy() => self.copy(ctx, clipboard, action),
+                _ => self.widgets.handle_event(ctx, evt),
+            },
             _ => self.widgets.handle_event(ctx, evt),
         }
         if ctx.mode != Mode::Help {
@@ -593,6 +1280,34 @@ impl App {
         }
     }
 
+    fn on_mouse(&mut self, ctx: &mut Context, m: &MouseEvent, evt: &Event) {
+        match ctx.mode {
+            Mode::Normal | Mode::Batch => {
+                // Send the event to whichever pane is under the mouse, focusing it on click.
+                // Drags stay with the pane they started in.
+                let dragging = matches!(m.kind, MouseEventKind::Drag(_) | MouseEventKind::Up(_));
+                let mode = if dragging {
+                    ctx.mode.to_owned()
+                } else if !ctx.batch.is_empty() && self.widgets.batch.mouse.contains(m) {
+                    Mode::Batch
+                } else if self.widgets.results.mouse.contains(m) {
+                    Mode::Normal
+                } else {
+                    ctx.mode.to_owned()
+                };
+                if let MouseEventKind::Down(_) = m.kind {
+                    ctx.mode = mode.to_owned();
+                }
+                match mode {
+                    Mode::Batch => self.widgets.batch.handle_event(ctx, evt),
+                    _ => self.widgets.results.handle_event(ctx, evt),
+                }
+            }
+            Mode::KeyCombo(_) | Mode::Loading(_) => {}
+            _ => self.widgets.handle_event(ctx, evt),
+        }
+    }
+
     fn on_help(&mut self, e: &Event, ctx: &mut Context) {
         if let Event::Key(KeyEvent {
             code,
@@ -601,7 +1316,12 @@ impl App {
         }) = e
         {
             match code {
-                KeyCode::Char('?') if ctx.mode != Mode::Search => {
+                KeyCode::Char('?')
+                    if !matches!(
+                        ctx.mode,
+                        Mode::Search | Mode::DownloadOptions | Mode::LocalFilter | Mode::Command
+                    ) =>
+                {
                     ctx.mode = Mode::Help;
                 }
                 KeyCode::F(1) => {
@@ -613,7 +1333,13 @@ impl App {
     }
 
     fn get_help(&mut self, ctx: &Context) {
-        let help = self.widgets.get_help(&ctx.mode);
+        let help = ctx.keymap.help(&ctx.mode).or_else(|| {
+            self.widgets.get_help(&ctx.mode).map(|help| {
+                help.into_iter()
+                    .map(|(key, map)| (key.to_owned(), map))
+                    .collect()
+            })
+        });
         if let Some(msg) = help {
             self.widgets.help.with_items(msg, ctx.mode.clone());
             self.widgets.help.table.select(0);
@@ -630,61 +1356,131 @@ impl App {
         if let Event::Key(KeyEvent {
             code,
             kind: KeyEventKind::Press,
+            modifiers,
             ..
         }) = e
         {
             match code {
-                // Only handle standard chars for now
-                KeyCode::Char(c) => keys.push(*c),
                 KeyCode::Esc => {
                     // Stop combo if esc
                     ctx.mode = Mode::Normal;
                     return;
                 }
-                _ => {}
+                _ => keys.push_str(&key_to_string(*code, *modifiers)),
             }
         }
         ctx.last_key.clone_from(&keys);
-        match keys.chars().collect::<Vec<char>>()[..] {
-            ['y', c] => {
-                let s = self.widgets.results.table.state.selected().unwrap_or(0);
+        match ctx.keymap.action(&Mode::Normal, &keys) {
+            Some(action) if action.is_copy() => {
                 ctx.mode = Mode::Normal;
-                match ctx.results.response.items.get(s).cloned() {
-                    Some(item) => {
-                        let link = match c {
-                            't' => item.torrent_link,
-                            'm' => {
-                                if ctx.config.yank_full_magnet {
-                                    item.magnet_link
-                                } else {
-                                    match minimal_magnet_link(&item.magnet_link) {
-                                        Ok(magnet) => magnet,
-                                        Err(e) => return ctx.notify_error(e),
-                                    }
-                                }
-                            }
-                            'p' => item.post_link,
-                            'i' => match item.extra.get("imdb").cloned() {
-                                Some(imdb) => imdb,
-                                None => return ctx.notify_error("No imdb ID found for this item."),
-                            },
-                            'n' => item.title,
-                            _ => return,
-                        };
-                        match clipboard.try_copy(&link) {
-                            Ok(()) => {
-                                ctx.notify_success(format!("Copied \"{}\" to clipboard", link))
-                            }
-                            Err(e) => ctx.notify_error(e),
-                        }
-                    }
-                    None if ['t', 'm', 'p', 'i', 'n'].contains(&c) => {
-                        ctx.notify_error("Failed to copy:\nFailed to get item")
+                self.copy(ctx, clipboard, action);
+            }
+            Some(action) => {
+                ctx.mode = Mode::Normal;
+                self.widgets.results.run(ctx, action);
+            }
+            None if ctx.keymap.is_prefix(&Mode::Normal, &keys) => ctx.mode = Mode::KeyCombo(keys),
+            None => ctx.mode = Mode::Normal,
+        }
+    }
+
+    fn copy(&mut self, ctx: &mut Context, clipboard: &mut ClipboardManager, action: Action) {
+        let s = self.widgets.results.table.state.selected().unwrap_or(0);
+        let Some(item) = ctx.results.response.items.get(s).cloned() else {
+            return ctx.notify_error("Failed to copy:\nFailed to get item");
+        };
+        let link = match action {
+            Action::CopyTorrentLink => item.torrent_link,
+            Action::CopyMagnetLink => {
+                if ctx.config.yank_full_magnet {
+                    item.magnet_link
+                } else {
+                    match minimal_magnet_link(&item.magnet_link) {
+                        Ok(magnet) => magnet,
+                        Err(e) => return ctx.notify_error(e),
                     }
-                    None => {}
                 }
             }
-            _ => ctx.mode = Mode::KeyCombo(keys),
+            Action::CopyPostLink => item.post_link,
+            Action::CopyImdbId => match item.extra.get("imdb").cloned() {
+                Some(imdb) => imdb,
+                None => return ctx.notify_error("No imdb ID found for this item."),
+            },
+            Action::CopyName => item.title,
+            _ => return,
+        };
+        match clipboard.try_copy(&link) {
+            Ok(()) => ctx.notify_success(format!("Copied \"{}\" to clipboard", link)),
+            Err(e) => ctx.notify_error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::{ResultResponse, ResultTable};
+
+    fn page(query: &str, page: usize, items: Vec<Item>) -> Results {
+        Results::new(
+            SearchQuery {
+                query: query.to_owned(),
+                page,
+                ..Default::default()
+            },
+            ResultResponse {
+                total_results: 40,
+                items,
+                last_page: 2,
+            },
+            ResultTable::default(),
+        )
+    }
+
+    fn item(id: &str, hash: &str) -> Item {
+        Item {
+            id: id.to_owned(),
+            magnet_link: format!("magnet:?xt=urn:btih:{}&dn={}", hash, id),
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn test_infinite_scroll() {
+        let mut ctx = Context::default();
+        ctx.config.infinite_scroll = true;
+        ctx.show_results(page("show", 1, vec![item("1", "aaaa"), item("2", "bbbb")]));
+
+        ctx.load_more();
+        assert!(ctx.loading_more());
+        // Only the next page of the same search is appended
+        assert!(page("show", 2, vec![]).search == ctx.next_search());
+        assert!(page("other show", 2, vec![]).search != ctx.next_search());
+
+        ctx.append_results(page(
+            "show",
+            2,
+            vec![
+                // Shifted over from the first page
+                item("2", "bbbb"),
+                // Same torrent as "1" under another id
+                item("5", "AAAA"),
+                item("3", "cccc"),
+            ],
+        ));
+        let ids = ctx
+            .results
+            .response
+            .items
+            .iter()
+            .map(|i| i.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!(ctx.end_page, 2);
+
+        // Nothing left to load past the last page
+        ctx.should_load_more = false;
+        ctx.load_more();
+        assert!(!ctx.loading_more());
+    }
 }
@@ -0,0 +1,135 @@
+use std::{error::Error, process};
+
+use lexopt::Parser;
+
+use crate::{
+    config::{AppConfig, Config, ConfigManager},
+    source::SourceExtraConfig,
+    util::cache::CACHE_DIR,
+};
+
+use self::{download::DownloadArgs, search::SearchArgs, watch::WatchArgs};
+
+pub mod download;
+pub mod search;
+pub mod watch;
+
+pub enum Command {
+    Tui,
+    Search(SearchArgs),
+    Download(DownloadArgs),
+    Watch(WatchArgs),
+}
+
+pub struct Args {
+    pub config_path: Option<String>,
+    /// Only browse pages in the cache, without making requests
+    pub offline: bool,
+    pub command: Command,
+}
+
+static HELP: &str =
+    "Usage: nyaa [-v|-V|--version] [-c|--config=/path/to/config/folder] [--offline] [COMMAND]
+
+Options:
+  --offline  Only browse pages saved in the cache, see `cache_ttl`
+
+Commands:
+  search    Search a source and print the results to stdout
+  download  Send a torrent to the configured download client
+  watch     Periodically search for new torrents and download matches
+
+Run `nyaa COMMAND --help` for more information on a command";
+
+pub fn parse_args() -> Result<Args, Box<dyn Error>> {
+    use lexopt::prelude::*;
+
+    let mut config_path = None;
+    let mut offline = false;
+    let mut parser = lexopt::Parser::from_env();
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('c') | Long("config") => config_path = Some(config_value(&mut parser)?),
+            Short('v') | Short('V') | Long("version") => print_version(),
+            Long("offline") => offline = true,
+            Long("help") => {
+                println!("{}", HELP);
+                process::exit(0);
+            }
+            Value(cmd) => {
+                let command = match cmd.string()?.as_str() {
+                    "search" => Command::Search(search::parse(&mut parser, &mut config_path)?),
+                    "download" => {
+                        Command::Download(download::parse(&mut parser, &mut config_path)?)
+                    }
+                    "watch" => Command::Watch(watch::parse(&mut parser, &mut config_path)?),
+                    cmd => return Err(format!("Unknown command \"{}\"", cmd).into()),
+                };
+                return Ok(Args {
+                    config_path,
+                    offline,
+                    command,
+                });
+            }
+            _ => return Err(arg.unexpected().into()),
+        }
+    }
+
+    Ok(Args {
+        config_path,
+        offline,
+        command: Command::Tui,
+    })
+}
+
+/// Read the value of `-c/--config`, which is valid both before and after a command
+fn config_value(parser: &mut Parser) -> Result<String, Box<dyn Error>> {
+    use lexopt::prelude::*;
+
+    Ok(shellexpand::full(&parser.value()?.string()?)?.to_string())
+}
+
+fn print_version() -> ! {
+    println!("nyaa v{}", env!("CARGO_PKG_VERSION"));
+    process::exit(0);
+}
+
+/// Run a non-interactive command, exiting with a non-zero code on failure
+pub async fn run(command: Command, config_path: Option<String>, offline: bool) -> ! {
+    let res = match command {
+        Command::Tui => Ok(()),
+        Command::Search(args) => search::run(args, config_path, offline).await,
+        Command::Download(args) => download::run(args, config_path, offline).await,
+        Command::Watch(args) => watch::run(args, config_path, offline).await,
+    };
+    if let Err(e) = res {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    process::exit(0);
+}
+
+pub fn config_manager(config_path: Option<String>) -> Result<AppConfig, Box<dyn Error>> {
+    match config_path {
+        Some(path) => AppConfig::from_path(path),
+        None => AppConfig::new(),
+    }
+}
+
+pub fn load_config(config_path: Option<String>) -> Result<(AppConfig, Config), Box<dyn Error>> {
+    let manager = config_manager(config_path)?;
+    let mut config: Config = manager
+        .load()
+        .map_err(|e| format!("Failed to load config:\n{}", e))?;
+    config.client.compile_rules(&config.sources)?;
+    Ok((manager, config))
+}
+
+/// Source options that use the same cache as the TUI
+pub fn source_extra(manager: &AppConfig, config: &Config, offline: bool) -> SourceExtraConfig {
+    let mut extra: SourceExtraConfig = config.clone().into();
+    extra.cache = extra
+        .cache
+        .with_dir(manager.path().join(CACHE_DIR), offline);
+    extra
+}
@@ -0,0 +1,281 @@
+use std::{error::Error, process, sync::Arc, time::Duration};
+
+use lexopt::Parser;
+use reqwest::{cookie::Jar, StatusCode};
+use scraper::{Html, Selector};
+use urlencoding::decode;
+
+use crate::{
+    client::{self, Client, SingleDownloadResult},
+    config::{Config, ConfigManager},
+    history::{DownloadEntry, DownloadHistory, DOWNLOAD_HISTORY_FILE},
+    sel,
+    source::{request_client, torrent_galaxy, Item, SourceExtraConfig, Sources},
+    util::{
+        conv::{add_protocol, get_hash},
+        html::{attr, inner, labeled},
+    },
+};
+
+static HELP: &str =
+    "Usage: nyaa download [OPTIONS] <--id <ID>|--magnet <MAGNET>|--torrent-url <URL>>
+
+Options:
+  -s, --source <SOURCE>      Source the id belongs to (nyaa, sukebei, tgx)
+  -i, --id <ID>              Id of the post to download (e.g. 1234567)
+  -m, --magnet <MAGNET>      Magnet link to download
+  -t, --torrent-url <URL>    Link to a .torrent file to download
+  -d, --client <CLIENT>      Download client to use instead of `download_client`
+      --title <TITLE>        Title to use for the torrent";
+
+pub enum Target {
+    Id(String),
+    Magnet(String),
+    TorrentUrl(String),
+}
+
+pub struct DownloadArgs {
+    pub target: Target,
+    pub source: Option<Sources>,
+    pub client: Option<Client>,
+    pub title: Option<String>,
+}
+
+pub fn parse(
+    parser: &mut Parser,
+    config_path: &mut Option<String>,
+) -> Result<DownloadArgs, Box<dyn Error>> {
+    use lexopt::prelude::*;
+
+    let mut target = None;
+    let mut source = None;
+    let mut client = None;
+    let mut title = None;
+    while let Some(arg) = parser.next()? {
+        let new_target = match arg {
+            Short('c') | Long("config") => {
+                *config_path = Some(super::config_value(parser)?);
+                None
+            }
+            Short('v') | Short('V') | Long("version") => super::print_version(),
+            Short('s') | Long("source") => {
+                source = Some(parser.value()?.string()?.parse()?);
+                None
+            }
+            Short('d') | Long("client") => {
+                client = Some(parser.value()?.string()?.parse()?);
+                None
+            }
+            Long("title") => {
+                title = Some(parser.value()?.string()?);
+                None
+            }
+            Short('i') | Long("id") => Some(Target::Id(parser.value()?.string()?)),
+            Short('m') | Long("magnet") => Some(Target::Magnet(parser.value()?.string()?)),
+            Short('t') | Long("torrent-url") => Some(Target::TorrentUrl(parser.value()?.string()?)),
+            Long("help") => {
+                println!("{}", HELP);
+                process::exit(0);
+            }
+            _ => return Err(arg.unexpected().into()),
+        };
+        if new_target.is_some() {
+            if target.is_some() {
+                return Err("Only one of --id, --magnet or --torrent-url can be given".into());
+            }
+            target = new_target;
+        }
+    }
+
+    Ok(DownloadArgs {
+        target: target.ok_or("One of --id, --magnet or --torrent-url is required")?,
+        source,
+        client,
+        title,
+    })
+}
+
+fn magnet_title(magnet: &str) -> Option<String> {
+    let dn = magnet
+        .split(['?', '&'])
+        .find_map(|param| param.strip_prefix("dn="))?;
+    decode(&dn.replace('+', " ")).ok().map(|t| t.into_owned())
+}
+
+async fn fetch_item(
+    client: &reqwest::Client,
+    src: Sources,
+    id: &str,
+    config: &Config,
+    extra: &SourceExtraConfig,
+) -> Result<Item, Box<dyn Error>> {
+    let sources = &config.sources;
+    let (prefix, base_url, timeout) = match src {
+        Sources::Nyaa => {
+            let nyaa = sources.nyaa.clone().unwrap_or_default();
+            ("nyaa", nyaa.base_url, nyaa.timeout)
+        }
+        Sources::SukebeiNyaa => {
+            let sukebei = sources.sukebei.clone().unwrap_or_default();
+            ("sukebei", sukebei.base_url, sukebei.timeout)
+        }
+        Sources::TorrentGalaxy => {
+            let tgx = sources.tgx.clone().unwrap_or_default();
+            ("tgx", tgx.base_url, tgx.timeout)
+        }
+        Sources::Custom | Sources::Torznab => {
+            return Err(format!("Downloading by id is not supported for the {} source, use --magnet or --torrent-url instead", src).into());
+        }
+    };
+    // Accept ids copied straight from the TUI, e.g. `nyaa-1234567`
+    let id = id
+        .strip_prefix(&format!("{}-", prefix))
+        .unwrap_or(id)
+        .to_owned();
+    let base_url = add_protocol(base_url, true).map_err(|e| e.to_string())?;
+    let (post_link, torrent_link) = match src {
+        Sources::Nyaa | Sources::SukebeiNyaa => (
+            base_url.join(&format!("view/{}", id))?,
+            base_url
+                .join(&format!("download/{}.torrent", id))?
+                .to_string(),
+        ),
+        Sources::TorrentGalaxy | Sources::Custom | Sources::Torznab => {
+            (base_url.join(&format!("torrent/{}/", id))?, String::new())
+        }
+    };
+
+    let cached = extra.cache.get(&post_link).map_err(|e| e.to_string())?;
+    let content = match (src, cached) {
+        (_, Some(content)) => String::from_utf8(content)?,
+        // TGX turns away requests without a browser user agent
+        (Sources::TorrentGalaxy, None) => {
+            torrent_galaxy::try_get_content(client, timeout, &post_link)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        _ => {
+            let mut request = client.get(post_link.clone());
+            if let Some(timeout) = timeout {
+                request = request.timeout(Duration::from_secs(timeout));
+            }
+            let response = request.send().await?;
+            if response.status() != StatusCode::OK {
+                let code = response.status().as_u16();
+                return Err(format!("{}\nInvalid response code: {}", post_link, code).into());
+            }
+            response.text().await?
+        }
+    };
+    let doc = Html::parse_document(&content);
+
+    let magnet_sel = &sel!("a[href^=\"magnet:\"]")?;
+    let root = doc.root_element();
+    let magnet_link = attr(root, magnet_sel, "href");
+    if magnet_link.is_empty() && torrent_link.is_empty() {
+        return Err(format!("{}\nNo magnet link found", post_link).into());
+    }
+    extra.cache.put(&post_link, content.as_bytes());
+    let title = match src {
+        Sources::TorrentGalaxy => {
+            labeled(root, &sel!("div.tprow > div:first-child")?, "Name:").unwrap_or_default()
+        }
+        _ => inner(root, &sel!("h3.panel-title")?, "").trim().to_owned(),
+    };
+    let title = match title.is_empty() {
+        true => magnet_title(&magnet_link).unwrap_or(id.clone()),
+        false => title,
+    };
+    let id = format!("{}-{}", prefix, id);
+
+    Ok(Item {
+        file_name: format!("{}.torrent", id),
+        id,
+        title,
+        torrent_link,
+        magnet_link,
+        post_link: post_link.to_string(),
+        ..Default::default()
+    })
+}
+
+fn magnet_item(magnet: String) -> Item {
+    let hash = get_hash(magnet.clone()).unwrap_or_else(|| {
+        magnet
+            .split_once("xt=urn:btih:")
+            .map(|m| m.1.to_owned())
+            .unwrap_or_default()
+    });
+    Item {
+        title: magnet_title(&magnet).unwrap_or(hash.clone()),
+        file_name: format!("{}.torrent", hash),
+        id: hash,
+        magnet_link: magnet,
+        ..Default::default()
+    }
+}
+
+fn torrent_item(url: String) -> Item {
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|u| u.rsplit('/').next())
+        .filter(|f| !f.is_empty())
+        .unwrap_or("download.torrent")
+        .to_owned();
+    Item {
+        id: file_name.clone(),
+        title: file_name.trim_end_matches(".torrent").to_owned(),
+        file_name,
+        torrent_link: url,
+        ..Default::default()
+    }
+}
+
+pub async fn run(
+    args: DownloadArgs,
+    config_path: Option<String>,
+    offline: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (manager, config) = super::load_config(config_path)?;
+    let jar = Arc::new(Jar::default());
+
+    let src = args.source.unwrap_or(config.source);
+    let mut item = match args.target {
+        Target::Id(id) => {
+            let client = request_client(&jar, config.timeout, config.request_proxy.clone())?;
+            let extra = super::source_extra(&manager, &config, offline);
+            fetch_item(&client, src, &id, &config, &extra).await?
+        }
+        Target::Magnet(magnet) => magnet_item(magnet),
+        Target::TorrentUrl(url) => torrent_item(url),
+    };
+    if let Some(title) = args.title {
+        item.title = title;
+    }
+
+    // An explicit client skips the [[client.rules]]
+    let rule = match args.client {
+        Some(_) => None,
+        None => config.client.find_rule(src, &item, &config.sources),
+    };
+    let default = match args.client {
+        Some(client) => vec![client],
+        None => config.download_client.to_vec(),
+    };
+    let (clients, client_config) = config.client.routed(rule, default);
+    let rq_client = request_client(&jar, config.timeout, None)?;
+    let entry = DownloadEntry::new(src, &clients, &item);
+    match client::download_all(clients, item, client_config, rq_client).await {
+        SingleDownloadResult::Success(res) => {
+            println!("{}", res.msg.content);
+            if let Err(e) =
+                DownloadHistory::append(manager.path().join(DOWNLOAD_HISTORY_FILE), [entry])
+            {
+                eprintln!("Failed to save download history:\n{}", e);
+            }
+            Ok(())
+        }
+        SingleDownloadResult::Error(res) => Err(res.msg.content.into()),
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{self, Write},
+    process,
+    str::FromStr,
+    sync::Arc,
+};
+
+use lexopt::Parser;
+use reqwest::cookie::Jar;
+use serde::Serialize;
+
+use crate::{
+    app::LoadType,
+    source::{request_client, Item, SourceConfig, SourceExtraConfig, SourceResponse, Sources},
+    sync::SearchQuery,
+    widget::sort::{SelectedSort, SortDir},
+};
+
+static HELP: &str = "Usage: nyaa search [OPTIONS] <QUERY>...
+
+Options:
+  -s, --source <SOURCE>      Source to search (nyaa, sukebei, tgx, custom, torznab)
+      --custom <NAME>        Name of the custom source to search, when there are several
+      --category <CATEGORY>  Category id, config name or nyaa-style id (e.g. 1_2)
+      --filter <FILTER>      Filter name (e.g. \"trusted only\")
+      --sort <SORT>          Sort by name (e.g. seeders)
+      --sort-dir <DIR>       Sort direction (asc, desc)
+  -u, --user <USER>          Only show posts by this user
+  -p, --page <PAGE>          Page of results to fetch
+  -f, --format <FORMAT>      Output format (json, csv, tsv) [default: json]";
+
+#[derive(Clone, Copy, Default)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!("Unknown output format \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SearchArgs {
+    pub query: Vec<String>,
+    pub source: Option<Sources>,
+    pub custom: Option<String>,
+    pub category: Option<String>,
+    pub filter: Option<String>,
+    pub sort: Option<String>,
+    pub sort_dir: Option<SortDir>,
+    pub user: Option<String>,
+    pub page: Option<usize>,
+    pub format: OutputFormat,
+}
+
+pub fn parse(
+    parser: &mut Parser,
+    config_path: &mut Option<String>,
+) -> Result<SearchArgs, Box<dyn Error>> {
+    use lexopt::prelude::*;
+
+    let mut args = SearchArgs::default();
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('c') | Long("config") => *config_path = Some(super::config_value(parser)?),
+            Short('v') | Short('V') | Long("version") => super::print_version(),
+            Short('s') | Long("source") => {
+                args.source = Some(parser.value()?.string()?.parse()?);
+            }
+            Long("custom") => args.custom = Some(parser.value()?.string()?),
+            Long("category") => args.category = Some(parser.value()?.string()?),
+            Long("filter") => args.filter = Some(parser.value()?.string()?),
+            Long("sort") => args.sort = Some(parser.value()?.string()?),
+            Long("sort-dir") => args.sort_dir = Some(parser.value()?.string()?.parse()?),
+            Short('u') | Long("user") => args.user = Some(parser.value()?.string()?),
+            Short('p') | Long("page") => args.page = Some(parser.value()?.parse()?),
+            Short('f') | Long("format") => args.format = parser.value()?.string()?.parse()?,
+            Long("help") => {
+                println!("{}", HELP);
+                process::exit(0);
+            }
+            Value(q) => args.query.push(q.string()?),
+            _ => return Err(arg.unexpected().into()),
+        }
+    }
+    Ok(args)
+}
+
+#[derive(Serialize)]
+struct ItemOutput<'a> {
+    id: &'a str,
+    title: &'a str,
+    category: usize,
+    date: &'a str,
+    size: &'a str,
+    bytes: usize,
+    seeders: u32,
+    leechers: u32,
+    downloads: u32,
+    magnet_link: &'a str,
+    torrent_link: &'a str,
+    post_link: &'a str,
+    extra: BTreeMap<&'a str, &'a str>,
+}
+
+impl<'a> From<&'a Item> for ItemOutput<'a> {
+    fn from(item: &'a Item) -> Self {
+        ItemOutput {
+            id: &item.id,
+            title: &item.title,
+            category: item.category,
+            date: &item.date,
+            size: &item.size,
+            bytes: item.bytes,
+            seeders: item.seeders,
+            leechers: item.leechers,
+            downloads: item.downloads,
+            magnet_link: &item.magnet_link,
+            torrent_link: &item.torrent_link,
+            post_link: &item.post_link,
+            extra: item
+                .extra
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+        }
+    }
+}
+
+impl ItemOutput<'_> {
+    const HEADERS: [&'static str; 13] = [
+        "id",
+        "title",
+        "category",
+        "date",
+        "size",
+        "bytes",
+        "seeders",
+        "leechers",
+        "downloads",
+        "magnet_link",
+        "torrent_link",
+        "post_link",
+        "extra",
+    ];
+
+    fn fields(&self) -> [String; 13] {
+        [
+            self.id.to_owned(),
+            self.title.to_owned(),
+            self.category.to_string(),
+            self.date.to_owned(),
+            self.size.to_owned(),
+            self.bytes.to_string(),
+            self.seeders.to_string(),
+            self.leechers.to_string(),
+            self.downloads.to_string(),
+            self.magnet_link.to_owned(),
+            self.torrent_link.to_owned(),
+            self.post_link.to_owned(),
+            self.extra
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<String>>()
+                .join(";"),
+        ]
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn tsv_field(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+pub fn write_items<W: Write>(
+    out: &mut W,
+    items: &[Item],
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let items = items.iter().map(ItemOutput::from).collect::<Vec<_>>();
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &items)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let (sep, escape): (&str, fn(&str) -> String) = match format {
+                OutputFormat::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+            writeln!(out, "{}", ItemOutput::HEADERS.join(sep))?;
+            for item in items.iter() {
+                let row = item
+                    .fields()
+                    .iter()
+                    .map(|f| escape(f))
+                    .collect::<Vec<String>>();
+                writeln!(out, "{}", row.join(sep))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Build a query for `src` from the arguments, using the source's defaults for anything missing
+pub fn build_query(
+    args: &SearchArgs,
+    src: Sources,
+    config: &SourceConfig,
+) -> Result<SearchQuery, Box<dyn Error>> {
+    let info = src.info(config);
+    let category = match &args.category {
+        Some(c) => {
+            info.find_category(c)
+                .map_err(|e| format!("{} for {}", e, src))?
+                .id
+        }
+        None => src.default_category(config),
+    };
+    let filter = match &args.filter {
+        Some(f) => info
+            .find_filter(f)
+            .ok_or(format!("Unknown filter \"{}\" for {}", f, src))?,
+        None => src.default_filter(config),
+    };
+    let sort = match &args.sort {
+        Some(s) => SelectedSort {
+            sort: info
+                .find_sort(s)
+                .ok_or(format!("Unknown sort \"{}\" for {}", s, src))?,
+            dir: args.sort_dir.unwrap_or(SortDir::Desc),
+        },
+        None => {
+            let mut sort = src.default_sort(config);
+            sort.dir = args.sort_dir.unwrap_or(sort.dir);
+            sort
+        }
+    };
+    let query = match args.query.is_empty() {
+        true => src.default_search(config),
+        false => args.query.join(" "),
+    };
+
+    Ok(SearchQuery {
+        query,
+        page: args.page.unwrap_or(1).max(1),
+        category,
+        filter,
+        sort,
+        user: args.user.clone(),
+    })
+}
+
+/// Run a search outside of the TUI, treating a captcha as an error
+pub async fn load_items(
+    src: Sources,
+    client: &reqwest::Client,
+    search: &SearchQuery,
+    sources: &SourceConfig,
+    extra: &SourceExtraConfig,
+) -> Result<Vec<Item>, Box<dyn Error>> {
+    let res = src
+        .load(LoadType::Searching, client, search, sources, extra)
+        .await
+        .map_err(|e| e.to_string())?;
+    match res {
+        SourceResponse::Results(res) => Ok(res.items),
+        #[cfg(feature = "captcha")]
+        SourceResponse::Captcha(_) => {
+            Err(format!("{} requires a captcha to be solved, try again later", src).into())
+        }
+    }
+}
+
+pub async fn run(
+    args: SearchArgs,
+    config_path: Option<String>,
+    offline: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (manager, mut config) = super::load_config(config_path)?;
+    let src = match args.custom {
+        Some(_) => args.source.unwrap_or(Sources::Custom),
+        None => args.source.unwrap_or(config.source),
+    };
+    if let Some(name) = args.custom.clone() {
+        config.sources.custom_source = Some(name);
+    }
+    src.load_config(&mut config.sources);
+    let search = build_query(&args, src, &config.sources)?;
+
+    let jar = Arc::new(Jar::default());
+    let client = request_client(&jar, config.timeout, config.request_proxy.clone())?;
+    let extra = super::source_extra(&manager, &config, offline);
+    let items = load_items(src, &client, &search, &config.sources, &extra).await?;
+
+    write_items(&mut io::stdout().lock(), &items, args.format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_items_csv() {
+        let items = vec![Item {
+            id: "nyaa-1".to_owned(),
+            title: "Title, \"quoted\"".to_owned(),
+            ..Default::default()
+        }];
+        let mut out = vec![];
+        write_items(&mut out, &items, OutputFormat::Csv).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let row = out.lines().nth(1).unwrap();
+        assert!(row.starts_with("nyaa-1,\"Title, \"\"quoted\"\"\",0,"));
+    }
+
+    #[test]
+    fn test_find_category() {
+        let config = SourceConfig::default();
+        let category = |c: &str| {
+            let args = SearchArgs {
+                category: Some(c.to_owned()),
+                ..Default::default()
+            };
+            build_query(&args, Sources::Nyaa, &config).map(|q| q.category)
+        };
+        assert_eq!(category("AnimeEnglishTranslated").unwrap(), 12);
+        assert_eq!(category("1_2").unwrap(), 12);
+        assert_eq!(category("anime music video").unwrap(), 11);
+        // Shared by Anime, Literature and Live Action
+        let err = category("English Translated").unwrap_err().to_string();
+        assert!(err.contains("AnimeEnglishTranslated, LitEnglishTranslated"));
+        assert!(category("anime_english").is_err());
+    }
+}
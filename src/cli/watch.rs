@@ -0,0 +1,416 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    error::Error,
+    fmt::Display,
+    process,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Local;
+use lexopt::Parser;
+use regex::{Regex, RegexBuilder};
+use reqwest::cookie::Jar;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{self, Client, SingleDownloadResult},
+    config::{self, Config, ConfigManager},
+    history::{DownloadEntry, DownloadHistory, DOWNLOAD_HISTORY_FILE},
+    source::{request_client, Item, SourceConfig, SourceExtraConfig, Sources},
+    sync::SearchQuery,
+    util::conv::to_bytes,
+};
+
+use super::search::{self, SearchArgs};
+
+pub static WATCH_STATE_FILE: &str = "watch_state.toml";
+
+/// Number of seen ids remembered before the oldest are forgotten
+static MAX_SEEN: usize = 10000;
+
+static HELP: &str = "Usage: nyaa watch [OPTIONS]
+
+Periodically run the rules under [[watch.rules]] in config.toml and send new matches to the download client
+
+Options:
+      --once      Check every rule once, then exit
+      --dry-run   Print matches without downloading them or marking them as seen";
+
+#[derive(Default)]
+pub struct WatchArgs {
+    pub once: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Seconds to wait between checking all rules
+    pub interval: u64,
+    /// Use the RSS feed for sources that support it
+    pub rss: bool,
+    pub rules: Vec<WatchRule>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            interval: 900,
+            rss: true,
+            rules: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WatchRule {
+    pub name: String,
+    pub source: Option<Sources>,
+    /// Name of the custom source to search, which implies `source = "Custom"`
+    pub custom: Option<String>,
+    pub query: String,
+    pub category: Option<String>,
+    pub filter: Option<String>,
+    pub user: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub min_seeders: Option<u32>,
+    pub min_size: Option<String>,
+    pub max_size: Option<String>,
+    pub client: Option<Client>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct WatchState {
+    pub seen: VecDeque<String>,
+}
+
+struct CompiledRule {
+    name: String,
+    src: Sources,
+    /// The source config with the rule's custom source picked
+    sources: SourceConfig,
+    search: SearchQuery,
+    title: Option<Regex>,
+    uploader: Option<Regex>,
+    min_seeders: u32,
+    min_bytes: usize,
+    max_bytes: usize,
+    client: Option<Client>,
+}
+
+pub fn parse(
+    parser: &mut Parser,
+    config_path: &mut Option<String>,
+) -> Result<WatchArgs, Box<dyn Error>> {
+    use lexopt::prelude::*;
+
+    let mut args = WatchArgs::default();
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('c') | Long("config") => *config_path = Some(super::config_value(parser)?),
+            Short('v') | Short('V') | Long("version") => super::print_version(),
+            Long("once") => args.once = true,
+            Long("dry-run") => args.dry_run = true,
+            Long("help") => {
+                println!("{}", HELP);
+                process::exit(0);
+            }
+            _ => return Err(arg.unexpected().into()),
+        }
+    }
+    Ok(args)
+}
+
+fn regex(pattern: &Option<String>) -> Result<Option<Regex>, regex::Error> {
+    pattern
+        .as_ref()
+        .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+        .transpose()
+}
+
+impl CompiledRule {
+    fn new(rule: &WatchRule, config: &Config) -> Result<Self, Box<dyn Error>> {
+        let err = |e: &dyn Display| format!("Watch rule \"{}\":\n{}", rule.name, e);
+        let src = match rule.custom {
+            Some(_) => rule.source.unwrap_or(Sources::Custom),
+            None => rule.source.unwrap_or(config.source),
+        };
+        let mut sources = config.sources.clone();
+        if let Some(name) = rule.custom.clone() {
+            sources.custom_source = Some(name);
+        }
+        src.load_config(&mut sources);
+        if rule.uploader.is_some() && !src.has_uploader() {
+            return Err(err(&format!(
+                "{} results don't show the uploader, use `user` instead of `uploader`",
+                src
+            ))
+            .into());
+        }
+        let args = SearchArgs {
+            query: vec![rule.query.clone()],
+            category: rule.category.clone(),
+            filter: rule.filter.clone(),
+            // Custom sources only have the sorts they define, so use their default otherwise
+            sort: src
+                .info(&sources)
+                .find_sort("date")
+                .map(|_| "date".to_owned()),
+            user: rule.user.clone(),
+            ..Default::default()
+        };
+        Ok(Self {
+            name: rule.name.clone(),
+            src,
+            search: search::build_query(&args, src, &sources).map_err(|e| err(&e))?,
+            sources,
+            title: regex(&rule.title).map_err(|e| err(&e))?,
+            uploader: regex(&rule.uploader).map_err(|e| err(&e))?,
+            min_seeders: rule.min_seeders.unwrap_or(0),
+            min_bytes: rule.min_size.as_deref().map(to_bytes).unwrap_or(0),
+            max_bytes: rule.max_size.as_deref().map(to_bytes).unwrap_or(usize::MAX),
+            client: rule.client,
+        })
+    }
+
+    fn matches(&self, item: &Item) -> bool {
+        let uploader = item.extra.get("uploader");
+        self.title
+            .as_ref()
+            .map_or(true, |r| r.is_match(&item.title))
+            && self
+                .uploader
+                .as_ref()
+                .map_or(true, |r| uploader.is_some_and(|u| r.is_match(u)))
+            && item.seeders >= self.min_seeders
+            && (self.min_bytes..=self.max_bytes).contains(&item.bytes)
+    }
+}
+
+impl WatchState {
+    fn mark_seen(&mut self, id: String) {
+        self.seen.push_back(id);
+        while self.seen.len() > MAX_SEEN {
+            self.seen.pop_front();
+        }
+    }
+}
+
+fn log<S: Display>(msg: S) {
+    println!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg);
+}
+
+async fn check_rule(
+    rule: &CompiledRule,
+    args: &WatchArgs,
+    config: &Config,
+    extra: &SourceExtraConfig,
+    state: &mut WatchState,
+    clients: &(reqwest::Client, reqwest::Client),
+    downloaded: &mut Vec<DownloadEntry>,
+) -> Result<(), Box<dyn Error>> {
+    let (src_client, dl_client) = clients;
+    let items =
+        search::load_items(rule.src, src_client, &rule.search, &rule.sources, extra).await?;
+
+    let seen = state.seen.iter().cloned().collect::<HashSet<String>>();
+    let matches = items
+        .into_iter()
+        .filter(|i| !seen.contains(&i.id) && rule.matches(i))
+        .collect::<Vec<Item>>();
+    for item in matches.into_iter().rev() {
+        if args.dry_run {
+            log(format!("[{}] Matched \"{}\"", rule.name, item.title));
+            continue;
+        }
+        let (id, title) = (item.id.clone(), item.title.clone());
+        // A client picked by the watch rule skips the [[client.rules]]
+        let (clients, client_config) = match rule.client {
+            Some(client) => config.client.routed(None, vec![client]),
+            None => config.client.routed(
+                config.client.find_rule(rule.src, &item, &rule.sources),
+                config.download_client.to_vec(),
+            ),
+        };
+        let entry = DownloadEntry::new(rule.src, &clients, &item);
+        match client::download_all(clients, item, client_config, dl_client.clone()).await {
+            SingleDownloadResult::Success(res) => {
+                log(format!(
+                    "[{}] Sent \"{}\": {}",
+                    rule.name, title, res.msg.content
+                ));
+                state.mark_seen(id);
+                downloaded.push(entry);
+            }
+            SingleDownloadResult::Error(e) => log(format!(
+                "[{}] Failed to download \"{}\":\n{}",
+                rule.name, title, e.msg.content
+            )),
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(
+    args: WatchArgs,
+    config_path: Option<String>,
+    offline: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (manager, mut config) = super::load_config(config_path)?;
+    let watch = config.watch.clone().unwrap_or_default();
+    if watch.rules.is_empty() {
+        return Err("No watch rules found, add some under [[watch.rules]] in config.toml".into());
+    }
+
+    for src in watch.rules.iter().filter_map(|r| r.source) {
+        src.load_config(&mut config.sources);
+    }
+    config.source.load_config(&mut config.sources);
+    if watch.rss {
+        if let Some(nyaa) = config.sources.nyaa.as_mut() {
+            nyaa.rss = true;
+        }
+        if let Some(sukebei) = config.sources.sukebei.as_mut() {
+            sukebei.rss = true;
+        }
+    }
+    let rules = watch
+        .rules
+        .iter()
+        .map(|r| CompiledRule::new(r, &config))
+        .collect::<Result<Vec<CompiledRule>, Box<dyn Error>>>()?;
+
+    let state_path = manager.path().join(WATCH_STATE_FILE);
+    let mut state: WatchState = config::load_path(&state_path)?;
+
+    let extra = super::source_extra(&manager, &config, offline);
+    let jar = Arc::new(Jar::default());
+    let clients = (
+        request_client(&jar, config.timeout, config.request_proxy.clone())?,
+        request_client(&jar, config.timeout, None)?,
+    );
+    let history_path = manager.path().join(DOWNLOAD_HISTORY_FILE);
+    loop {
+        let mut downloaded = vec![];
+        for rule in rules.iter() {
+            if let Err(e) = check_rule(
+                rule,
+                &args,
+                &config,
+                &extra,
+                &mut state,
+                &clients,
+                &mut downloaded,
+            )
+            .await
+            {
+                log(format!("[{}] Failed to search:\n{}", rule.name, e));
+            }
+        }
+        if !args.dry_run {
+            config::store_path(&state_path, &state)?;
+        }
+        if !downloaded.is_empty() {
+            if let Err(e) = DownloadHistory::append(&history_path, downloaded) {
+                log(format!("Failed to save download history:\n{}", e));
+            }
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(watch.interval.max(1))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_matches() {
+        let rule = CompiledRule {
+            name: "test".to_owned(),
+            src: Sources::Nyaa,
+            sources: SourceConfig::default(),
+            search: SearchQuery::default(),
+            title: regex(&Some("one piece.*1080p".to_owned())).unwrap(),
+            uploader: None,
+            min_seeders: 10,
+            min_bytes: to_bytes("1 GiB"),
+            max_bytes: to_bytes("2 GiB"),
+            client: Some(Client::Cmd),
+        };
+        let item = Item {
+            title: "[Group] One Piece - 1100 [1080p].mkv".to_owned(),
+            seeders: 20,
+            bytes: to_bytes("1.4 GiB"),
+            ..Default::default()
+        };
+        assert!(rule.matches(&item));
+        assert!(!rule.matches(&Item {
+            seeders: 5,
+            ..item.clone()
+        }));
+        assert!(!rule.matches(&Item {
+            bytes: to_bytes("500 MiB"),
+            ..item.clone()
+        }));
+        assert!(!rule.matches(&Item {
+            title: "[Group] One Piece - 1100 [720p].mkv".to_owned(),
+            ..item
+        }));
+    }
+
+    #[test]
+    fn test_uploader_needs_source() {
+        let rule = WatchRule {
+            name: "test".to_owned(),
+            uploader: Some("someone".to_owned()),
+            ..Default::default()
+        };
+        let config = Config::default();
+        assert!(CompiledRule::new(&rule, &config).is_err());
+        let rule = WatchRule {
+            source: Some(Sources::TorrentGalaxy),
+            ..rule
+        };
+        assert!(CompiledRule::new(&rule, &config).is_ok());
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let config = Config {
+            sources: toml::from_str(
+                r#"
+[[custom]]
+name = "Example"
+base_url = "https://example.org/"
+
+[[custom]]
+name = "Other"
+base_url = "https://other.example.net/"
+sorts = [{ name = "Seeders", value = "seeders" }]
+"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let rule = WatchRule {
+            name: "test".to_owned(),
+            custom: Some("Other".to_owned()),
+            ..Default::default()
+        };
+        // Without a "date" sort, the custom source's default is used
+        let rule = CompiledRule::new(&rule, &config).unwrap();
+        assert!(rule.src == Sources::Custom);
+        assert_eq!(
+            rule.sources.custom().unwrap().base_url,
+            "https://other.example.net/"
+        );
+        assert_eq!(rule.search.sort.sort, 0);
+    }
+}
@@ -1,25 +1,35 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 use strum::{Display, VariantArray};
-use tokio::task::JoinSet;
+use tokio::task::{JoinHandle, JoinSet};
 
-use crate::{client::cmd::CmdClient, source::Item, widget::notifications::Notification};
+use crate::{
+    client::cmd::CmdClient,
+    source::Item,
+    widget::notifications::{Notification, NotificationType},
+};
 
 use self::{
+    aria2::{Aria2Client, Aria2Config},
     cmd::CmdConfig,
     default_app::{DefaultAppClient, DefaultAppConfig},
+    deluge::{DelugeClient, DelugeConfig},
     download::{DownloadConfig, DownloadFileClient},
     qbit::{QbitClient, QbitConfig},
     rqbit::{RqbitClient, RqbitConfig},
+    rules::ClientRule,
     transmission::{TransmissionClient, TransmissionConfig},
 };
 
+pub mod aria2;
 pub mod cmd;
 pub mod default_app;
+pub mod deluge;
 pub mod download;
 pub mod qbit;
 pub mod rqbit;
+pub mod rules;
 pub mod transmission;
 
 pub struct DownloadError(String);
@@ -50,6 +60,62 @@ pub trait DownloadClient {
     fn load_config(cfg: &mut ClientConfig);
 }
 
+/// A torrent as reported by a download client
+#[derive(Clone, Default)]
+pub struct TorrentStatus {
+    pub hash: String,
+    pub name: String,
+    /// Between 0 and 1
+    pub progress: f64,
+    pub size: usize,
+    /// Bytes per second
+    pub dl_speed: u64,
+    pub up_speed: u64,
+    /// Seconds until done, if known
+    pub eta: Option<u64>,
+    pub state: String,
+}
+
+/// Something to do to torrents already in a download client
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TorrentAction {
+    Pause,
+    Resume,
+    Recheck,
+    Remove,
+    RemoveWithData,
+}
+
+impl TorrentAction {
+    fn done(self) -> &'static str {
+        match self {
+            Self::Pause => "Paused",
+            Self::Resume => "Resumed",
+            Self::Recheck => "Started rechecking",
+            Self::Remove => "Removed",
+            Self::RemoveWithData => "Removed (with data)",
+        }
+    }
+}
+
+/// Clients that can report on and manage the torrents they're downloading
+pub trait TorrentManager {
+    fn status(
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> impl std::future::Future<Output = Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>>
+           + std::marker::Send
+           + 'static;
+    fn action(
+        action: TorrentAction,
+        hashes: Vec<String>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> impl std::future::Future<Output = Result<(), Box<dyn Error + Send + Sync>>>
+           + std::marker::Send
+           + 'static;
+}
+
 impl Display for DownloadError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -74,6 +140,8 @@ pub struct BatchDownloadResult {
     pub msg: Notification,
     pub errors: Vec<Notification>,
     pub ids: Vec<String>,
+    /// Ids that couldn't be sent, along with why
+    pub failed: Vec<(String, String)>,
 }
 
 pub enum DownloadClientResult {
@@ -81,6 +149,46 @@ pub enum DownloadClientResult {
     Batch(BatchDownloadResult),
 }
 
+/// What one client did with a download: the ids it took, and why it failed on the others
+#[derive(Clone)]
+pub struct ClientOutcome {
+    pub client: Client,
+    pub ids: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+impl ClientOutcome {
+    fn single(client: Client, id: &str, res: &SingleDownloadResult) -> Self {
+        let (ids, failed) = match res {
+            SingleDownloadResult::Success(_) => (vec![id.to_owned()], vec![]),
+            SingleDownloadResult::Error(e) => {
+                (vec![], vec![(id.to_owned(), e.msg.content.clone())])
+            }
+        };
+        Self {
+            client,
+            ids,
+            failed,
+        }
+    }
+
+    fn batch(client: Client, res: &BatchDownloadResult) -> Self {
+        Self {
+            client,
+            ids: res.ids.clone(),
+            failed: res.failed.clone(),
+        }
+    }
+}
+
+/// The result of a download, along with the id of every item that was sent
+pub struct DownloadResponse {
+    pub ids: Vec<String>,
+    pub result: DownloadClientResult,
+    /// What each client did, so only the ones that failed are retried
+    pub outcomes: Vec<ClientOutcome>,
+}
+
 impl SingleDownloadResult {
     pub fn success<S: Display>(msg: S, id: String) -> Self {
         Self::Success(DownloadSuccessResult {
@@ -129,6 +237,37 @@ pub enum Client {
     #[serde(rename = "RunCommand")]
     #[strum(serialize = "Run Command")]
     Cmd = 5,
+
+    #[serde(rename = "Deluge")]
+    #[strum(serialize = "Deluge")]
+    Deluge = 6,
+
+    #[serde(rename = "aria2")]
+    #[strum(serialize = "aria2")]
+    Aria2 = 7,
+}
+
+impl FromStr for Client {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase();
+        match name.as_str() {
+            "qbittorrent" | "qbit" => Ok(Client::Qbit),
+            "transmission" => Ok(Client::Transmission),
+            "rqbit" => Ok(Client::Rqbit),
+            "defaultapp" => Ok(Client::DefaultApp),
+            "downloadtorrentfile" | "download" => Ok(Client::Download),
+            "runcommand" | "command" | "cmd" => Ok(Client::Cmd),
+            "deluge" => Ok(Client::Deluge),
+            "aria2" | "aria2c" => Ok(Client::Aria2),
+            _ => Err(format!("Unknown download client \"{}\"", s)),
+        }
+    }
 }
 
 #[derive(Default, Clone, Deserialize, Serialize)]
@@ -145,6 +284,143 @@ pub struct ClientConfig {
     pub download: Option<DownloadConfig>,
     #[serde(rename = "rqbit")]
     pub rqbit: Option<RqbitConfig>,
+    #[serde(rename = "deluge")]
+    pub deluge: Option<DelugeConfig>,
+    #[serde(rename = "aria2")]
+    pub aria2: Option<Aria2Config>,
+    /// Send matching downloads to a different client or with different options
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ClientRule>,
+}
+
+/// Settings that can be changed for a single download. Fields are `None` when
+/// the client has no equivalent setting.
+#[derive(Clone, Default, PartialEq)]
+pub struct DownloadOptions {
+    pub save_path: Option<String>,
+    pub category: Option<String>,
+    /// Comma separated
+    pub tags: Option<String>,
+    pub paused: Option<bool>,
+}
+
+impl DownloadOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+    s.filter(|s| !s.trim().is_empty())
+}
+
+fn split_tags(s: Option<String>) -> Option<Vec<String>> {
+    let tags: Vec<String> = s?
+        .split(',')
+        .map(|t| t.trim().to_owned())
+        .filter(|t| !t.is_empty())
+        .collect();
+    Some(tags).filter(|t| !t.is_empty())
+}
+
+/// Leave paused unset unless it was toggled, so the client's own default still applies
+fn toggled(old: Option<bool>, new: Option<bool>) -> Option<bool> {
+    match new == Some(old.unwrap_or_default()) {
+        true => old,
+        false => new,
+    }
+}
+
+impl ClientConfig {
+    /// The current settings for a client that can be changed for a single download
+    pub fn download_options(&self, client: Client) -> DownloadOptions {
+        match client {
+            Client::Qbit => {
+                let c = self.qbit.clone().unwrap_or_default();
+                DownloadOptions {
+                    save_path: Some(c.savepath.unwrap_or_default()),
+                    category: Some(c.category.unwrap_or_default()),
+                    tags: Some(c.tags.unwrap_or_default().join(",")),
+                    paused: Some(c.paused.unwrap_or_default()),
+                }
+            }
+            Client::Transmission => {
+                let c = self.transmission.clone().unwrap_or_default();
+                DownloadOptions {
+                    save_path: Some(c.download_dir.unwrap_or_default()),
+                    tags: Some(c.labels.unwrap_or_default().join(",")),
+                    paused: Some(c.paused.unwrap_or_default()),
+                    ..Default::default()
+                }
+            }
+            Client::Deluge => {
+                let c = self.deluge.clone().unwrap_or_default();
+                DownloadOptions {
+                    save_path: Some(c.download_location.unwrap_or_default()),
+                    category: Some(c.label.unwrap_or_default()),
+                    paused: Some(c.paused.unwrap_or_default()),
+                    ..Default::default()
+                }
+            }
+            Client::Aria2 => {
+                let c = self.aria2.clone().unwrap_or_default();
+                DownloadOptions {
+                    save_path: Some(c.dir.unwrap_or_default()),
+                    paused: Some(c.paused.unwrap_or_default()),
+                    ..Default::default()
+                }
+            }
+            Client::Rqbit => {
+                let c = self.rqbit.clone().unwrap_or_default();
+                DownloadOptions {
+                    save_path: Some(c.output_folder.unwrap_or_default()),
+                    ..Default::default()
+                }
+            }
+            Client::DefaultApp | Client::Download | Client::Cmd => DownloadOptions::default(),
+        }
+    }
+
+    /// A copy of this config with the options for a single download applied
+    pub fn with_download_options(mut self, client: Client, opts: DownloadOptions) -> Self {
+        match client {
+            Client::Qbit => {
+                let mut c = self.qbit.unwrap_or_default();
+                c.savepath = non_empty(opts.save_path);
+                c.category = non_empty(opts.category);
+                c.tags = split_tags(opts.tags);
+                c.paused = toggled(c.paused, opts.paused);
+                self.qbit = Some(c);
+            }
+            Client::Transmission => {
+                let mut c = self.transmission.unwrap_or_default();
+                c.download_dir = non_empty(opts.save_path);
+                c.labels = split_tags(opts.tags);
+                c.paused = toggled(c.paused, opts.paused);
+                self.transmission = Some(c);
+            }
+            Client::Deluge => {
+                let mut c = self.deluge.unwrap_or_default();
+                c.download_location = non_empty(opts.save_path);
+                c.label = non_empty(opts.category);
+                c.paused = toggled(c.paused, opts.paused);
+                self.deluge = Some(c);
+            }
+            Client::Aria2 => {
+                let mut c = self.aria2.unwrap_or_default();
+                c.dir = non_empty(opts.save_path);
+                c.paused = toggled(c.paused, opts.paused);
+                self.aria2 = Some(c);
+            }
+            Client::Rqbit => {
+                let mut c = self.rqbit.unwrap_or_default();
+                c.output_folder = non_empty(opts.save_path);
+                self.rqbit = Some(c);
+            }
+            Client::DefaultApp | Client::Download | Client::Cmd => {}
+        }
+        self
+    }
 }
 
 pub async fn multidownload<C: DownloadClient, F>(
@@ -158,16 +434,27 @@ where
 {
     let mut set = JoinSet::new();
     for item in items.iter() {
-        let item = item.to_owned();
-        set.spawn(C::download(item.clone(), conf.clone(), client.clone()));
+        let (item, conf, client) = (item.to_owned(), conf.clone(), client.clone());
+        set.spawn(async move { (item.id.clone(), C::download(item, conf, client).await) });
     }
 
     let mut success_ids: Vec<String> = vec![];
     let mut errors: Vec<Notification> = vec![];
+    let mut failed: Vec<(String, String)> = vec![];
     while let Some(res) = set.join_next().await {
-        match res.unwrap_or_else(SingleDownloadResult::error) {
-            SingleDownloadResult::Success(sr) => success_ids.push(sr.id),
-            SingleDownloadResult::Error(er) => errors.push(er.msg),
+        match res {
+            Ok((_, SingleDownloadResult::Success(sr))) => {
+                // Keep warnings about items that were still sent, like a missing label
+                if sr.msg.notif_type == NotificationType::Warning {
+                    errors.push(sr.msg);
+                }
+                success_ids.push(sr.id)
+            }
+            Ok((id, SingleDownloadResult::Error(er))) => {
+                failed.push((id, er.msg.content.clone()));
+                errors.push(er.msg);
+            }
+            Err(e) => errors.push(Notification::error(e)),
         }
     }
 
@@ -175,10 +462,251 @@ where
         msg: Notification::success(success_msg(success_ids.len())),
         errors,
         ids: success_ids,
+        failed,
+    }
+}
+
+/// One or more clients that every download is sent to
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DownloadClients {
+    One(Client),
+    Many(Vec<Client>),
+}
+
+impl Default for DownloadClients {
+    fn default() -> Self {
+        Self::One(Client::Cmd)
+    }
+}
+
+impl From<Client> for DownloadClients {
+    fn from(value: Client) -> Self {
+        Self::One(value)
+    }
+}
+
+impl From<Vec<Client>> for DownloadClients {
+    fn from(mut value: Vec<Client>) -> Self {
+        match value.len() {
+            1 => Self::One(value.remove(0)),
+            _ => Self::Many(value),
+        }
+    }
+}
+
+impl DownloadClients {
+    /// The client shown as selected, and the one whose options can be changed
+    pub fn primary(&self) -> Client {
+        self.to_vec()[0]
+    }
+
+    pub fn to_vec(&self) -> Vec<Client> {
+        let mut clients = match self {
+            Self::One(c) => vec![*c],
+            Self::Many(c) => c.clone(),
+        };
+        let mut seen = vec![];
+        clients.retain(|c| {
+            let new = !seen.contains(c);
+            seen.push(*c);
+            new
+        });
+        if clients.is_empty() {
+            clients.push(Client::Cmd);
+        }
+        clients
+    }
+}
+
+/// List clients for notifications, like "qBittorrent, Download Torrent File"
+pub fn client_names(clients: &[Client]) -> String {
+    clients
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Run a download for every client at once, keeping the results in the same order.
+/// A client task that panicked is reported as an error for that client.
+async fn fan_out<T, F, Fut>(clients: &[Client], f: F) -> Vec<(Client, Result<T, String>)>
+where
+    T: Send + 'static,
+    F: Fn(Client) -> Fut,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+{
+    let handles = clients
+        .iter()
+        .map(|c| tokio::spawn(f(*c)))
+        .collect::<Vec<JoinHandle<T>>>();
+    let mut results = vec![];
+    for (c, handle) in clients.iter().zip(handles) {
+        let res = handle.await.map_err(|e| format!("Download failed: {}", e));
+        results.push((*c, res));
     }
+    results
+}
+
+/// Send an item to several clients, reporting them in a single notification
+pub async fn download_all(
+    clients: Vec<Client>,
+    item: Item,
+    conf: ClientConfig,
+    client: reqwest::Client,
+) -> SingleDownloadResult {
+    download_each(clients, item, conf, client).await.0
+}
+
+/// Like [`download_all`], but also returns what each client did
+pub async fn download_each(
+    clients: Vec<Client>,
+    item: Item,
+    conf: ClientConfig,
+    client: reqwest::Client,
+) -> (SingleDownloadResult, Vec<ClientOutcome>) {
+    let id = item.id.clone();
+    if let [c] = clients[..] {
+        let res = c.download(item, conf, client).await;
+        let outcome = ClientOutcome::single(c, &id, &res);
+        return (res, vec![outcome]);
+    }
+    let results = fan_out(&clients, |c| {
+        c.download(item.clone(), conf.clone(), client.clone())
+    })
+    .await
+    .into_iter()
+    .map(|(c, res)| (c, res.unwrap_or_else(SingleDownloadResult::error)));
+
+    let (mut sent, mut failed, mut outcomes) = (vec![], vec![], vec![]);
+    for (c, res) in results {
+        outcomes.push(ClientOutcome::single(c, &id, &res));
+        match res {
+            SingleDownloadResult::Success(_) => sent.push(c),
+            SingleDownloadResult::Error(e) => failed.push(format!("{}: {}", c, e.msg.content)),
+        }
+    }
+    let res = match (sent.is_empty(), failed.is_empty()) {
+        (_, true) => SingleDownloadResult::success(
+            format!("Successfully sent torrent to {}", client_names(&sent)),
+            id,
+        ),
+        (true, false) => SingleDownloadResult::error(format!(
+            "Failed to send torrent to any client:\n{}",
+            failed.join("\n")
+        )),
+        (false, false) => SingleDownloadResult::Success(DownloadSuccessResult {
+            msg: Notification::warning(format!(
+                "Sent torrent to {}, but failed with:\n{}",
+                client_names(&sent),
+                failed.join("\n")
+            )),
+            id,
+        }),
+    };
+    (res, outcomes)
+}
+
+/// Send a batch to several clients. Items are counted as downloaded if any client took them.
+pub async fn batch_download_all(
+    clients: Vec<Client>,
+    items: Vec<Item>,
+    conf: ClientConfig,
+    client: reqwest::Client,
+) -> BatchDownloadResult {
+    batch_download_each(clients, items, conf, client).await.0
+}
+
+/// Like [`batch_download_all`], but also returns what each client did
+pub async fn batch_download_each(
+    clients: Vec<Client>,
+    items: Vec<Item>,
+    conf: ClientConfig,
+    client: reqwest::Client,
+) -> (BatchDownloadResult, Vec<ClientOutcome>) {
+    if let [c] = clients[..] {
+        let res = c.batch_download(items, conf, client).await;
+        let outcome = ClientOutcome::batch(c, &res);
+        return (res, vec![outcome]);
+    }
+    let num_items = items.len();
+    let results = fan_out(&clients, |c| {
+        let (items, conf, client) = (items.clone(), conf.clone(), client.clone());
+        async move { c.batch_download(items, conf, client).await }
+    })
+    .await
+    .into_iter()
+    .map(|(c, res)| {
+        let res = res.unwrap_or_else(|e| BatchDownloadResult {
+            msg: Notification::error(format!("{}: {}", c, e)),
+            errors: vec![],
+            ids: vec![],
+            failed: items.iter().map(|i| (i.id.clone(), e.clone())).collect(),
+        });
+        (c, res)
+    })
+    .collect::<Vec<(Client, BatchDownloadResult)>>();
+
+    let (mut ids, mut errors, mut sent) = (vec![], vec![], vec![]);
+    let outcomes = results
+        .iter()
+        .map(|(c, res)| ClientOutcome::batch(*c, res))
+        .collect::<Vec<ClientOutcome>>();
+    for (c, res) in results {
+        match (res.ids.is_empty(), res.msg.notif_type) {
+            (false, _) => sent.push(c),
+            // A client that had nothing to send isn't a failure
+            (true, NotificationType::Success) => {}
+            (true, _) => errors.push(res.msg),
+        }
+        errors.extend(res.errors);
+        for id in res.ids {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    // Items no client took, with what went wrong on each client
+    let mut failed: Vec<(String, String)> = vec![];
+    for o in outcomes.iter() {
+        for (id, e) in o.failed.iter().filter(|(id, _)| !ids.contains(id)) {
+            let error = format!("{}: {}", o.client, e);
+            match failed.iter_mut().find(|(i, _)| i == id) {
+                Some((_, errors)) => *errors = format!("{}\n{}", errors, error),
+                None => failed.push((id.clone(), error)),
+            }
+        }
+    }
+    let names = client_names(&sent);
+    let res = BatchDownloadResult {
+        msg: match errors.is_empty() {
+            true => Notification::success(format!(
+                "Successfully sent {} torrents to {}",
+                num_items, names
+            )),
+            false => Notification::warning(format!(
+                "Sent {} of {} torrents to {}",
+                ids.len(),
+                num_items,
+                names
+            )),
+        },
+        errors,
+        ids,
+        failed,
+    };
+    (res, outcomes)
 }
 
 impl Client {
+    /// Clients running as a separate service, which may be unreachable for a while
+    pub fn is_remote(self) -> bool {
+        matches!(
+            self,
+            Self::Qbit | Self::Transmission | Self::Rqbit | Self::Deluge | Self::Aria2
+        )
+    }
+
     pub async fn download(
         self,
         item: Item,
@@ -186,8 +714,10 @@ impl Client {
         client: reqwest::Client,
     ) -> SingleDownloadResult {
         match self {
+            Self::Aria2 => Aria2Client::download(item, conf, client).await,
             Self::Cmd => CmdClient::download(item, conf, client).await,
             Self::DefaultApp => DefaultAppClient::download(item, conf, client).await,
+            Self::Deluge => DelugeClient::download(item, conf, client).await,
             Self::Download => DownloadFileClient::download(item, conf, client).await,
             Self::Qbit => QbitClient::download(item, conf, client).await,
             Self::Rqbit => RqbitClient::download(item, conf, client).await,
@@ -202,8 +732,10 @@ impl Client {
         client: reqwest::Client,
     ) -> BatchDownloadResult {
         match self {
+            Self::Aria2 => Aria2Client::batch_download(items, conf, client).await,
             Self::Cmd => CmdClient::batch_download(items, conf, client).await,
             Self::DefaultApp => DefaultAppClient::batch_download(items, conf, client).await,
+            Self::Deluge => DelugeClient::batch_download(items, conf, client).await,
             Self::Download => DownloadFileClient::batch_download(items, conf, client).await,
             Self::Qbit => QbitClient::batch_download(items, conf, client).await,
             Self::Rqbit => RqbitClient::batch_download(items, conf, client).await,
@@ -213,8 +745,10 @@ impl Client {
 
     pub fn load_config(self, cfg: &mut ClientConfig) {
         match self {
+            Self::Aria2 => Aria2Client::load_config(cfg),
             Self::Cmd => CmdClient::load_config(cfg),
             Self::DefaultApp => DefaultAppClient::load_config(cfg),
+            Self::Deluge => DelugeClient::load_config(cfg),
             Self::Download => DownloadFileClient::load_config(cfg),
             Self::Rqbit => RqbitClient::load_config(cfg),
             Self::Qbit => QbitClient::load_config(cfg),
@@ -222,6 +756,39 @@ impl Client {
         };
     }
 
+    pub async fn status(
+        self,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Self::Qbit => QbitClient::status(conf, client).await,
+            Self::Rqbit => RqbitClient::status(conf, client).await,
+            Self::Transmission => TransmissionClient::status(conf, client).await,
+            _ => Err(format!("{} does not support listing torrents", self).into()),
+        }
+    }
+
+    pub async fn action(
+        self,
+        action: TorrentAction,
+        hashes: Vec<String>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let num = hashes.len();
+        match self {
+            Self::Qbit => QbitClient::action(action, hashes, conf, client).await,
+            Self::Rqbit => RqbitClient::action(action, hashes, conf, client).await,
+            Self::Transmission => TransmissionClient::action(action, hashes, conf, client).await,
+            _ => return Err(format!("{} does not support managing torrents", self).into()),
+        }?;
+        Ok(match num {
+            1 => format!("{} torrent in {}", action.done(), self),
+            n => format!("{} {} torrents in {}", action.done(), n, self),
+        })
+    }
+
     pub fn get_link(
         use_magnet: bool,
         yank_full_magnet: Option<bool>,
@@ -291,4 +858,22 @@ mod tests {
             torrent_link
         );
     }
+
+    #[tokio::test]
+    async fn test_fan_out_panic() {
+        let clients = [Client::Qbit, Client::Transmission, Client::Rqbit];
+        let results = fan_out(&clients, |c| async move {
+            if c == Client::Transmission {
+                panic!("client crashed");
+            }
+            c.to_string()
+        })
+        .await;
+
+        let clients = results.iter().map(|(c, _)| *c).collect::<Vec<Client>>();
+        assert!(clients == [Client::Qbit, Client::Transmission, Client::Rqbit]);
+        assert_eq!(results[0].1, Ok("qBittorrent".to_owned()));
+        assert!(results[1].1.as_ref().is_err_and(|e| e.contains("panicked")));
+        assert_eq!(results[2].1, Ok("rqbit".to_owned()));
+    }
 }
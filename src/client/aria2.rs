@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, error::Error, fs};
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{source::Item, util::conv::add_protocol};
+
+use super::{
+    download::fetch_torrent, multidownload, BatchDownloadResult, ClientConfig, DownloadClient,
+    SingleDownloadResult,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Aria2Config {
+    pub base_url: String,
+    pub secret: Option<String>,
+    pub secret_file: Option<String>,
+    pub use_magnet: Option<bool>,
+    pub dir: Option<String>,
+    pub paused: Option<bool>,
+    /// Any other input options for the download, e.g. `seed-ratio`
+    pub options: Option<BTreeMap<String, String>>,
+    pub yank_full_magnet: Option<bool>,
+}
+
+pub struct Aria2Client;
+
+impl Default for Aria2Config {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:6800/jsonrpc".to_owned(),
+            secret: None,
+            secret_file: None,
+            use_magnet: None,
+            dir: None,
+            paused: None,
+            options: None,
+            yank_full_magnet: None,
+        }
+    }
+}
+
+impl Aria2Config {
+    fn options(&self) -> BTreeMap<String, String> {
+        let mut options = self.options.clone().unwrap_or_default();
+        if let Some(dir) = self.dir.as_ref() {
+            options.insert("dir".to_owned(), shellexpand::tilde(dir).to_string());
+        }
+        if let Some(paused) = self.paused {
+            options.insert("pause".to_owned(), paused.to_string());
+        }
+        options
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+async fn call(
+    conf: &Aria2Config,
+    client: &reqwest::Client,
+    method: &str,
+    mut params: Vec<Value>,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let secret = match conf.secret.as_ref() {
+        Some(secret) => Some(secret.to_owned()),
+        None => match conf.secret_file.as_ref() {
+            Some(file) => {
+                let contents = fs::read_to_string(file)?;
+                Some(shellexpand::full(contents.trim())?.to_string())
+            }
+            None => None,
+        },
+    };
+    if let Some(secret) = secret {
+        params.insert(0, json!(format!("token:{}", secret)));
+    }
+
+    let url = add_protocol(conf.base_url.clone(), false)?;
+    let body = json!({ "jsonrpc": "2.0", "id": "nyaa", "method": method, "params": params });
+    // aria2 responds with an error status for failed calls, so check the body first
+    let res = client.post(url).json(&body).send().await?;
+    let status = res.status();
+    match res.json::<RpcResponse>().await {
+        Ok(RpcResponse { error: Some(e), .. }) => Err(e.message.into()),
+        Ok(RpcResponse {
+            result: Some(r), ..
+        }) => Ok(r),
+        _ => Err(format!("aria2 returned status code {}", status.as_u16()).into()),
+    }
+}
+
+async fn add_torrent(
+    conf: &Aria2Config,
+    item: &Item,
+    client: &reqwest::Client,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let link = super::Client::get_link(
+        conf.use_magnet.unwrap_or(true),
+        conf.yank_full_magnet,
+        item.torrent_link.clone(),
+        item.magnet_link.clone(),
+    );
+    let options = json!(conf.options());
+    match link.starts_with("magnet:") {
+        true => call(conf, client, "aria2.addUri", vec![json!([link]), options]).await,
+        false => {
+            let content = fetch_torrent(&link, client).await?;
+            let torrent = base64::engine::general_purpose::STANDARD.encode(content);
+            let params = vec![json!(torrent), json!([]), options];
+            call(conf, client, "aria2.addTorrent", params).await
+        }
+    }
+}
+
+impl DownloadClient for Aria2Client {
+    async fn download(
+        item: Item,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> SingleDownloadResult {
+        let Some(conf) = conf.aria2.clone() else {
+            return SingleDownloadResult::error("Failed to get configuration for aria2");
+        };
+        match add_torrent(&conf, &item, &client).await {
+            Ok(gid) => SingleDownloadResult::success(
+                format!(
+                    "Successfully sent torrent to aria2 (gid {})",
+                    gid.as_str().unwrap_or("?")
+                ),
+                item.id,
+            ),
+            Err(e) => SingleDownloadResult::error(format!("Failed to add torrent:\n{}", e)),
+        }
+    }
+
+    async fn batch_download(
+        items: Vec<Item>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> BatchDownloadResult {
+        multidownload::<Aria2Client, _>(
+            |s| format!("Successfully sent {} torrents to aria2", s),
+            &items,
+            &conf,
+            &client,
+        )
+        .await
+    }
+
+    fn load_config(cfg: &mut ClientConfig) {
+        if cfg.aria2.is_none() {
+            cfg.aria2 = Some(Aria2Config::default());
+        }
+    }
+}
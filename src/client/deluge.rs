@@ -0,0 +1,265 @@
+use std::{error::Error, fs};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    source::Item,
+    util::conv::{add_protocol, get_hash},
+    widget::notifications::Notification,
+};
+
+use super::{
+    multidownload, BatchDownloadResult, ClientConfig, DownloadClient, DownloadSuccessResult,
+    SingleDownloadResult,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DelugeConfig {
+    pub base_url: String,
+    pub password: Option<String>,
+    pub password_file: Option<String>,
+    pub use_magnet: Option<bool>,
+    pub download_location: Option<String>,
+    pub move_completed_path: Option<String>,
+    pub label: Option<String>, // Requires the Label plugin
+    pub paused: Option<bool>,
+    pub yank_full_magnet: Option<bool>,
+}
+
+pub struct DelugeClient;
+
+impl Default for DelugeConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8112".to_owned(),
+            password: None,
+            password_file: None,
+            use_magnet: None,
+            download_location: None,
+            move_completed_path: None,
+            label: None,
+            paused: None,
+            yank_full_magnet: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct DelugeOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    move_completed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    move_completed_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    add_paused: Option<bool>,
+}
+
+impl DelugeConfig {
+    fn options(&self) -> DelugeOptions {
+        let expand = |p: &String| shellexpand::tilde(p).to_string();
+        DelugeOptions {
+            download_location: self.download_location.as_ref().map(expand),
+            move_completed: self.move_completed_path.as_ref().map(|_| true),
+            move_completed_path: self.move_completed_path.as_ref().map(expand),
+            add_paused: self.paused,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Value,
+    error: Option<RpcError>,
+}
+
+/// The api lives under `base_url`, which may have a path when behind a reverse proxy
+fn rpc_url(base_url: &str) -> Result<Url, Box<dyn Error + Send + Sync>> {
+    let mut url = add_protocol(base_url, false)?;
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url.join("json")?)
+}
+
+/// Call a method of the Web UI's JSON-RPC api. The session cookie from
+/// `auth.login` is kept by the client's cookie jar.
+async fn call(
+    conf: &DelugeConfig,
+    client: &reqwest::Client,
+    method: &str,
+    params: Value,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let url = rpc_url(&conf.base_url)?;
+    let body = json!({ "method": method, "params": params, "id": 1 });
+    let res = client.post(url).json(&body).send().await?;
+    if !res.status().is_success() {
+        return Err(format!("Deluge returned status code {}", res.status().as_u16()).into());
+    }
+    let res: RpcResponse = res.json().await?;
+    match res.error {
+        Some(e) => Err(format!("{} failed: {}", method, e.message).into()),
+        None => Ok(res.result),
+    }
+}
+
+async fn login(
+    conf: &DelugeConfig,
+    client: &reqwest::Client,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let pass = match conf.password.as_ref() {
+        Some(pass) => pass.to_owned(),
+        None => match conf.password_file.as_ref() {
+            Some(file) => {
+                let contents = fs::read_to_string(file)?;
+                shellexpand::full(contents.trim())?.to_string()
+            }
+            // The Web UI's default password
+            None => "deluge".to_owned(),
+        },
+    };
+    if call(conf, client, "auth.login", json!([pass])).await? != json!(true) {
+        return Err("Likely incorrect password".into());
+    }
+
+    // The Web UI may not be connected to a daemon yet, so connect to the first one
+    if call(conf, client, "web.connected", json!([])).await? != json!(true) {
+        let hosts = call(conf, client, "web.get_hosts", json!([])).await?;
+        let host = hosts
+            .get(0)
+            .and_then(|h| h.get(0))
+            .cloned()
+            .ok_or("Deluge Web UI has no daemons to connect to")?;
+        call(conf, client, "web.connect", json!([host])).await?;
+    }
+    Ok(())
+}
+
+async fn set_label(
+    conf: &DelugeConfig,
+    item: &Item,
+    client: &reqwest::Client,
+    label: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let hash = get_hash(item.magnet_link.clone())
+        .ok_or("Unable to set label without the torrent's info hash")?
+        .to_lowercase();
+    // Adding a label that already exists is an error, so ignore it
+    let _ = call(conf, client, "label.add", json!([label])).await;
+    call(conf, client, "label.set_torrent", json!([hash, label])).await?;
+    Ok(())
+}
+
+/// Returns a warning if the torrent was added but its label couldn't be set
+async fn add_torrent(
+    conf: &DelugeConfig,
+    item: &Item,
+    client: &reqwest::Client,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    login(conf, client).await?;
+
+    let link = super::Client::get_link(
+        conf.use_magnet.unwrap_or(true),
+        conf.yank_full_magnet,
+        item.torrent_link.clone(),
+        item.magnet_link.clone(),
+    );
+    let path = match link.starts_with("magnet:") {
+        true => link,
+        // Torrent files are fetched by the Web UI first, then added from its temp path
+        false => call(
+            conf,
+            client,
+            "web.download_torrent_from_url",
+            json!([link, ""]),
+        )
+        .await?
+        .as_str()
+        .ok_or("Deluge did not return a path for the torrent file")?
+        .to_owned(),
+    };
+    let torrent = json!({ "path": path, "options": conf.options() });
+    call(conf, client, "web.add_torrents", json!([[torrent]])).await?;
+
+    // The torrent is already added, so a missing label shouldn't make it be sent again
+    Ok(match conf.label.as_ref() {
+        Some(label) => set_label(conf, item, client, label)
+            .await
+            .err()
+            .map(|e| format!("Failed to set label \"{}\":\n{}", label, e)),
+        None => None,
+    })
+}
+
+impl DownloadClient for DelugeClient {
+    async fn download(
+        item: Item,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> SingleDownloadResult {
+        let Some(conf) = conf.deluge.clone() else {
+            return SingleDownloadResult::error("Failed to get configuration for Deluge");
+        };
+        match add_torrent(&conf, &item, &client).await {
+            Ok(None) => {
+                SingleDownloadResult::success("Successfully sent torrent to Deluge", item.id)
+            }
+            Ok(Some(warning)) => SingleDownloadResult::Success(DownloadSuccessResult {
+                msg: Notification::warning(format!(
+                    "Successfully sent torrent to Deluge\n{}",
+                    warning
+                )),
+                id: item.id,
+            }),
+            Err(e) => SingleDownloadResult::error(format!("Failed to add torrent:\n{}", e)),
+        }
+    }
+
+    async fn batch_download(
+        items: Vec<Item>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> BatchDownloadResult {
+        multidownload::<DelugeClient, _>(
+            |s| format!("Successfully sent {} torrents to Deluge", s),
+            &items,
+            &conf,
+            &client,
+        )
+        .await
+    }
+
+    fn load_config(cfg: &mut ClientConfig) {
+        if cfg.deluge.is_none() {
+            cfg.deluge = Some(DelugeConfig::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_url() {
+        let url = |base: &str| rpc_url(base).unwrap().to_string();
+        assert_eq!(url("localhost:8112"), "http://localhost:8112/json");
+        assert_eq!(
+            url("https://example.org/deluge"),
+            "https://example.org/deluge/json"
+        );
+        assert_eq!(
+            url("https://example.org/deluge/"),
+            "https://example.org/deluge/json"
+        );
+    }
+}
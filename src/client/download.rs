@@ -38,6 +38,20 @@ impl Default for DownloadConfig {
     }
 }
 
+/// Fetch the contents of a .torrent file
+pub async fn fetch_torrent(
+    torrent_link: &str,
+    client: &reqwest::Client,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let response = client.get(torrent_link).send().await?;
+    if response.status() != StatusCode::OK {
+        // Throw error if response code is not OK
+        let code = response.status().as_u16();
+        return Err(format!("{}\nInvalid response code: {}", torrent_link, code).into());
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
 async fn download_torrent(
     torrent_link: String,
     filename: String,
@@ -45,14 +59,8 @@ async fn download_torrent(
     create_root_folder: bool,
     overwrite: bool,
     client: reqwest::Client,
-) -> Result<String, Box<dyn Error>> {
-    let response = client.get(torrent_link.to_owned()).send().await?;
-    if response.status() != StatusCode::OK {
-        // Throw error if response code is not OK
-        let code = response.status().as_u16();
-        return Err(format!("{}\nInvalid response code: {}", torrent_link, code).into());
-    }
-    let content = response.bytes().await?;
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let content = fetch_torrent(&torrent_link, &client).await?;
     let folder = PathBuf::from(shellexpand::full(&save_dir)?.to_string());
     let filepath = folder.join(filename);
     if !overwrite && filepath.exists() {
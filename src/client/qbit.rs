@@ -5,7 +5,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{source::Item, util::conv::add_protocol, widget::notifications::Notification};
 
-use super::{BatchDownloadResult, ClientConfig, DownloadClient, SingleDownloadResult};
+use super::{
+    BatchDownloadResult, ClientConfig, DownloadClient, SingleDownloadResult, TorrentAction,
+    TorrentManager, TorrentStatus,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(default)]
@@ -160,6 +163,98 @@ async fn add_torrent(
     Ok(client.post(url).form(&qbit.to_form(links)).send().await?)
 }
 
+#[derive(Deserialize)]
+struct QbitTorrent {
+    hash: String,
+    name: String,
+    progress: f64,
+    size: usize,
+    dlspeed: u64,
+    upspeed: u64,
+    eta: u64,
+    state: String,
+}
+
+impl From<QbitTorrent> for TorrentStatus {
+    fn from(t: QbitTorrent) -> Self {
+        let state = match t.state.as_str() {
+            "downloading" | "forcedDL" => "Downloading",
+            "uploading" | "forcedUP" => "Seeding",
+            "pausedDL" | "pausedUP" | "stoppedDL" | "stoppedUP" => "Paused",
+            "stalledDL" | "stalledUP" => "Stalled",
+            "checkingDL" | "checkingUP" | "checkingResumeData" => "Checking",
+            "queuedDL" | "queuedUP" => "Queued",
+            "metaDL" | "forcedMetaDL" => "Metadata",
+            "moving" => "Moving",
+            "error" | "missingFiles" => "Error",
+            other => other,
+        };
+        TorrentStatus {
+            hash: t.hash.to_lowercase(),
+            name: t.name,
+            progress: t.progress,
+            size: t.size,
+            dl_speed: t.dlspeed,
+            up_speed: t.upspeed,
+            // qBittorrent uses 100 days to mean infinity
+            eta: Some(t.eta).filter(|e| *e < 8640000),
+            state: state.to_owned(),
+        }
+    }
+}
+
+async fn get_torrents(
+    qbit: &QbitConfig,
+    client: &reqwest::Client,
+) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+    login(qbit, client).await?;
+    let base_url = add_protocol(qbit.base_url.clone(), false)?;
+    let url = base_url.join("/api/v2/torrents/info")?;
+    let res = client.get(url).send().await?;
+    if res.status() != StatusCode::OK {
+        return Err(format!("qBittorrent returned status code {}", res.status().as_u16()).into());
+    }
+    let torrents: Vec<QbitTorrent> = res.json().await?;
+    let _ = logout(qbit, client).await;
+    Ok(torrents.into_iter().map(TorrentStatus::from).collect())
+}
+
+async fn manage_torrents(
+    qbit: &QbitConfig,
+    action: TorrentAction,
+    hashes: Vec<String>,
+    client: &reqwest::Client,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    login(qbit, client).await?;
+    let base_url = add_protocol(qbit.base_url.clone(), false)?;
+    let mut form = HashMap::new();
+    form.insert("hashes", hashes.join("|"));
+    // qBittorrent 5 renamed pause/resume to stop/start
+    let endpoints = match action {
+        TorrentAction::Pause => vec!["stop", "pause"],
+        TorrentAction::Resume => vec!["start", "resume"],
+        TorrentAction::Recheck => vec!["recheck"],
+        TorrentAction::Remove | TorrentAction::RemoveWithData => {
+            let delete = action == TorrentAction::RemoveWithData;
+            form.insert("deleteFiles", delete.to_string());
+            vec!["delete"]
+        }
+    };
+    let mut status = StatusCode::NOT_FOUND;
+    for endpoint in endpoints {
+        let url = base_url.join(&format!("/api/v2/torrents/{}", endpoint))?;
+        status = client.post(url).form(&form).send().await?.status();
+        if status != StatusCode::NOT_FOUND {
+            break;
+        }
+    }
+    let _ = logout(qbit, client).await;
+    match status {
+        StatusCode::OK => Ok(()),
+        s => Err(format!("qBittorrent returned status code {}", s.as_u16()).into()),
+    }
+}
+
 async fn download_some(
     items: Vec<Item>,
     conf: ClientConfig,
@@ -230,19 +325,21 @@ impl DownloadClient for QbitClient {
         conf: ClientConfig,
         client: reqwest::Client,
     ) -> BatchDownloadResult {
-        let ids = items.iter().map(|i| i.id.clone()).collect();
+        let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
         let num_items = items.len();
         match download_some(items, conf, client).await {
             Ok(()) => BatchDownloadResult {
                 msg: Notification::success("Successfully sent {} torrents to qBittorrent"),
                 ids,
                 errors: vec![],
+                failed: vec![],
             },
             Err(e) => BatchDownloadResult {
                 msg: Notification::error(format!(
                     "Failed to send {} torrents to qBittorrent",
                     num_items
                 )),
+                failed: ids.into_iter().map(|id| (id, e.clone())).collect(),
                 errors: vec![Notification::error(e)],
                 ids: vec![],
             },
@@ -255,3 +352,23 @@ impl DownloadClient for QbitClient {
         }
     }
 }
+
+impl TorrentManager for QbitClient {
+    async fn status(
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+        let qbit = conf.qbit.ok_or("Failed to get qBittorrent config")?;
+        get_torrents(&qbit, &client).await
+    }
+
+    async fn action(
+        action: TorrentAction,
+        hashes: Vec<String>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let qbit = conf.qbit.ok_or("Failed to get qBittorrent config")?;
+        manage_torrents(&qbit, action, hashes, &client).await
+    }
+}
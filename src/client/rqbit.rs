@@ -2,13 +2,14 @@ use std::error::Error;
 
 use reqwest::{Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use urlencoding::encode;
 
 use crate::{source::Item, util::conv::add_protocol};
 
 use super::{
     multidownload, BatchDownloadResult, ClientConfig, DownloadClient, DownloadError,
-    SingleDownloadResult,
+    SingleDownloadResult, TorrentAction, TorrentManager, TorrentStatus,
 };
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -66,6 +67,98 @@ async fn add_torrent(
     }
 }
 
+#[derive(Deserialize)]
+struct RqbitTorrent {
+    id: usize,
+    info_hash: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RqbitTorrents {
+    torrents: Vec<RqbitTorrent>,
+}
+
+async fn get_json(
+    url: reqwest::Url,
+    client: &reqwest::Client,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let res = client.get(url).send().await?;
+    if res.status() != StatusCode::OK {
+        return Err(format!("rqbit returned status code {}", res.status().as_u16()).into());
+    }
+    Ok(res.json().await?)
+}
+
+async fn get_torrents(
+    conf: &RqbitConfig,
+    client: &reqwest::Client,
+) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+    let base_url = add_protocol(conf.base_url.clone(), false)?;
+    let list: RqbitTorrents =
+        serde_json::from_value(get_json(base_url.join("/torrents")?, client).await?)?;
+
+    let mut torrents = vec![];
+    for t in list.torrents {
+        let url = base_url.join(&format!("/torrents/{}/stats/v1", t.id))?;
+        let stats = get_json(url, client).await?;
+        let num = |v: &Value, path: &str| v.pointer(path).and_then(Value::as_f64).unwrap_or(0.);
+
+        let total = num(&stats, "/total_bytes");
+        let state = match stats.get("state").and_then(Value::as_str) {
+            Some("live") if stats.get("finished") == Some(&Value::Bool(true)) => "Seeding",
+            Some("live") => "Downloading",
+            Some("paused") => "Paused",
+            Some("initializing") => "Checking",
+            Some("error") => "Error",
+            _ => "Unknown",
+        };
+        // Speeds are reported in MiB/s
+        let speed = |path: &str| (num(&stats, path) * 1024. * 1024.) as u64;
+        torrents.push(TorrentStatus {
+            hash: t.info_hash.to_lowercase(),
+            name: t.name.unwrap_or(t.info_hash),
+            progress: match total > 0. {
+                true => num(&stats, "/progress_bytes") / total,
+                false => 0.,
+            },
+            size: total as usize,
+            dl_speed: speed("/live/download_speed/mbps"),
+            up_speed: speed("/live/upload_speed/mbps"),
+            eta: stats
+                .pointer("/live/time_remaining/duration/secs")
+                .and_then(Value::as_u64),
+            state: state.to_owned(),
+        });
+    }
+    Ok(torrents)
+}
+
+async fn manage_torrents(
+    conf: &RqbitConfig,
+    action: TorrentAction,
+    hashes: Vec<String>,
+    client: &reqwest::Client,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let endpoint = match action {
+        TorrentAction::Pause => "pause",
+        TorrentAction::Resume => "start",
+        TorrentAction::Recheck => return Err("rqbit does not support rechecking torrents".into()),
+        TorrentAction::Remove => "forget",
+        TorrentAction::RemoveWithData => "delete",
+    };
+    let base_url = add_protocol(conf.base_url.clone(), false)?;
+    // rqbit accepts an info hash anywhere it takes a torrent id
+    for hash in hashes {
+        let url = base_url.join(&format!("/torrents/{}/{}", hash, endpoint))?;
+        let res = client.post(url).send().await?;
+        if res.status() != StatusCode::OK {
+            return Err(format!("rqbit returned status code {}", res.status().as_u16()).into());
+        }
+    }
+    Ok(())
+}
+
 impl DownloadClient for RqbitClient {
     async fn download(
         item: Item,
@@ -123,3 +216,23 @@ impl DownloadClient for RqbitClient {
         }
     }
 }
+
+impl TorrentManager for RqbitClient {
+    async fn status(
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+        let conf = conf.rqbit.ok_or("Failed to get rqbit config")?;
+        get_torrents(&conf, &client).await
+    }
+
+    async fn action(
+        action: TorrentAction,
+        hashes: Vec<String>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = conf.rqbit.ok_or("Failed to get rqbit config")?;
+        manage_torrents(&conf, action, hashes, &client).await
+    }
+}
@@ -0,0 +1,216 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::source::{Item, SourceConfig, Sources};
+
+use super::{Client, ClientConfig, DownloadOptions};
+
+/// Picks the client and download options for items matching every field that is set
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ClientRule {
+    pub source: Option<Sources>,
+    /// Config name, display name or id of the item's category
+    pub category: Option<String>,
+    /// Regex matched against the title, ignoring case
+    pub title: Option<String>,
+    /// Regex matched against the uploader, ignoring case
+    pub uploader: Option<String>,
+
+    pub client: Option<Client>,
+    pub save_path: Option<String>,
+    /// Category (qBittorrent) or label (Deluge) to add the torrent with
+    pub client_category: Option<String>,
+    /// Tags (qBittorrent) or labels (Transmission) to add the torrent with
+    pub tags: Option<Vec<String>>,
+    pub paused: Option<bool>,
+
+    /// `title` and `uploader`, compiled when the config is loaded
+    #[serde(skip)]
+    title_regex: Option<Regex>,
+    #[serde(skip)]
+    uploader_regex: Option<Regex>,
+}
+
+fn regex(pattern: &Option<String>) -> Result<Option<Regex>, regex::Error> {
+    pattern
+        .as_ref()
+        .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+        .transpose()
+}
+
+impl ClientRule {
+    fn matches(&self, src: Sources, item: &Item, sources: &SourceConfig) -> bool {
+        if self.source.is_some_and(|s| s != src) {
+            return false;
+        }
+        if let Some(cat) = self.category.as_ref() {
+            let Ok(ent) = src.info(sources).find_category(cat) else {
+                return false;
+            };
+            // Nyaa's major categories (`1_0`) cover all of their minor categories
+            let major = matches!(src, Sources::Nyaa | Sources::SukebeiNyaa)
+                && (ent.id == 0 || ent.id % 10 == 0 && ent.id / 10 == item.category / 10);
+            if ent.id != item.category && !major {
+                return false;
+            }
+        }
+        // A pattern that wasn't compiled can't match, rather than matching everything
+        if self.title.is_some() && self.title_regex.is_none()
+            || self.uploader.is_some() && self.uploader_regex.is_none()
+        {
+            return false;
+        }
+        let item_uploader = item.extra.get("uploader");
+        self.title_regex
+            .as_ref()
+            .map_or(true, |r| r.is_match(&item.title))
+            && self
+                .uploader_regex
+                .as_ref()
+                .map_or(true, |r| item_uploader.is_some_and(|u| r.is_match(u)))
+    }
+
+    fn options(&self, mut opts: DownloadOptions) -> DownloadOptions {
+        if let Some(path) = self.save_path.as_ref() {
+            opts.save_path = opts.save_path.map(|_| path.clone());
+        }
+        if let Some(cat) = self.client_category.as_ref() {
+            opts.category = opts.category.map(|_| cat.clone());
+        }
+        if let Some(tags) = self.tags.as_ref() {
+            opts.tags = opts.tags.map(|_| tags.join(","));
+        }
+        if let Some(paused) = self.paused {
+            opts.paused = opts.paused.map(|_| paused);
+        }
+        opts
+    }
+}
+
+impl ClientConfig {
+    /// Compile every rule's regexes, and check that its category exists
+    pub fn compile_rules(&mut self, sources: &SourceConfig) -> Result<(), String> {
+        for (i, rule) in self.rules.iter_mut().enumerate() {
+            let err = |e: &dyn std::fmt::Display| format!("Client rule #{}:\n{}", i + 1, e);
+            rule.title_regex = regex(&rule.title).map_err(|e| err(&e))?;
+            rule.uploader_regex = regex(&rule.uploader).map_err(|e| err(&e))?;
+            if let Some(src) = rule
+                .source
+                .filter(|s| rule.uploader.is_some() && !s.has_uploader())
+            {
+                return Err(err(&format!("{} results don't show the uploader", src)));
+            }
+            if let (Some(cat), Some(src)) = (rule.category.as_ref(), rule.source) {
+                if let Err(e) = src.info(sources).find_category(cat) {
+                    return Err(err(&format!("{} for {}", e, src)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The index of the first rule matching the item
+    pub fn find_rule(&self, src: Sources, item: &Item, sources: &SourceConfig) -> Option<usize> {
+        self.rules
+            .iter()
+            .position(|r| r.matches(src, item, sources))
+    }
+
+    /// The clients and config to download with, after applying a rule
+    pub fn routed(&self, rule: Option<usize>, default: Vec<Client>) -> (Vec<Client>, ClientConfig) {
+        let rule = rule.and_then(|i| self.rules.get(i));
+        let clients = match rule.and_then(|r| r.client) {
+            Some(client) => vec![client],
+            None => default,
+        };
+        let mut config = self.clone();
+        for client in clients.iter() {
+            client.load_config(&mut config);
+            if let Some(rule) = rule {
+                let opts = rule.options(config.download_options(*client));
+                config = config.with_download_options(*client, opts);
+            }
+        }
+        (clients, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routing() {
+        let config = ClientConfig {
+            rules: vec![
+                ClientRule {
+                    source: Some(Sources::Nyaa),
+                    category: Some("AllAnime".to_owned()),
+                    client: Some(Client::Qbit),
+                    client_category: Some("anime".to_owned()),
+                    ..Default::default()
+                },
+                ClientRule {
+                    source: Some(Sources::SukebeiNyaa),
+                    save_path: Some("~/Sukebei".to_owned()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let sources = SourceConfig::default();
+        let anime = Item {
+            category: 12,
+            ..Default::default()
+        };
+        let music = Item {
+            category: 21,
+            ..Default::default()
+        };
+
+        let rule = config.find_rule(Sources::Nyaa, &anime, &sources);
+        assert_eq!(rule, Some(0));
+        let (clients, routed) = config.routed(rule, vec![Client::Transmission]);
+        assert!(clients == [Client::Qbit]);
+        assert_eq!(routed.qbit.unwrap().category.as_deref(), Some("anime"));
+
+        assert_eq!(config.find_rule(Sources::Nyaa, &music, &sources), None);
+
+        let rule = config.find_rule(Sources::SukebeiNyaa, &music, &sources);
+        assert_eq!(rule, Some(1));
+        let (clients, routed) = config.routed(rule, vec![Client::Transmission, Client::Download]);
+        assert!(clients == [Client::Transmission, Client::Download]);
+        let transmission = routed.transmission.unwrap();
+        assert_eq!(transmission.download_dir.as_deref(), Some("~/Sukebei"));
+        assert_eq!(transmission.paused, None);
+    }
+
+    #[test]
+    fn test_compile_rules() {
+        let rule = |title: &str, uploader: Option<&str>| ClientRule {
+            source: Some(Sources::Nyaa),
+            title: Some(title.to_owned()),
+            uploader: uploader.map(str::to_owned),
+            ..Default::default()
+        };
+        let sources = SourceConfig::default();
+        let item = Item {
+            title: "[SubsPlease] Frieren - 01".to_owned(),
+            ..Default::default()
+        };
+
+        let mut config = ClientConfig {
+            rules: vec![rule("(?i)frieren", None)],
+            ..Default::default()
+        };
+        assert_eq!(config.find_rule(Sources::Nyaa, &item, &sources), None);
+        config.compile_rules(&sources).unwrap();
+        assert_eq!(config.find_rule(Sources::Nyaa, &item, &sources), Some(0));
+
+        config.rules = vec![rule("[unclosed", None)];
+        assert!(config.compile_rules(&sources).is_err());
+        config.rules = vec![rule("frieren", Some("SubsPlease"))];
+        assert!(config.compile_rules(&sources).is_err());
+    }
+}
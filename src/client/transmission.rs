@@ -2,7 +2,10 @@ use std::{error::Error, fs};
 
 use serde::{Deserialize, Serialize};
 use transmission_rpc::{
-    types::{BasicAuth, Priority, TorrentAddArgs},
+    types::{
+        BasicAuth, Id, Priority, TorrentAction as TransAction, TorrentAddArgs, TorrentGetField,
+        TorrentStatus as TorrentStatusKind,
+    },
     TransClient,
 };
 
@@ -10,6 +13,7 @@ use crate::{source::Item, util::conv::add_protocol};
 
 use super::{
     multidownload, BatchDownloadResult, ClientConfig, DownloadClient, SingleDownloadResult,
+    TorrentAction, TorrentManager, TorrentStatus,
 };
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -62,11 +66,10 @@ impl TransmissionConfig {
     }
 }
 
-async fn add_torrent(
-    conf: TransmissionConfig,
-    link: String,
+fn trans_client(
+    conf: &TransmissionConfig,
     client: reqwest::Client,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
+) -> Result<TransClient, Box<dyn Error + Send + Sync>> {
     let base_url = add_protocol(conf.base_url.clone(), false)?;
     let mut client = TransClient::new_with_client(base_url, client);
 
@@ -87,6 +90,15 @@ async fn add_torrent(
             password: password.clone(),
         });
     }
+    Ok(client)
+}
+
+async fn add_torrent(
+    conf: TransmissionConfig,
+    link: String,
+    client: reqwest::Client,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut client = trans_client(&conf, client)?;
     let add = conf.form(link);
     client
         .torrent_add(add)
@@ -95,6 +107,77 @@ async fn add_torrent(
     Ok(())
 }
 
+fn state_name(status: TorrentStatusKind) -> &'static str {
+    match status {
+        TorrentStatusKind::Stopped => "Paused",
+        TorrentStatusKind::QueuedToVerify
+        | TorrentStatusKind::QueuedToDownload
+        | TorrentStatusKind::QueuedToSeed => "Queued",
+        TorrentStatusKind::Verifying => "Checking",
+        TorrentStatusKind::Downloading => "Downloading",
+        TorrentStatusKind::Seeding => "Seeding",
+    }
+}
+
+async fn get_torrents(
+    conf: TransmissionConfig,
+    client: reqwest::Client,
+) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+    let mut client = trans_client(&conf, client)?;
+    let fields = vec![
+        TorrentGetField::HashString,
+        TorrentGetField::Name,
+        TorrentGetField::PercentDone,
+        TorrentGetField::RateDownload,
+        TorrentGetField::RateUpload,
+        TorrentGetField::Eta,
+        TorrentGetField::Status,
+        TorrentGetField::TotalSize,
+    ];
+    let res = client
+        .torrent_get(Some(fields), None)
+        .await
+        .map_err(|e| format!("Failed to get torrents:\n{}", e))?;
+    Ok(res
+        .arguments
+        .torrents
+        .into_iter()
+        .map(|t| TorrentStatus {
+            hash: t.hash_string.unwrap_or_default().to_lowercase(),
+            name: t.name.unwrap_or_default(),
+            progress: t.percent_done.unwrap_or_default() as f64,
+            size: t.total_size.unwrap_or_default().max(0) as usize,
+            dl_speed: t.rate_download.unwrap_or_default().max(0) as u64,
+            up_speed: t.rate_upload.unwrap_or_default().max(0) as u64,
+            // Negative values mean unknown or unavailable
+            eta: t.eta.filter(|e| *e >= 0).map(|e| e as u64),
+            state: t.status.map(state_name).unwrap_or("Unknown").to_owned(),
+        })
+        .collect())
+}
+
+async fn manage_torrents(
+    conf: TransmissionConfig,
+    action: TorrentAction,
+    hashes: Vec<String>,
+    client: reqwest::Client,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut client = trans_client(&conf, client)?;
+    let ids = hashes.into_iter().map(Id::Hash).collect();
+    let res = match action {
+        TorrentAction::Pause => client.torrent_action(TransAction::Stop, ids).await,
+        TorrentAction::Resume => client.torrent_action(TransAction::Start, ids).await,
+        TorrentAction::Recheck => client.torrent_action(TransAction::Verify, ids).await,
+        TorrentAction::Remove => client.torrent_remove(ids, false).await,
+        TorrentAction::RemoveWithData => client.torrent_remove(ids, true).await,
+    }
+    .map_err(|e| e.to_string())?;
+    match res.is_ok() {
+        true => Ok(()),
+        false => Err(res.result.into()),
+    }
+}
+
 impl DownloadClient for TransmissionClient {
     async fn download(
         item: Item,
@@ -147,3 +230,27 @@ impl DownloadClient for TransmissionClient {
         }
     }
 }
+
+impl TorrentManager for TransmissionClient {
+    async fn status(
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>> {
+        let conf = conf
+            .transmission
+            .ok_or("Failed to get configuration for transmission")?;
+        get_torrents(conf, client).await
+    }
+
+    async fn action(
+        action: TorrentAction,
+        hashes: Vec<String>,
+        conf: ClientConfig,
+        client: reqwest::Client,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = conf
+            .transmission
+            .ok_or("Failed to get configuration for transmission")?;
+        manage_torrents(conf, action, hashes, client).await
+    }
+}
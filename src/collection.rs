@@ -0,0 +1,340 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::Context,
+    client::download::fetch_torrent,
+    config,
+    source::{Item, ItemType, SourceConfig, Sources},
+};
+
+pub static COLLECTIONS_DIR: &str = "collections";
+
+/// Everything needed to show a batch item again and send it to a download client
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CollectionItem {
+    pub source: Sources,
+    pub id: String,
+    pub title: String,
+    pub date: String,
+    #[serde(default)]
+    pub timestamp: i64,
+    pub size: String,
+    pub bytes: usize,
+    pub seeders: u32,
+    pub leechers: u32,
+    pub downloads: u32,
+    pub category: usize,
+    pub item_type: ItemType,
+    pub magnet_link: String,
+    pub torrent_link: String,
+    pub post_link: String,
+    pub file_name: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, String>,
+}
+
+impl CollectionItem {
+    pub fn new(source: Sources, item: &Item) -> Self {
+        CollectionItem {
+            source: item.source.unwrap_or(source),
+            id: item.id.clone(),
+            title: item.title.clone(),
+            date: item.date.clone(),
+            timestamp: item.timestamp,
+            size: item.size.clone(),
+            bytes: item.bytes,
+            seeders: item.seeders,
+            leechers: item.leechers,
+            downloads: item.downloads,
+            category: item.category,
+            item_type: item.item_type,
+            magnet_link: item.magnet_link.clone(),
+            torrent_link: item.torrent_link.clone(),
+            post_link: item.post_link.clone(),
+            file_name: item.file_name.clone(),
+            extra: item.extra.clone(),
+        }
+    }
+
+    pub fn item(&self, sources: &SourceConfig) -> Item {
+        Item {
+            id: self.id.clone(),
+            date: self.date.clone(),
+            timestamp: self.timestamp,
+            seeders: self.seeders,
+            leechers: self.leechers,
+            downloads: self.downloads,
+            size: self.size.clone(),
+            bytes: self.bytes,
+            title: self.title.clone(),
+            torrent_link: self.torrent_link.clone(),
+            magnet_link: self.magnet_link.clone(),
+            post_link: self.post_link.clone(),
+            file_name: self.file_name.clone(),
+            category: self.category,
+            icon: self.source.info(sources).entry_from_id(self.category).icon,
+            item_type: self.item_type,
+            extra: self.extra.clone(),
+            source: Some(self.source),
+        }
+    }
+}
+
+/// A batch saved to `collections/<name>.toml` in the config folder
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Collection {
+    #[serde(rename = "item")]
+    pub items: Vec<CollectionItem>,
+}
+
+impl Collection {
+    pub fn from_batch(source: Sources, batch: &[Item]) -> Self {
+        Collection {
+            items: batch
+                .iter()
+                .map(|i| CollectionItem::new(source, i))
+                .collect(),
+        }
+    }
+
+    pub fn items(&self, sources: &SourceConfig) -> Vec<Item> {
+        self.items.iter().map(|i| i.item(sources)).collect()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        match path.as_ref().exists() {
+            true => config::load_path(path),
+            false => Err(format!("{:?} does not exist", path.as_ref()).into()),
+        }
+    }
+
+    pub fn store(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        config::store_path(path, self)
+    }
+}
+
+#[derive(Clone)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub len: usize,
+}
+
+/// Every collection in the folder, sorted by name
+pub fn list(dir: impl AsRef<Path>) -> Result<Vec<CollectionInfo>, Box<dyn Error>> {
+    let dir = dir.as_ref();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut collections = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "toml") {
+            if let Some(name) = path.file_stem() {
+                collections.push(CollectionInfo {
+                    name: name.to_string_lossy().to_string(),
+                    len: Collection::load(&path).map(|c| c.items.len()).unwrap_or(0),
+                });
+            }
+        }
+    }
+    collections.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(collections)
+}
+
+fn collection_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_owned());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("\"{}\" is not a valid collection name", name));
+    }
+    Ok(dir.join(format!("{}.toml", name)))
+}
+
+/// Add items that aren't already in the batch, returning how many were added
+pub fn merge(batch: &mut Vec<Item>, items: Vec<Item>) -> usize {
+    let len = batch.len();
+    for item in items {
+        if !batch.iter().any(|b| b.id == item.id) {
+            batch.push(item);
+        }
+    }
+    batch.len() - len
+}
+
+/// One magnet link per line
+pub fn magnet_list(items: &[Item]) -> String {
+    items
+        .iter()
+        .filter(|i| !i.magnet_link.is_empty())
+        .map(|i| format!("{}\n", i.magnet_link))
+        .collect()
+}
+
+/// Fetch the `.torrent` file of every item into a folder
+pub async fn export_torrents(
+    items: Vec<Item>,
+    dir: String,
+    client: reqwest::Client,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let folder = PathBuf::from(shellexpand::full(&dir)?.to_string());
+    fs::create_dir_all(&folder)?;
+    let mut errors = vec![];
+    for item in items.iter() {
+        if item.torrent_link.is_empty() {
+            errors.push(format!("{} has no torrent file", item.title));
+            continue;
+        }
+        let file_name = match item.file_name.is_empty() {
+            true => format!("{}.torrent", item.id),
+            false => item.file_name.clone(),
+        };
+        match fetch_torrent(&item.torrent_link, &client).await {
+            Ok(content) => {
+                if let Err(e) = fs::write(folder.join(file_name), content) {
+                    errors.push(e.to_string());
+                }
+            }
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    let msg = format!(
+        "Exported {}/{} torrents to {}",
+        items.len() - errors.len(),
+        items.len(),
+        folder.to_string_lossy()
+    );
+    match errors.is_empty() {
+        true => Ok(msg),
+        false => Err(format!("{}:\n{}", msg, errors.join("\n")).into()),
+    }
+}
+
+#[derive(Clone)]
+pub enum CollectionOp {
+    Refresh,
+    Save(String),
+    Load(String),
+    Merge(String),
+    Remove(String),
+    /// Write the batch's magnet links to a file
+    ExportMagnets(String),
+    /// Download the batch's `.torrent` files into a folder
+    ExportTorrents(String),
+}
+
+impl CollectionOp {
+    /// Run everything except exporting torrents, which has to be done asynchronously
+    pub fn run(self, ctx: &mut Context, dir: &Path) -> Result<(), Box<dyn Error>> {
+        match self {
+            CollectionOp::Refresh | CollectionOp::ExportTorrents(_) => {}
+            CollectionOp::Save(name) => {
+                Collection::from_batch(ctx.src, &ctx.batch).store(collection_path(dir, &name)?)?;
+                ctx.notify_success(format!(
+                    "Saved {} items to collection \"{}\"",
+                    ctx.batch.len(),
+                    name
+                ));
+            }
+            CollectionOp::Load(name) => {
+                let items = Collection::load(collection_path(dir, &name)?)?;
+                ctx.batch = items.items(&ctx.config.sources);
+                ctx.notify_success(format!(
+                    "Loaded {} items from collection \"{}\"",
+                    ctx.batch.len(),
+                    name
+                ));
+            }
+            CollectionOp::Merge(name) => {
+                let items = Collection::load(collection_path(dir, &name)?)?;
+                let added = merge(&mut ctx.batch, items.items(&ctx.config.sources));
+                ctx.notify_success(format!(
+                    "Added {} items from collection \"{}\"",
+                    added, name
+                ));
+            }
+            CollectionOp::Remove(name) => {
+                fs::remove_file(collection_path(dir, &name)?)?;
+                ctx.notify_info(format!("Removed collection \"{}\"", name));
+            }
+            CollectionOp::ExportMagnets(file) => {
+                let path = PathBuf::from(shellexpand::full(&file)?.to_string());
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let magnets = magnet_list(&ctx.batch);
+                fs::write(&path, &magnets)?;
+                ctx.notify_success(format!(
+                    "Exported {} magnet links to {}",
+                    magnets.lines().count(),
+                    path.to_string_lossy()
+                ));
+            }
+        }
+        ctx.collections = list(dir)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_round_trip() {
+        let item = |id: &str, source: Sources| Item {
+            id: id.to_owned(),
+            title: format!("Title {}", id),
+            magnet_link: format!("magnet:?xt=urn:btih:{}", id),
+            category: 12,
+            source: Some(source),
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir().join(format!("nyaa-collections-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut ctx = Context::default();
+        ctx.batch = vec![item("1", Sources::Nyaa), item("2", Sources::TorrentGalaxy)];
+        // Saved from another source, but each item keeps the one it was found on
+        ctx.src = Sources::SukebeiNyaa;
+        CollectionOp::Save("anime".to_owned())
+            .run(&mut ctx, &dir)
+            .unwrap();
+        assert_eq!(ctx.collections.len(), 1);
+        assert_eq!(ctx.collections[0].name, "anime");
+        assert_eq!(ctx.collections[0].len, 2);
+
+        ctx.batch.clear();
+        CollectionOp::Load("anime".to_owned())
+            .run(&mut ctx, &dir)
+            .unwrap();
+        let items = ctx.batch.clone();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].title, "Title 2");
+        assert_eq!(items[0].category, 12);
+        assert!(items[0].source == Some(Sources::Nyaa));
+        assert!(items[1].source == Some(Sources::TorrentGalaxy));
+
+        CollectionOp::Remove("anime".to_owned())
+            .run(&mut ctx, &dir)
+            .unwrap();
+        assert!(ctx.collections.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut batch = vec![item("2", Sources::Nyaa), item("3", Sources::Nyaa)];
+        assert_eq!(merge(&mut batch, items), 1);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(
+            magnet_list(&batch[..2]),
+            "magnet:?xt=urn:btih:2\nmagnet:?xt=urn:btih:3\n"
+        );
+    }
+}
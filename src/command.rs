@@ -0,0 +1,299 @@
+use strum::VariantArray as _;
+
+use crate::{
+    app::{Context, LoadType, Mode, Widgets},
+    client::Client,
+    keymap::Action,
+    source::{federated::FederatedSort, Sources},
+    widget::{
+        clients::select_client,
+        sort::{SelectedSort, SortDir},
+        sources::select_source,
+    },
+};
+
+/// Everything that can be typed after `:`
+pub static COMMANDS: &[&str] = &[
+    "search", "source", "sort", "filter", "category", "page", "user", "client", "theme", "yank",
+    "quit",
+];
+
+static YANKS: &[(&str, Action)] = &[
+    ("torrent", Action::CopyTorrentLink),
+    ("magnet", Action::CopyMagnetLink),
+    ("post", Action::CopyPostLink),
+    ("imdb", Action::CopyImdbId),
+    ("name", Action::CopyName),
+];
+
+#[derive(Clone)]
+pub enum Command {
+    Search(String),
+    Source(Sources),
+    Sort(usize, SortDir),
+    /// Sorting merged results while searching all sources
+    FederatedSort(FederatedSort, SortDir),
+    Filter(usize),
+    Category(usize),
+    Page(usize),
+    User(Option<String>),
+    Client(Client),
+    Theme(usize),
+    /// Copying needs the clipboard, so it's run by the app
+    Yank(Action),
+    Quit,
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// The command name and its argument
+fn split(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    match input.split_once(' ') {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (input, ""),
+    }
+}
+
+/// Commands can be shortened as long as only one starts with what was typed, like `:sou`
+fn command_name(name: &str) -> Result<&'static str, String> {
+    if let Some(cmd) = COMMANDS.iter().find(|c| **c == name) {
+        return Ok(cmd);
+    }
+    let matches: Vec<&&str> = COMMANDS.iter().filter(|c| c.starts_with(name)).collect();
+    match matches[..] {
+        [cmd] => Ok(cmd),
+        [] => Err(format!("Unknown command \"{}\"", name)),
+        _ => Err(format!("Ambiguous command \"{}\"", name)),
+    }
+}
+
+fn sort_dir(s: &str) -> Option<SortDir> {
+    match s.to_lowercase().as_str() {
+        "asc" => Some(SortDir::Asc),
+        "desc" => Some(SortDir::Desc),
+        _ => None,
+    }
+}
+
+/// The values the argument of a command can take
+fn arguments(cmd: &str, ctx: &Context) -> Vec<String> {
+    match cmd {
+        "source" => Sources::VARIANTS.iter().map(|s| s.to_string()).collect(),
+        "client" => Client::VARIANTS.iter().map(|c| c.to_string()).collect(),
+        "theme" => ctx.themes.keys().cloned().collect(),
+        "sort" if ctx.federated => FederatedSort::VARIANTS
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        "sort" => ctx.src_info.sorts.clone(),
+        "filter" => ctx.src_info.filters.clone(),
+        "category" => ctx
+            .src_info
+            .cats
+            .iter()
+            .flat_map(|cat| cat.entries.iter().map(|ent| ent.cfg.clone()))
+            .collect(),
+        "yank" => YANKS.iter().map(|(name, _)| name.to_string()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Every way the word being typed could be finished, as whole command lines
+pub fn completions(input: &str, ctx: &Context) -> Vec<String> {
+    let (name, arg) = split(input);
+    if !input.trim_start().contains(' ') {
+        return COMMANDS
+            .iter()
+            .filter(|c| c.starts_with(name))
+            .map(|c| c.to_string())
+            .collect();
+    }
+    let Ok(cmd) = command_name(name) else {
+        return vec![];
+    };
+    let mut args = arguments(cmd, ctx);
+    let mut prefix = cmd.to_owned();
+    // The direction comes after the name of the sort
+    if cmd == "sort" {
+        if let Some((sort, dir)) = arg.rsplit_once(' ') {
+            if args.iter().any(|a| normalize(a) == normalize(sort)) {
+                prefix = format!("{} {}", cmd, sort);
+                args = vec!["asc".to_owned(), "desc".to_owned()];
+                return complete(&prefix, args, dir);
+            }
+        }
+    }
+    complete(&prefix, args, arg)
+}
+
+fn complete(prefix: &str, args: Vec<String>, arg: &str) -> Vec<String> {
+    let arg = arg.to_lowercase();
+    args.into_iter()
+        .filter(|a| a.to_lowercase().starts_with(&arg))
+        .map(|a| format!("{} {}", prefix, a))
+        .collect()
+}
+
+impl Command {
+    pub fn parse(input: &str, ctx: &Context) -> Result<Self, String> {
+        let (name, arg) = split(input);
+        let cmd = command_name(name)?;
+        let missing = || format!("Missing argument for \"{}\"", cmd);
+        if arg.is_empty() && !matches!(cmd, "search" | "user" | "quit") {
+            return Err(missing());
+        }
+        Ok(match cmd {
+            "search" => Command::Search(arg.to_owned()),
+            "source" => Command::Source(arg.parse()?),
+            "client" => Command::Client(arg.parse()?),
+            "sort" => {
+                let (sort, dir) = match arg
+                    .rsplit_once(' ')
+                    .and_then(|(s, d)| Some((s, sort_dir(d)?)))
+                {
+                    Some((sort, dir)) => (sort, dir),
+                    None => (arg, SortDir::Desc),
+                };
+                match ctx.federated {
+                    true => FederatedSort::VARIANTS
+                        .iter()
+                        .find(|s| normalize(&s.to_string()) == normalize(sort))
+                        .map(|s| Command::FederatedSort(*s, dir)),
+                    false => ctx.src_info.find_sort(sort).map(|s| Command::Sort(s, dir)),
+                }
+                .ok_or_else(|| format!("Unknown sort \"{}\"", sort))?
+            }
+            "filter" => ctx
+                .src_info
+                .find_filter(arg)
+                .map(Command::Filter)
+                .ok_or_else(|| format!("Unknown filter \"{}\"", arg))?,
+            "category" => ctx
+                .src_info
+                .find_category(arg)
+                .map(|c| Command::Category(c.id))?,
+            "page" => match arg.parse::<usize>() {
+                Ok(page) if page >= 1 && page <= ctx.results.response.last_page.max(1) => {
+                    Command::Page(page)
+                }
+                _ => return Err(format!("\"{}\" is not a valid page", arg)),
+            },
+            "user" => Command::User(Some(arg.to_owned()).filter(|u| !u.is_empty())),
+            "theme" => ctx
+                .themes
+                .keys()
+                .position(|t| normalize(t) == normalize(arg))
+                .map(Command::Theme)
+                .ok_or_else(|| format!("Unknown theme \"{}\"", arg))?,
+            "yank" => YANKS
+                .iter()
+                .find(|(name, _)| name.starts_with(&arg.to_lowercase()))
+                .map(|(_, action)| Command::Yank(*action))
+                .ok_or_else(|| format!("Can't yank \"{}\"", arg))?,
+            _ => Command::Quit,
+        })
+    }
+
+    /// Run everything except `Yank`, which needs the clipboard
+    pub fn run(self, ctx: &mut Context, w: &mut Widgets) {
+        ctx.mode = Mode::Normal;
+        match self {
+            Command::Search(query) => {
+                w.search.input.input = query;
+                w.search
+                    .input
+                    .set_cursor(w.search.input.input.chars().count());
+                ctx.page = 1;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Command::Source(src) => select_source(ctx, src),
+            Command::Sort(sort, dir) => {
+                w.sort.selected = SelectedSort { sort, dir };
+                w.sort.table.select(sort);
+                if let Some(s) = ctx.src_info.sorts.get(sort) {
+                    ctx.notify_info(format!("Sort by \"{}\" {}", s, dir));
+                }
+                ctx.mode = Mode::Loading(LoadType::Sorting);
+            }
+            Command::FederatedSort(sort, dir) => {
+                ctx.config.federated.sort = sort;
+                ctx.config.federated.sort_dir = dir;
+                ctx.format_results();
+                ctx.notify_info(format!("Sorting merged results by {}", sort));
+            }
+            Command::Filter(filter) => {
+                w.filter.selected = filter;
+                w.filter.table.select(filter);
+                if let Some(f) = ctx.src_info.filters.get(filter) {
+                    ctx.notify_info(format!("Filter by \"{}\"", f));
+                }
+                ctx.mode = Mode::Loading(LoadType::Filtering);
+            }
+            Command::Category(id) => {
+                let (major, minor) = ctx.src_info.get_major_minor(id);
+                w.category.selected = id;
+                w.category.table.select(major + minor + 1);
+                w.category.major = major;
+                w.category.minor = minor;
+                ctx.notify_info(format!(
+                    "Category \"{}\"",
+                    ctx.src_info.clone().entry_from_id(id).name
+                ));
+                ctx.mode = Mode::Loading(LoadType::Categorizing);
+            }
+            Command::Page(page) => {
+                ctx.page = page;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Command::User(user) => {
+                ctx.user = user;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Command::Client(client) => {
+                select_client(ctx, client);
+                w.clients.table.select(client as usize);
+            }
+            Command::Theme(idx) => w.theme.select(ctx, idx),
+            Command::Yank(_) => {}
+            Command::Quit => ctx.quit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_name() {
+        assert_eq!(command_name("sort"), Ok("sort"));
+        assert_eq!(
+            command_name("so"),
+            Err("Ambiguous command \"so\"".to_owned())
+        );
+        assert_eq!(command_name("sou"), Ok("source"));
+        assert_eq!(command_name("q"), Ok("quit"));
+        assert!(command_name("explode").is_err());
+        assert_eq!(split("  sort seeders   asc "), ("sort", "seeders   asc"));
+        assert_eq!(split("page"), ("page", ""));
+    }
+
+    #[test]
+    fn test_completions() {
+        let ctx = Context::default();
+        assert_eq!(completions("s", &ctx), ["search", "source", "sort"]);
+        assert_eq!(
+            completions("source t", &ctx),
+            ["source TorrentGalaxy", "source Torznab"]
+        );
+        assert_eq!(completions("cli q", &ctx), ["client qBittorrent"]);
+        assert_eq!(completions("yank m", &ctx), ["yank magnet"]);
+        assert!(completions("explode ", &ctx).is_empty());
+    }
+}
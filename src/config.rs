@@ -8,11 +8,13 @@ use std::{
 
 use crate::{
     app::{Context, Widgets, APP_NAME},
-    client::{Client, ClientConfig},
+    cli::watch::WatchConfig,
+    client::{ClientConfig, DownloadClients},
     clip::ClipboardConfig,
-    source::{SourceConfig, Sources},
+    keymap::{KeyConfig, Keymap},
+    source::{federated::FederatedConfig, SourceConfig, Sources},
     theme::{self, Theme},
-    widget::notifications::NotificationConfig,
+    widget::{notifications::NotificationConfig, saved::SavedSearch},
 };
 use directories::ProjectDirs;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -49,16 +51,23 @@ pub struct Config {
     pub theme: String,
     #[serde(rename = "default_source")]
     pub source: Sources,
-    pub download_client: Client,
+    pub download_client: DownloadClients,
     pub date_format: Option<String>,
     pub relative_date: Option<bool>,
     pub relative_date_short: Option<bool>,
     pub request_proxy: Option<String>,
     pub timeout: u64,
+    /// How long pages loaded by sources are cached for, in seconds
+    pub cache_ttl: u64,
     pub scroll_padding: usize,
+    /// Scrolling past the last result appends the next page instead of stopping
+    pub infinite_scroll: bool,
+    /// Capture the mouse for clicking and scrolling, which stops the terminal from selecting text
+    pub mouse: bool,
     pub cursor_padding: usize,
     pub save_config_on_change: bool,
     pub hot_reload_config: bool,
+    pub search_history_size: usize,
     /// Tell if we yank all available magnet info or just the minimal magnet info when it is `false`:
     /// `magnet:?xt=urn:btih:691526c892951e9b41b7946524513f945e5c7c45&dn=Example.File.Name&tr=http://example.com/tracker/announce` become `magnet:?xt=urn:btih:691526c892951e9b41b7946524513f945e5c7c45` when `false`
     pub yank_full_magnet: bool,
@@ -71,29 +80,45 @@ pub struct Config {
     pub client: ClientConfig,
     #[serde(rename = "source")]
     pub sources: SourceConfig,
+    #[serde(rename = "watch")]
+    pub watch: Option<WatchConfig>,
+    #[serde(rename = "federated")]
+    pub federated: FederatedConfig,
+    #[serde(rename = "saved_search")]
+    pub saved_searches: Vec<SavedSearch>,
+    #[serde(skip_serializing_if = "KeyConfig::is_empty")]
+    pub keys: KeyConfig,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
             source: Sources::Nyaa,
-            download_client: Client::Cmd,
+            download_client: DownloadClients::default(),
             theme: Theme::default().name,
             date_format: None,
             relative_date: None,
             relative_date_short: None,
             request_proxy: None,
             timeout: 30,
+            cache_ttl: 0,
             scroll_padding: 3,
+            infinite_scroll: false,
+            mouse: false,
             cursor_padding: 4,
             save_config_on_change: true,
             hot_reload_config: true,
+            search_history_size: 100,
             yank_full_magnet: true,
 
             notifications: None,
             clipboard: None,
             client: ClientConfig::default(),
             sources: SourceConfig::default(),
+            watch: None,
+            federated: FederatedConfig::default(),
+            saved_searches: vec![],
+            keys: KeyConfig::default(),
         }
     }
 }
@@ -123,11 +148,11 @@ impl Config {
         self.partial_apply(ctx, w)?;
 
         // Set download client
-        ctx.client = ctx.config.download_client;
+        ctx.client = ctx.config.download_client.primary();
         // Set source
         ctx.src = ctx.config.source;
         // Set source info (categories, etc.)
-        ctx.src_info = ctx.src.info();
+        ctx.src_info = ctx.src.info(&ctx.config.sources);
 
         ctx.src.apply(ctx, w);
         if let Some(conf) = ctx.config.notifications {
@@ -152,10 +177,13 @@ impl Config {
 
         // Load download client config
         ctx.client.load_config(&mut ctx.config.client);
+        ctx.config.client.compile_rules(&self.sources)?;
 
         // Load current source config
         ctx.src.load_config(&mut ctx.config.sources);
 
+        ctx.keymap = Keymap::new(&self.keys)?;
+
         Ok(())
     }
 }
@@ -186,7 +214,7 @@ pub fn load_path<T: Serialize + DeserializeOwned + Default>(
     }
 }
 
-fn store_path(path: impl AsRef<Path>, cfg: impl Serialize) -> Result<(), Box<dyn Error>> {
+pub fn store_path(path: impl AsRef<Path>, cfg: impl Serialize) -> Result<(), Box<dyn Error>> {
     let path = path.as_ref();
     let config_dir = path
         .parent()
@@ -0,0 +1,447 @@
+use std::{collections::HashSet, error::Error, path::Path};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{Client, DownloadClients},
+    config,
+    source::{Item, Sources},
+    util::conv::get_hash,
+};
+
+pub static SEARCH_HISTORY_FILE: &str = "search_history.toml";
+pub static DOWNLOAD_HISTORY_FILE: &str = "download_history.toml";
+pub static PENDING_DOWNLOADS_FILE: &str = "pending_downloads.toml";
+
+/// Longest wait between retries of a failed download, in seconds
+static MAX_BACKOFF: i64 = 3600;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SearchEntry {
+    pub source: Sources,
+    pub query: String,
+}
+
+/// Queries entered in the search bar, oldest first
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SearchHistory {
+    #[serde(rename = "search")]
+    pub entries: Vec<SearchEntry>,
+}
+
+impl SearchHistory {
+    /// Add a query to the history, moving it to the front if it was already searched on the
+    /// same source. Returns `false` if nothing changed.
+    pub fn push(&mut self, source: Sources, query: &str, max_len: usize) -> bool {
+        let query = query.trim();
+        if query.is_empty() || max_len == 0 {
+            return false;
+        }
+        let entry = SearchEntry {
+            source,
+            query: query.to_owned(),
+        };
+        if self.entries.last() == Some(&entry) {
+            return false;
+        }
+        self.entries.retain(|e| e != &entry);
+        self.entries.push(entry);
+        if self.entries.len() > max_len {
+            self.entries.drain(..self.entries.len() - max_len);
+        }
+        true
+    }
+
+    /// Queries searched on `source`, newest first
+    pub fn queries(&self, source: Sources) -> Vec<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| e.source == source)
+            .map(|e| e.query.as_str())
+            .collect()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        match path.as_ref().exists() {
+            true => config::load_path(path),
+            false => Ok(Self::default()),
+        }
+    }
+
+    pub fn store(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        config::store_path(path, self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadEntry {
+    pub source: Sources,
+    pub id: String,
+    pub hash: Option<String>,
+    pub title: String,
+    pub size: String,
+    /// Every client the torrent was sent to
+    #[serde(rename = "client")]
+    pub clients: DownloadClients,
+    pub date: String,
+    pub magnet_link: String,
+    pub torrent_link: String,
+    pub post_link: String,
+    pub file_name: String,
+}
+
+impl DownloadEntry {
+    pub fn new(source: Sources, clients: &[Client], item: &Item) -> Self {
+        DownloadEntry {
+            source,
+            id: item.id.clone(),
+            hash: item_hash(item),
+            title: item.title.clone(),
+            size: item.size.clone(),
+            clients: clients.to_vec().into(),
+            date: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            magnet_link: item.magnet_link.clone(),
+            torrent_link: item.torrent_link.clone(),
+            post_link: item.post_link.clone(),
+            file_name: item.file_name.clone(),
+        }
+    }
+
+    /// Rebuild enough of the original item to send it to a download client again
+    pub fn item(&self) -> Item {
+        Item {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            size: self.size.clone(),
+            magnet_link: self.magnet_link.clone(),
+            torrent_link: self.torrent_link.clone(),
+            post_link: self.post_link.clone(),
+            file_name: self.file_name.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn item_hash(item: &Item) -> Option<String> {
+    get_hash(item.magnet_link.clone()).map(|h| h.to_lowercase())
+}
+
+/// Ids are only unique within a source, while info hashes are the same everywhere
+fn id_key(source: Sources, id: &str) -> String {
+    format!("{}:{}", source, id)
+}
+
+/// Successful downloads, oldest first
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DownloadHistory {
+    #[serde(rename = "download")]
+    pub entries: Vec<DownloadEntry>,
+    /// Source ids and info hashes of every entry, for quickly checking if an item was downloaded
+    #[serde(skip)]
+    keys: HashSet<String>,
+}
+
+impl DownloadHistory {
+    pub fn push(&mut self, entry: DownloadEntry) {
+        self.keys.insert(id_key(entry.source, &entry.id));
+        self.keys.extend(entry.hash.clone());
+        self.entries.push(entry);
+    }
+
+    pub fn contains(&self, source: Sources, item: &Item) -> bool {
+        self.keys.contains(&id_key(source, &item.id))
+            || item_hash(item).is_some_and(|h| self.keys.contains(&h))
+    }
+
+    /// Whether a torrent in a download client was sent from nyaa
+    pub fn contains_hash(&self, hash: &str) -> bool {
+        self.keys.contains(&hash.to_lowercase())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let mut history: Self = match path.as_ref().exists() {
+            true => config::load_path(path)?,
+            false => Self::default(),
+        };
+        history.keys = history
+            .entries
+            .iter()
+            .flat_map(|e| [Some(id_key(e.source, &e.id)), e.hash.clone()])
+            .flatten()
+            .collect();
+        Ok(history)
+    }
+
+    pub fn store(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        config::store_path(path, self)
+    }
+
+    /// Add entries to the history file without keeping it loaded
+    pub fn append(
+        path: impl AsRef<Path>,
+        entries: impl IntoIterator<Item = DownloadEntry>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut history = Self::load(&path)?;
+        entries.into_iter().for_each(|e| history.push(e));
+        history.store(path)
+    }
+}
+
+/// A download that failed because its client couldn't be reached, waiting to be retried
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingDownload {
+    #[serde(flatten)]
+    pub entry: DownloadEntry,
+    /// The clients that haven't taken the torrent yet
+    pub clients: Vec<Client>,
+    #[serde(default)]
+    pub category: usize,
+    pub attempts: u32,
+    /// Unix timestamp of the next attempt
+    pub next_retry: i64,
+    /// Why each client failed on the last attempt, one per line
+    pub error: String,
+}
+
+impl PendingDownload {
+    pub fn item(&self) -> Item {
+        Item {
+            category: self.category,
+            ..self.entry.item()
+        }
+    }
+}
+
+/// Seconds to wait after a number of failed attempts, doubling each time
+pub fn backoff(attempts: u32) -> i64 {
+    (30i64 << attempts.saturating_sub(1).min(16)).min(MAX_BACKOFF)
+}
+
+/// Failed downloads, oldest first
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PendingDownloads {
+    #[serde(rename = "pending")]
+    pub entries: Vec<PendingDownload>,
+}
+
+impl PendingDownloads {
+    /// Queue a download for the clients that failed on it, or push back its next retry if it's
+    /// already queued
+    pub fn fail(&mut self, source: Sources, failed: Vec<(Client, String)>, item: &Item) {
+        let now = Local::now().timestamp();
+        let error = failed
+            .iter()
+            .map(|(c, e)| format!("{}: {}", c, e))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let mut clients = failed.into_iter().map(|(c, _)| c).collect::<Vec<Client>>();
+        match self
+            .entries
+            .iter_mut()
+            .find(|p| p.entry.source == source && p.entry.id == item.id)
+        {
+            Some(pending) => {
+                clients.retain(|c| !pending.clients.contains(c));
+                pending.clients.extend(clients);
+                pending.attempts += 1;
+                pending.next_retry = now + backoff(pending.attempts);
+                pending.error = error;
+            }
+            None => self.entries.push(PendingDownload {
+                entry: DownloadEntry::new(source, &clients, item),
+                clients,
+                category: item.category,
+                attempts: 1,
+                next_retry: now + backoff(1),
+                error,
+            }),
+        }
+    }
+
+    pub fn contains(&self, source: Sources, id: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|p| p.entry.source == source && p.entry.id == id)
+    }
+
+    /// Stop retrying a download on clients that took it, dropping it once none are left.
+    /// Returns `true` if anything changed.
+    pub fn sent(&mut self, source: Sources, id: &str, clients: &[Client]) -> bool {
+        let Some(pending) = self
+            .entries
+            .iter_mut()
+            .find(|p| p.entry.source == source && p.entry.id == id)
+        else {
+            return false;
+        };
+        let len = pending.clients.len();
+        pending.clients.retain(|c| !clients.contains(c));
+        if pending.clients.is_empty() {
+            return self.remove(source, id);
+        }
+        pending.clients.len() != len
+    }
+
+    /// Returns `true` if the download was queued
+    pub fn remove(&mut self, source: Sources, id: &str) -> bool {
+        let len = self.entries.len();
+        self.entries
+            .retain(|p| !(p.entry.source == source && p.entry.id == id));
+        self.entries.len() != len
+    }
+
+    /// Downloads whose next retry has come. They stay queued until they succeed.
+    pub fn due(&mut self) -> Vec<PendingDownload> {
+        let now = Local::now().timestamp();
+        let due = self
+            .entries
+            .iter_mut()
+            .filter(|p| p.next_retry <= now)
+            .map(|p| {
+                // Don't try again while this attempt is still running
+                p.next_retry = now + backoff(p.attempts + 1);
+                p.clone()
+            })
+            .collect();
+        due
+    }
+
+    /// Retry everything sent to a client on the next check, like once it's reachable again
+    pub fn retry_now(&mut self, client: Option<Client>) {
+        self.entries
+            .iter_mut()
+            .filter(|p| client.map_or(true, |c| p.clients.contains(&c)))
+            .for_each(|p| p.next_retry = 0);
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        match path.as_ref().exists() {
+            true => config::load_path(path),
+            false => Ok(Self::default()),
+        }
+    }
+
+    pub fn store(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        config::store_path(path, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_history_push() {
+        let mut history = SearchHistory::default();
+        assert!(history.push(Sources::Nyaa, "one", 3));
+        assert!(history.push(Sources::Nyaa, "two", 3));
+        assert!(history.push(Sources::TorrentGalaxy, "one", 3));
+        assert!(!history.push(Sources::TorrentGalaxy, "one ", 3));
+        assert!(!history.push(Sources::Nyaa, "  ", 3));
+        assert_eq!(history.queries(Sources::Nyaa), vec!["two", "one"]);
+
+        assert!(history.push(Sources::Nyaa, "one", 3));
+        assert_eq!(history.queries(Sources::Nyaa), vec!["one", "two"]);
+        assert_eq!(history.entries.len(), 3);
+
+        assert!(history.push(Sources::Nyaa, "three", 3));
+        assert_eq!(history.queries(Sources::Nyaa), vec!["three", "one"]);
+        assert_eq!(history.queries(Sources::TorrentGalaxy), vec!["one"]);
+    }
+
+    #[test]
+    fn test_download_history_contains() {
+        let mut history = DownloadHistory::default();
+        let item = Item {
+            id: "nyaa-1".to_owned(),
+            magnet_link: "magnet:?xt=urn:btih:ABCDEF&dn=test".to_owned(),
+            ..Default::default()
+        };
+        history.push(DownloadEntry::new(Sources::Nyaa, &[Client::Cmd], &item));
+
+        assert!(history.contains(Sources::Nyaa, &item));
+        // Same id on another source is a different torrent
+        let other = Item {
+            id: "nyaa-1".to_owned(),
+            ..Default::default()
+        };
+        assert!(!history.contains(Sources::SukebeiNyaa, &other));
+        // Same torrent found through another source
+        let mirror = Item {
+            id: "tgx-2".to_owned(),
+            magnet_link: "magnet:?xt=urn:btih:abcdef&dn=mirror".to_owned(),
+            ..Default::default()
+        };
+        assert!(history.contains(Sources::TorrentGalaxy, &mirror));
+    }
+
+    #[test]
+    fn test_download_entry_clients() {
+        let item = Item::default();
+        let entry = DownloadEntry::new(Sources::Nyaa, &[Client::Qbit, Client::Download], &item);
+        let history = DownloadHistory {
+            entries: vec![entry],
+            ..Default::default()
+        };
+        let stored = toml::to_string(&history).unwrap();
+        let loaded: DownloadHistory = toml::from_str(&stored).unwrap();
+        assert!(loaded.entries[0].clients.to_vec() == [Client::Qbit, Client::Download]);
+
+        // Entries from before several clients were recorded
+        let old = stored.replace(
+            r#"["qBittorrent", "DownloadTorrentFile"]"#,
+            r#""qBittorrent""#,
+        );
+        assert_ne!(old, stored);
+        let loaded: DownloadHistory = toml::from_str(&old).unwrap();
+        assert!(loaded.entries[0].clients.to_vec() == [Client::Qbit]);
+    }
+
+    #[test]
+    fn test_pending_downloads() {
+        assert_eq!(backoff(1), 30);
+        assert_eq!(backoff(3), 120);
+        assert_eq!(backoff(100), MAX_BACKOFF);
+
+        let mut pending = PendingDownloads::default();
+        let item = Item {
+            id: "nyaa-1".to_owned(),
+            category: 12,
+            ..Default::default()
+        };
+        let failed = |c: Client, e: &str| vec![(c, e.to_owned())];
+        pending.fail(Sources::Nyaa, failed(Client::Qbit, "refused"), &item);
+        pending.fail(Sources::Nyaa, failed(Client::Deluge, "timed out"), &item);
+        assert_eq!(pending.entries.len(), 1);
+        assert_eq!(pending.entries[0].attempts, 2);
+        assert!(pending.entries[0].clients == [Client::Qbit, Client::Deluge]);
+        assert_eq!(pending.entries[0].error, "Deluge: timed out");
+        assert!(pending.due().is_empty());
+
+        pending.retry_now(Some(Client::Transmission));
+        assert!(pending.due().is_empty());
+        pending.retry_now(Some(Client::Deluge));
+        let due = pending.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].item().category, 12);
+        // Still queued, but not due again until this attempt has had time to finish
+        assert!(pending.due().is_empty());
+
+        // Only the clients that took it stop being retried
+        assert!(pending.sent(Sources::Nyaa, "nyaa-1", &[Client::Qbit]));
+        assert!(pending.entries[0].clients == [Client::Deluge]);
+        assert!(!pending.sent(Sources::SukebeiNyaa, "nyaa-1", &[Client::Deluge]));
+        assert!(pending.sent(Sources::Nyaa, "nyaa-1", &[Client::Deluge]));
+        assert!(pending.entries.is_empty());
+
+        pending.fail(Sources::Nyaa, failed(Client::Qbit, "refused"), &item);
+        assert!(!pending.remove(Sources::SukebeiNyaa, "nyaa-1"));
+        assert!(pending.remove(Sources::Nyaa, "nyaa-1"));
+        assert!(pending.entries.is_empty());
+    }
+}
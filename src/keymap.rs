@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::Mode;
+
+/// Something a key can be bound to in the `[keys]` config section
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Removes a default binding
+    None,
+    Quit,
+    Cancel,
+    Down,
+    Up,
+    DownFour,
+    UpFour,
+    Top,
+    Bottom,
+    NextPage,
+    PrevPage,
+    FirstPage,
+    LastPage,
+    Reload,
+    Download,
+    DownloadWithOptions,
+    DownloadBatch,
+    DownloadBatchWithOptions,
+    ClearBatch,
+    OpenInBrowser,
+    Details,
+    CopyTorrentLink,
+    CopyMagnetLink,
+    CopyPostLink,
+    CopyImdbId,
+    CopyName,
+    ToggleBatch,
+    VisualAdd,
+    VisualRemove,
+    VisualToggle,
+    FocusBatch,
+    FocusResults,
+    Search,
+    Categories,
+    Filters,
+    Sort,
+    SortReversed,
+    ToggleFederated,
+    Themes,
+    User,
+    SavedSearches,
+    Collections,
+    DownloadHistory,
+    Torrents,
+    PendingDownloads,
+    Clients,
+    GotoPage,
+    LocalFilter,
+    Sources,
+    Command,
+}
+
+impl Action {
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::None => "Nothing",
+            Action::Quit => "Exit app",
+            Action::Cancel => "Dismiss notification or exit visual mode",
+            Action::Down => "Down",
+            Action::Up => "Up",
+            Action::DownFour => "Down 4 items",
+            Action::UpFour => "Up 4 items",
+            Action::Top => "Goto top",
+            Action::Bottom => "Goto bottom",
+            Action::NextPage => "Next page",
+            Action::PrevPage => "Prev page",
+            Action::FirstPage => "First page",
+            Action::LastPage => "Last page",
+            Action::Reload => "Reload",
+            Action::Download => "Download",
+            Action::DownloadWithOptions => "Download with options",
+            Action::DownloadBatch => "Download all torrents",
+            Action::DownloadBatchWithOptions => "Download all torrents with options",
+            Action::ClearBatch => "Clear batch",
+            Action::OpenInBrowser => "Open in browser",
+            Action::Details => "Show details",
+            Action::CopyTorrentLink => "Copy torrent link",
+            Action::CopyMagnetLink => "Copy magnet link",
+            Action::CopyPostLink => "Copy post link",
+            Action::CopyImdbId => "Copy imdb id",
+            Action::CopyName => "Copy name",
+            Action::ToggleBatch => "Toggle item for batch download",
+            Action::VisualAdd => "Enter visual add mode",
+            Action::VisualRemove => "Enter visual remove mode",
+            Action::VisualToggle => "Enter visual toggle mode",
+            Action::FocusBatch => "Switch to batch",
+            Action::FocusResults => "Back to results",
+            Action::Search => "Search",
+            Action::Categories => "Categories",
+            Action::Filters => "Filters",
+            Action::Sort => "Sort",
+            Action::SortReversed => "Sort reversed",
+            Action::ToggleFederated => "Toggle searching all sources",
+            Action::Themes => "Themes",
+            Action::User => "Filter by user",
+            Action::SavedSearches => "Saved searches",
+            Action::Collections => "Save, load or export batch",
+            Action::DownloadHistory => "Download history",
+            Action::Torrents => "Torrent status",
+            Action::PendingDownloads => "Pending downloads",
+            Action::Clients => "Select download client",
+            Action::GotoPage => "Goto page",
+            Action::LocalFilter => "Filter loaded results",
+            Action::Sources => "Select source",
+            Action::Command => "Enter a command",
+        }
+    }
+
+    /// Copying needs the clipboard, so these are run by the app instead of a widget
+    pub fn is_copy(self) -> bool {
+        matches!(
+            self,
+            Action::CopyTorrentLink
+                | Action::CopyMagnetLink
+                | Action::CopyPostLink
+                | Action::CopyImdbId
+                | Action::CopyName
+        )
+    }
+}
+
+static NORMAL_KEYS: &[(&str, Action)] = &[
+    ("<CR>", Action::Download),
+    ("<Esc>", Action::Cancel),
+    ("q", Action::Quit),
+    ("g", Action::Top),
+    ("G", Action::Bottom),
+    ("k", Action::Up),
+    ("<Up>", Action::Up),
+    ("j", Action::Down),
+    ("<Down>", Action::Down),
+    ("K", Action::UpFour),
+    ("J", Action::DownFour),
+    ("n", Action::NextPage),
+    ("l", Action::NextPage),
+    ("<Right>", Action::NextPage),
+    ("p", Action::PrevPage),
+    ("h", Action::PrevPage),
+    ("<Left>", Action::PrevPage),
+    ("N", Action::LastPage),
+    ("L", Action::LastPage),
+    ("P", Action::FirstPage),
+    ("H", Action::FirstPage),
+    ("r", Action::Reload),
+    ("o", Action::OpenInBrowser),
+    ("I", Action::Details),
+    ("yt", Action::CopyTorrentLink),
+    ("ym", Action::CopyMagnetLink),
+    ("yp", Action::CopyPostLink),
+    ("yi", Action::CopyImdbId),
+    ("yn", Action::CopyName),
+    ("<Space>", Action::ToggleBatch),
+    ("v", Action::VisualAdd),
+    ("V", Action::VisualRemove),
+    ("<C-Space>", Action::VisualToggle),
+    ("<Tab>", Action::FocusBatch),
+    ("<S-Tab>", Action::FocusBatch),
+    ("/", Action::Search),
+    ("i", Action::Search),
+    ("c", Action::Categories),
+    ("f", Action::Filters),
+    ("s", Action::Sort),
+    ("S", Action::SortReversed),
+    ("F", Action::ToggleFederated),
+    ("t", Action::Themes),
+    ("u", Action::User),
+    ("b", Action::SavedSearches),
+    ("B", Action::Collections),
+    ("D", Action::DownloadHistory),
+    ("T", Action::Torrents),
+    ("R", Action::PendingDownloads),
+    ("d", Action::Clients),
+    ("<C-d>", Action::DownloadWithOptions),
+    ("<C-p>", Action::GotoPage),
+    ("<C-f>", Action::LocalFilter),
+    ("<C-s>", Action::Sources),
+    (":", Action::Command),
+];
+
+static BATCH_KEYS: &[(&str, Action)] = &[
+    ("<C-a>", Action::DownloadBatch),
+    ("<C-d>", Action::DownloadBatchWithOptions),
+    ("<C-x>", Action::ClearBatch),
+    ("B", Action::Collections),
+    ("<Esc>", Action::FocusResults),
+    ("<Tab>", Action::FocusResults),
+    ("<S-Tab>", Action::FocusResults),
+    ("q", Action::Quit),
+    ("g", Action::Top),
+    ("G", Action::Bottom),
+    ("k", Action::Up),
+    ("<Up>", Action::Up),
+    ("j", Action::Down),
+    ("<Down>", Action::Down),
+    ("K", Action::UpFour),
+    ("J", Action::DownFour),
+    ("<Space>", Action::ToggleBatch),
+];
+
+/// Keys written the way they're shown in the bottom right of the results, like `j`,
+/// `<C-f>` or `<CR>`. Plain characters can be chained into combos like `yt`.
+/// Only the results and the batch can be rebound, so any other mode is an error.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyConfig {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub normal: BTreeMap<String, Action>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub batch: BTreeMap<String, Action>,
+}
+
+impl KeyConfig {
+    pub fn is_empty(&self) -> bool {
+        self.normal.is_empty() && self.batch.is_empty()
+    }
+}
+
+/// The default bindings with the ones from the config laid over them
+#[derive(Clone)]
+pub struct Keymap {
+    normal: Vec<(String, Action)>,
+    batch: Vec<(String, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeyConfig::default()).expect("the default keys have no conflicting combos")
+    }
+}
+
+/// Split keys into single presses, where `<C-f>` is one press and `yt` is two
+fn presses(keys: &str) -> Vec<&str> {
+    let mut presses = vec![];
+    let mut rest = keys;
+    while let Some(c) = rest.chars().next() {
+        let len = match (c, rest.find('>')) {
+            ('<', Some(end)) if end > 1 => end + 1,
+            _ => c.len_utf8(),
+        };
+        presses.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    presses
+}
+
+fn starts_combo(keys: &str, combo: &str) -> bool {
+    let (keys, combo) = (presses(keys), presses(combo));
+    combo.len() > keys.len() && combo.starts_with(&keys)
+}
+
+/// A binding that's also the start of a combo would always run before the combo could finish
+fn check_combos(mode: &str, keys: &[(String, Action)]) -> Result<(), String> {
+    for (short, action) in keys.iter() {
+        if let Some((combo, _)) = keys.iter().find(|(k, _)| starts_combo(short, k)) {
+            return Err(format!(
+                "`{}` in [keys.{}] can never run because `{}` ({}) runs first, \
+                bind `{}` to \"none\" to use it",
+                combo,
+                mode,
+                short,
+                action.description(),
+                short
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn merge(defaults: &[(&str, Action)], config: &BTreeMap<String, Action>) -> Vec<(String, Action)> {
+    let mut keys: Vec<(String, Action)> = defaults
+        .iter()
+        .filter(|(k, _)| !config.contains_key(*k))
+        .map(|(k, a)| (k.to_string(), *a))
+        .collect();
+    keys.extend(
+        config
+            .iter()
+            .filter(|(k, a)| !k.is_empty() && **a != Action::None)
+            .map(|(k, a)| (k.to_owned(), *a)),
+    );
+    keys
+}
+
+impl Keymap {
+    pub fn new(config: &KeyConfig) -> Result<Self, String> {
+        let keymap = Keymap {
+            normal: merge(NORMAL_KEYS, &config.normal),
+            batch: merge(BATCH_KEYS, &config.batch),
+        };
+        check_combos("normal", &keymap.normal)?;
+        check_combos("batch", &keymap.batch)?;
+        Ok(keymap)
+    }
+
+    fn bindings(&self, mode: &Mode) -> &[(String, Action)] {
+        match mode {
+            Mode::Normal | Mode::KeyCombo(_) => &self.normal,
+            Mode::Batch => &self.batch,
+            _ => &[],
+        }
+    }
+
+    pub fn action(&self, mode: &Mode, keys: &str) -> Option<Action> {
+        self.bindings(mode)
+            .iter()
+            .find(|(k, _)| k == keys)
+            .map(|(_, a)| *a)
+    }
+
+    /// The first key bound to an action, for pointing to it in messages
+    pub fn key(&self, mode: &Mode, action: Action) -> Option<&str> {
+        self.bindings(mode)
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(k, _)| k.as_str())
+    }
+
+    /// Whether more keys could still complete a combo
+    pub fn is_prefix(&self, mode: &Mode, keys: &str) -> bool {
+        self.bindings(mode)
+            .iter()
+            .any(|(k, _)| starts_combo(keys, k))
+    }
+
+    /// Every action bound in a mode along with its keys, in the order they were bound
+    pub fn help(&self, mode: &Mode) -> Option<Vec<(String, &'static str)>> {
+        let mut help: Vec<(Action, Vec<&str>)> = vec![];
+        for (key, action) in self.bindings(mode) {
+            match help.iter_mut().find(|(a, _)| a == action) {
+                Some((_, keys)) => keys.push(key),
+                None => help.push((*action, vec![key])),
+            }
+        }
+        if help.is_empty() {
+            return None;
+        }
+        Some(
+            help.into_iter()
+                .map(|(action, keys)| (keys.join(", "), action.description()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        let config: KeyConfig = toml::from_str(
+            r#"
+            [normal]
+            x = "down"
+            j = "none"
+            "<C-n>" = "next_page"
+            g = "none"
+            gt = "torrents"
+
+            [batch]
+            "<C-a>" = "clear_batch"
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::new(&config).unwrap();
+        assert_eq!(keymap.action(&Mode::Normal, "x"), Some(Action::Down));
+        assert_eq!(keymap.action(&Mode::Normal, "j"), None);
+        assert_eq!(keymap.action(&Mode::Normal, "<Down>"), Some(Action::Down));
+        assert_eq!(
+            keymap.action(&Mode::Normal, "<C-n>"),
+            Some(Action::NextPage)
+        );
+        assert_eq!(keymap.action(&Mode::Normal, "gt"), Some(Action::Torrents));
+        assert_eq!(
+            keymap.action(&Mode::Batch, "<C-a>"),
+            Some(Action::ClearBatch)
+        );
+        assert_eq!(keymap.action(&Mode::Batch, "x"), None);
+        assert_eq!(keymap.action(&Mode::Search, "q"), None);
+
+        assert_eq!(keymap.key(&Mode::Normal, Action::Down), Some("<Down>"));
+        assert_eq!(
+            keymap.key(&Mode::Normal, Action::PendingDownloads),
+            Some("R")
+        );
+        assert!(keymap.is_prefix(&Mode::Normal, "y"));
+        assert!(keymap.is_prefix(&Mode::Normal, "g"));
+        assert!(!keymap.is_prefix(&Mode::Normal, "yt"));
+        assert!(!keymap.is_prefix(&Mode::Normal, "<"));
+
+        let help = keymap.help(&Mode::Normal).unwrap();
+        assert!(help.contains(&("<Down>, x".to_owned(), "Down")));
+        assert!(!help.iter().any(|(k, _)| k.contains('j')));
+        assert!(keymap
+            .help(&Mode::Batch)
+            .unwrap()
+            .contains(&("<C-x>, <C-a>".to_owned(), "Clear batch")));
+        assert!(keymap.help(&Mode::Search).is_none());
+        assert!(toml::from_str::<KeyConfig>("[normal]\nx = \"explode\"").is_err());
+        assert!(toml::from_str::<KeyConfig>("[search]\nx = \"quit\"").is_err());
+
+        // `g` goes to the top before `gt` could be finished
+        let config: KeyConfig = toml::from_str("[normal]\ngt = \"torrents\"").unwrap();
+        assert!(Keymap::new(&config).is_err());
+        let config: KeyConfig = toml::from_str("[batch]\n\"<C-x>x\" = \"top\"").unwrap();
+        assert!(Keymap::new(&config).is_err());
+    }
+}
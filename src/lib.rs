@@ -1,7 +1,12 @@
 pub mod app;
+pub mod cli;
 pub mod client;
 pub mod clip;
+pub mod collection;
+pub mod command;
 pub mod config;
+pub mod history;
+pub mod keymap;
 pub mod macros;
 pub mod results;
 pub mod source;
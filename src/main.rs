@@ -1,6 +1,7 @@
-use std::{error::Error, io::stdout};
+use std::io::stdout;
 
 use app::App;
+use cli::Command;
 use config::{AppConfig, ConfigManager};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use sync::AppSync;
@@ -9,9 +10,14 @@ use sync::AppSync;
 use ratatui::termion::raw::IntoRawMode;
 
 pub mod app;
+pub mod cli;
 pub mod client;
 pub mod clip;
+pub mod collection;
+pub mod command;
 pub mod config;
+pub mod history;
+pub mod keymap;
 pub mod macros;
 pub mod results;
 pub mod source;
@@ -20,35 +26,6 @@ pub mod theme;
 pub mod util;
 pub mod widget;
 
-struct Args {
-    config_path: Option<String>,
-}
-
-fn parse_args() -> Result<Args, Box<dyn Error>> {
-    use lexopt::prelude::*;
-
-    let mut config_path = None;
-    let mut parser = lexopt::Parser::from_env();
-    while let Some(arg) = parser.next()? {
-        match arg {
-            Short('c') | Long("config") => {
-                config_path = Some(shellexpand::full(&parser.value()?.string()?)?.to_string());
-            }
-            Short('v') | Short('V') | Long("version") => {
-                println!("nyaa v{}", env!("CARGO_PKG_VERSION"));
-                std::process::exit(0);
-            }
-            Long("help") => {
-                println!("Usage: nyaa [-v|-V|--version] [-c|--config=/path/to/config/folder]");
-                std::process::exit(0);
-            }
-            _ => return Err(arg.unexpected().into()),
-        }
-    }
-
-    Ok(Args { config_path })
-}
-
 #[tokio::main()]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let default_panic = std::panic::take_hook();
@@ -59,7 +36,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }));
 
-    let args = parse_args()?;
+    let args = cli::parse_args()?;
+    if !matches!(args.command, Command::Tui) {
+        cli::run(args.command, args.config_path, args.offline).await;
+    }
+
     util::term::setup_terminal()?;
 
     #[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
@@ -70,11 +51,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::default();
-    let config = match args.config_path {
-        Some(path) => AppConfig::from_path(path),
-        None => AppConfig::new(),
-    }?;
-    let sync = AppSync::new(config.path());
+    let config = cli::config_manager(args.config_path)?;
+    let sync = AppSync::new(config.path(), args.offline);
 
     app.run_app::<_, _, AppConfig, false>(&mut terminal, sync, config)
         .await?;
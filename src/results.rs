@@ -71,6 +71,17 @@ impl<S: PartialEq + Copy> ResultHeader<S> {
             })
             .collect()
     }
+
+    /// The sort each column switches to when its header is clicked
+    pub fn get_sorts(&self, index: impl Fn(S) -> usize) -> Vec<Option<usize>> {
+        self.cols
+            .iter()
+            .map(|c| match c {
+                ResultColumn::Normal(..) => None,
+                ResultColumn::Sorted(_, _, s) => Some(index(*s)),
+            })
+            .collect()
+    }
 }
 
 impl<S: PartialEq + Copy> ResultColumn<S> {
@@ -84,7 +95,7 @@ impl<S: PartialEq + Copy> ResultColumn<S> {
                             name.replace_range(
                                 name.char_indices()
                                     .nth(idx + 2)
-                                    .map(|(pos, ch)| (pos..pos + ch.len_utf8()))
+                                    .map(|(pos, ch)| pos..pos + ch.len_utf8())
                                     .unwrap(),
                                 match dir {
                                     SortDir::Asc => "▲",
@@ -106,6 +117,8 @@ pub struct ResultTable {
     pub headers: ResultRow,
     pub rows: Vec<ResultRow>,
     pub binding: Vec<Constraint>,
+    /// The sort for each column with a clickable header
+    pub sorts: Vec<Option<usize>>,
 }
 
 #[derive(Clone)]
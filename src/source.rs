@@ -1,4 +1,4 @@
-use std::{collections::HashMap, error::Error, sync::Arc, time::Duration};
+use std::{collections::HashMap, error::Error, str::FromStr, sync::Arc, time::Duration};
 
 use nyaa_html::NyaaTheme;
 use reqwest::{cookie::Jar, Proxy};
@@ -13,7 +13,7 @@ use crate::{
     results::{ResultResponse, ResultTable, Results},
     sync::SearchQuery,
     theme::Theme,
-    util::conv::add_protocol,
+    util::{cache::HttpCache, conv::add_protocol},
     widget::{
         category::{CatEntry, CatIcon, CatStruct},
         sort::SelectedSort,
@@ -21,18 +21,23 @@ use crate::{
 };
 
 use self::{
+    custom::{CustomConfig, CustomSource, CustomSources},
     nyaa_html::{NyaaConfig, NyaaHtmlSource},
     sukebei_nyaa::{SukebeiHtmlSource, SukebeiNyaaConfig},
     torrent_galaxy::{TgxConfig, TorrentGalaxyHtmlSource},
+    torznab::{TorznabConfig, TorznabSource},
 };
 
 #[cfg(feature = "captcha")]
 use ratatui_image::protocol::StatefulProtocol;
 
+pub mod custom;
+pub mod federated;
 pub mod nyaa_html;
 pub mod nyaa_rss;
 pub mod sukebei_nyaa;
 pub mod torrent_galaxy;
+pub mod torznab;
 
 #[derive(Clone)]
 pub enum SourceResults {
@@ -61,17 +66,33 @@ pub struct SourceTheme {
 #[derive(Serialize, Deserialize, Clone, Default)]
 #[serde(default)]
 pub struct SourceConfig {
+    /// Name of the custom source in use when there are several
+    pub custom_source: Option<String>,
     pub nyaa: Option<NyaaConfig>,
     #[serde(rename = "sukebei")]
     pub sukebei: Option<SukebeiNyaaConfig>,
     #[serde(rename = "torrentgalaxy")]
     pub tgx: Option<TgxConfig>,
+    pub custom: Option<CustomSources>,
+    pub torznab: Option<TorznabConfig>,
 }
 
+impl SourceConfig {
+    /// The custom source picked by `custom_source`
+    pub fn custom(&self) -> Option<CustomConfig> {
+        self.custom
+            .as_ref()
+            .and_then(|c| c.get(self.custom_source.as_deref()))
+            .cloned()
+    }
+}
+
+#[derive(Clone)]
 pub struct SourceExtraConfig {
     pub date_format: Option<String>,
     pub relative_date: Option<bool>,
     pub relative_date_short: Option<bool>,
+    pub cache: HttpCache,
 }
 
 impl From<Config> for SourceExtraConfig {
@@ -80,6 +101,7 @@ impl From<Config> for SourceExtraConfig {
             date_format: c.date_format,
             relative_date: c.relative_date,
             relative_date_short: c.relative_date_short,
+            cache: HttpCache::new(c.cache_ttl),
         }
     }
 }
@@ -126,6 +148,61 @@ impl SourceInfo {
         }
         self.cats[0].entries[0].clone()
     }
+
+    /// Find a category by its config name (`AnimeEnglishTranslated`), its
+    /// display name, its id, or nyaa's `1_2` url format. Display names shared by
+    /// several categories (like "English-translated") are rejected
+    pub fn find_category(&self, s: &str) -> Result<CatEntry, String> {
+        let id = match s.split_once('_') {
+            Some((high, low)) => high
+                .parse::<usize>()
+                .ok()
+                .zip(low.parse::<usize>().ok())
+                .map(|(high, low)| high * 10 + low),
+            None => s.parse::<usize>().ok(),
+        };
+        let entries = || self.cats.iter().flat_map(|cat| cat.entries.iter());
+        if let Some(ent) =
+            entries().find(|ent| Some(ent.id) == id || ent.cfg.eq_ignore_ascii_case(s))
+        {
+            return Ok(ent.clone());
+        }
+        let named = entries()
+            .filter(|ent| ent.name.eq_ignore_ascii_case(s))
+            .collect::<Vec<&CatEntry>>();
+        match named.as_slice() {
+            [] => Err(format!("Unknown category \"{}\"", s)),
+            [ent] => Ok((*ent).clone()),
+            _ => Err(format!(
+                "Category \"{}\" is ambiguous, use one of {}",
+                s,
+                named
+                    .iter()
+                    .map(|ent| ent.cfg.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    pub fn find_sort(&self, s: &str) -> Option<usize> {
+        find_name(&self.sorts, s)
+    }
+
+    pub fn find_filter(&self, s: &str) -> Option<usize> {
+        find_name(&self.filters, s)
+    }
+}
+
+fn find_name(names: &[String], s: &str) -> Option<usize> {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let s = normalize(s);
+    names.iter().position(|n| normalize(n) == s)
 }
 
 pub fn request_client(
@@ -145,7 +222,7 @@ pub fn request_client(
     Ok(client.build()?)
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
 pub enum ItemType {
     #[default]
     None,
@@ -157,6 +234,8 @@ pub enum ItemType {
 pub struct Item {
     pub id: String,
     pub date: String,
+    /// Unix timestamp of the upload, or 0 if the source's date couldn't be read
+    pub timestamp: i64,
     pub seeders: u32,
     pub leechers: u32,
     pub downloads: u32,
@@ -171,9 +250,37 @@ pub struct Item {
     pub icon: CatIcon,
     pub item_type: ItemType,
     pub extra: HashMap<String, String>,
+    /// Only set when results from several sources are shown together
+    pub source: Option<Sources>,
+}
+
+#[derive(Clone, Default)]
+pub struct DetailsFile {
+    pub name: String,
+    pub size: Option<String>,
+    /// How many folders deep the file is nested
+    pub depth: usize,
+    pub folder: bool,
 }
 
-#[derive(Serialize, Deserialize, Display, Clone, Copy, VariantArray, PartialEq, Eq)]
+#[derive(Clone, Default)]
+pub struct DetailsComment {
+    pub user: String,
+    pub date: String,
+    pub text: String,
+}
+
+/// Everything shown on an item's page that isn't part of the results listing
+#[derive(Clone, Default)]
+pub struct ItemDetails {
+    pub description: String,
+    pub hash: Option<String>,
+    pub submitter: Option<String>,
+    pub files: Vec<DetailsFile>,
+    pub comments: Vec<DetailsComment>,
+}
+
+#[derive(Serialize, Deserialize, Display, Clone, Copy, VariantArray, PartialEq, Eq, Hash)]
 pub enum Sources {
     #[strum(serialize = "Nyaa")]
     Nyaa = 0,
@@ -181,6 +288,25 @@ pub enum Sources {
     SukebeiNyaa = 1,
     #[strum(serialize = "TorrentGalaxy")]
     TorrentGalaxy = 2,
+    #[strum(serialize = "Custom")]
+    Custom = 3,
+    #[strum(serialize = "Torznab")]
+    Torznab = 4,
+}
+
+impl FromStr for Sources {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nyaa" => Ok(Sources::Nyaa),
+            "sukebei" | "sukebeinyaa" => Ok(Sources::SukebeiNyaa),
+            "tgx" | "torrentgalaxy" => Ok(Sources::TorrentGalaxy),
+            "custom" => Ok(Sources::Custom),
+            "torznab" | "jackett" | "prowlarr" => Ok(Sources::Torznab),
+            _ => Err(format!("Unknown source \"{}\"", s)),
+        }
+    }
 }
 
 pub trait Source {
@@ -215,6 +341,11 @@ pub trait Source {
         config: &SourceConfig,
         extra: &SourceExtraConfig,
     ) -> impl std::future::Future<Output = Result<SourceResponse, Box<dyn Error + Send + Sync>>> + Send;
+    fn details(
+        client: &reqwest::Client,
+        item: &Item,
+        config: &SourceConfig,
+    ) -> impl std::future::Future<Output = Result<ItemDetails, Box<dyn Error + Send + Sync>>> + Send;
     fn info() -> SourceInfo;
     fn load_config(config: &mut SourceConfig);
 
@@ -232,6 +363,17 @@ pub trait Source {
 }
 
 impl Sources {
+    /// Whether results list who uploaded them in `extra["uploader"]`. Nyaa's listings
+    /// and RSS feeds don't, so it can only be searched for with `user`
+    pub fn has_uploader(self) -> bool {
+        self == Sources::TorrentGalaxy
+    }
+
+    /// Whether searches can be limited to one user's uploads
+    pub fn has_user_filter(self) -> bool {
+        matches!(self, Sources::Nyaa | Sources::SukebeiNyaa | Sources::Custom)
+    }
+
     pub async fn load(
         &self,
         load_type: LoadType,
@@ -242,7 +384,7 @@ impl Sources {
     ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
         match self {
             Sources::Nyaa => match load_type {
-                LoadType::Searching | LoadType::Sourcing => {
+                LoadType::Searching | LoadType::Sourcing | LoadType::Reloading => {
                     NyaaHtmlSource::search(client, search, config, extra).await
                 }
                 LoadType::Sorting => NyaaHtmlSource::sort(client, search, config, extra).await,
@@ -253,10 +395,10 @@ impl Sources {
                 LoadType::SolvingCaptcha(solution) => {
                     NyaaHtmlSource::solve(solution, client, search, config, extra).await
                 }
-                LoadType::Downloading | LoadType::Batching => unreachable!(),
+                LoadType::Downloading | LoadType::Batching | LoadType::Details => unreachable!(),
             },
             Sources::SukebeiNyaa => match load_type {
-                LoadType::Searching | LoadType::Sourcing => {
+                LoadType::Searching | LoadType::Sourcing | LoadType::Reloading => {
                     SukebeiHtmlSource::search(client, search, config, extra).await
                 }
                 LoadType::Sorting => SukebeiHtmlSource::sort(client, search, config, extra).await,
@@ -269,10 +411,10 @@ impl Sources {
                 LoadType::SolvingCaptcha(solution) => {
                     SukebeiHtmlSource::solve(solution, client, search, config, extra).await
                 }
-                LoadType::Downloading | LoadType::Batching => unreachable!(),
+                LoadType::Downloading | LoadType::Batching | LoadType::Details => unreachable!(),
             },
             Sources::TorrentGalaxy => match load_type {
-                LoadType::Searching | LoadType::Sourcing => {
+                LoadType::Searching | LoadType::Sourcing | LoadType::Reloading => {
                     TorrentGalaxyHtmlSource::search(client, search, config, extra).await
                 }
                 LoadType::Sorting => {
@@ -287,13 +429,56 @@ impl Sources {
                 LoadType::SolvingCaptcha(solution) => {
                     TorrentGalaxyHtmlSource::solve(solution, client, search, config, extra).await
                 }
-                LoadType::Downloading | LoadType::Batching => unreachable!(),
+                LoadType::Downloading | LoadType::Batching | LoadType::Details => unreachable!(),
+            },
+            Sources::Custom => match load_type {
+                LoadType::Searching | LoadType::Sourcing | LoadType::Reloading => {
+                    CustomSource::search(client, search, config, extra).await
+                }
+                LoadType::Sorting => CustomSource::sort(client, search, config, extra).await,
+                LoadType::Filtering => CustomSource::filter(client, search, config, extra).await,
+                LoadType::Categorizing => {
+                    CustomSource::categorize(client, search, config, extra).await
+                }
+                LoadType::SolvingCaptcha(solution) => {
+                    CustomSource::solve(solution, client, search, config, extra).await
+                }
+                LoadType::Downloading | LoadType::Batching | LoadType::Details => unreachable!(),
             },
+            Sources::Torznab => match load_type {
+                LoadType::Searching | LoadType::Sourcing | LoadType::Reloading => {
+                    TorznabSource::search(client, search, config, extra).await
+                }
+                LoadType::Sorting => TorznabSource::sort(client, search, config, extra).await,
+                LoadType::Filtering => TorznabSource::filter(client, search, config, extra).await,
+                LoadType::Categorizing => {
+                    TorznabSource::categorize(client, search, config, extra).await
+                }
+                LoadType::SolvingCaptcha(solution) => {
+                    TorznabSource::solve(solution, client, search, config, extra).await
+                }
+                LoadType::Downloading | LoadType::Batching | LoadType::Details => unreachable!(),
+            },
+        }
+    }
+
+    pub async fn details(
+        &self,
+        client: &reqwest::Client,
+        item: &Item,
+        config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        match self {
+            Sources::Nyaa => NyaaHtmlSource::details(client, item, config).await,
+            Sources::SukebeiNyaa => SukebeiHtmlSource::details(client, item, config).await,
+            Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::details(client, item, config).await,
+            Sources::Custom => CustomSource::details(client, item, config).await,
+            Sources::Torznab => TorznabSource::details(client, item, config).await,
         }
     }
 
     pub fn apply(self, ctx: &mut Context, w: &mut Widgets) {
-        ctx.src_info = self.info();
+        ctx.src_info = self.info(&ctx.config.sources);
         w.category.selected = self.default_category(&ctx.config.sources);
 
         let (major, minor) = ctx.src_info.get_major_minor(w.category.selected);
@@ -315,11 +500,13 @@ impl Sources {
         ctx.page = 1;
     }
 
-    pub fn info(self) -> SourceInfo {
+    pub fn info(self, config: &SourceConfig) -> SourceInfo {
         match self {
             Sources::Nyaa => NyaaHtmlSource::info(),
             Sources::SukebeiNyaa => SukebeiHtmlSource::info(),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::info(),
+            Sources::Custom => CustomSource::config_info(config),
+            Sources::Torznab => TorznabSource::info(),
         }
     }
 
@@ -328,6 +515,8 @@ impl Sources {
             Sources::Nyaa => NyaaHtmlSource::load_config(config),
             Sources::SukebeiNyaa => SukebeiHtmlSource::load_config(config),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::load_config(config),
+            Sources::Custom => CustomSource::load_config(config),
+            Sources::Torznab => TorznabSource::load_config(config),
         };
     }
 
@@ -336,6 +525,8 @@ impl Sources {
             Sources::Nyaa => NyaaHtmlSource::default_category(config),
             Sources::SukebeiNyaa => SukebeiHtmlSource::default_category(config),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::default_category(config),
+            Sources::Custom => CustomSource::default_category(config),
+            Sources::Torznab => TorznabSource::default_category(config),
         }
     }
 
@@ -344,6 +535,8 @@ impl Sources {
             Sources::Nyaa => NyaaHtmlSource::default_sort(config),
             Sources::SukebeiNyaa => SukebeiHtmlSource::default_sort(config),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::default_sort(config),
+            Sources::Custom => CustomSource::default_sort(config),
+            Sources::Torznab => TorznabSource::default_sort(config),
         }
     }
 
@@ -352,6 +545,8 @@ impl Sources {
             Sources::Nyaa => NyaaHtmlSource::default_filter(config),
             Sources::SukebeiNyaa => SukebeiHtmlSource::default_filter(config),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::default_filter(config),
+            Sources::Custom => CustomSource::default_filter(config),
+            Sources::Torznab => TorznabSource::default_filter(config),
         }
     }
 
@@ -360,6 +555,8 @@ impl Sources {
             Sources::Nyaa => NyaaHtmlSource::default_search(config),
             Sources::SukebeiNyaa => SukebeiHtmlSource::default_search(config),
             Sources::TorrentGalaxy => TorrentGalaxyHtmlSource::default_search(config),
+            Sources::Custom => CustomSource::default_search(config),
+            Sources::Torznab => TorznabSource::default_search(config),
         }
     }
 
@@ -376,6 +573,8 @@ impl Sources {
             Sources::TorrentGalaxy => {
                 TorrentGalaxyHtmlSource::format_table(items, search, config, theme)
             }
+            Sources::Custom => CustomSource::format_table(items, search, config, theme),
+            Sources::Torznab => TorznabSource::format_table(items, search, config, theme),
         }
     }
 }
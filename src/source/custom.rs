@@ -0,0 +1,575 @@
+use std::{error::Error, time::Duration};
+
+use ratatui::{
+    layout::{Alignment, Constraint},
+    style::Stylize as _,
+};
+use reqwest::{StatusCode, Url};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use urlencoding::encode;
+
+use crate::{
+    results::{ResultColumn, ResultHeader, ResultResponse, ResultRow, ResultTable},
+    sync::SearchQuery,
+    theme::Theme,
+    util::{
+        conv::{get_hash, shorten_number, to_bytes, to_timestamp},
+        html::{as_type, attr, text},
+    },
+    widget::{
+        category::{CatEntry, CatStruct},
+        sort::{SelectedSort, SortDir},
+    },
+};
+
+use super::{
+    add_protocol, Item, ItemDetails, Source, SourceConfig, SourceExtraConfig, SourceInfo,
+    SourceResponse,
+};
+
+/// A single `[source.custom]`, or several `[[source.custom]]` told apart by their `name`
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum CustomSources {
+    Many(Vec<CustomConfig>),
+    One(Box<CustomConfig>),
+}
+
+impl CustomSources {
+    pub fn to_vec(&self) -> Vec<&CustomConfig> {
+        match self {
+            Self::Many(c) => c.iter().collect(),
+            Self::One(c) => vec![c.as_ref()],
+        }
+    }
+
+    /// The custom source called `name`, or the first one if there is no such source
+    pub fn get(&self, name: Option<&str>) -> Option<&CustomConfig> {
+        let sources = self.to_vec();
+        name.and_then(|n| {
+            sources
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(n))
+                .copied()
+        })
+        .or(sources.first().copied())
+    }
+
+    /// Names to pick between, or nothing when there's only one custom source
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Self::Many(c) if c.len() > 1 => c.iter().map(|c| c.name.to_owned()).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CustomConfig {
+    /// Shown when picking between several custom sources
+    pub name: String,
+    pub base_url: String,
+    /// Search page relative to `base_url`, with placeholders like `{query}` and `{page}`
+    pub search_url: String,
+    pub default_sort: String,
+    pub default_sort_dir: SortDir,
+    pub default_filter: String,
+    pub default_category: String,
+    pub default_search: String,
+    /// Number of results on a full page, used to guess the last page
+    pub per_page: Option<usize>,
+    pub timeout: Option<u64>,
+    pub selectors: CustomSelectors,
+    pub categories: Vec<CustomOption>,
+    pub sorts: Vec<CustomOption>,
+    pub filters: Vec<CustomOption>,
+}
+
+impl Default for CustomConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_url: String::new(),
+            search_url: String::new(),
+            default_sort: String::new(),
+            default_sort_dir: SortDir::Desc,
+            default_filter: String::new(),
+            default_category: String::new(),
+            default_search: String::new(),
+            per_page: None,
+            timeout: None,
+            selectors: CustomSelectors::default(),
+            categories: vec![],
+            sorts: vec![],
+            filters: vec![],
+        }
+    }
+}
+
+/// CSS selectors relative to each row. Append `@attr` to use an attribute instead of the text.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CustomSelectors {
+    pub row: String,
+    pub title: String,
+    pub post_link: Option<String>,
+    pub size: Option<String>,
+    pub date: Option<String>,
+    pub seeders: Option<String>,
+    pub leechers: Option<String>,
+    pub downloads: Option<String>,
+    pub magnet: Option<String>,
+    pub torrent: Option<String>,
+    /// Selector for the total number of results, relative to the whole page
+    pub total_results: Option<String>,
+}
+
+/// A category, sort or filter, and the value substituted into `search_url` when it's selected
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CustomOption {
+    pub name: String,
+    pub value: String,
+}
+
+struct Field {
+    sel: Selector,
+    attr: Option<String>,
+}
+
+impl Field {
+    fn parse(s: &str) -> Result<Self, String> {
+        let (sel, attr) = match s.rsplit_once('@') {
+            Some((sel, attr))
+                if !attr.is_empty()
+                    && attr
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                (sel, Some(attr.to_owned()))
+            }
+            _ => (s, None),
+        };
+        let sel =
+            Selector::parse(sel).map_err(|e| format!("Invalid selector \"{}\":\n{}", s, e))?;
+        Ok(Self { sel, attr })
+    }
+
+    fn optional(s: &Option<String>) -> Result<Option<Self>, String> {
+        s.as_deref().map(Self::parse).transpose()
+    }
+
+    fn get(&self, e: ElementRef) -> String {
+        match &self.attr {
+            Some(a) => attr(e, &self.sel, a),
+            None => e.select(&self.sel).next().map(text).unwrap_or_default(),
+        }
+    }
+}
+
+fn get(field: &Option<Field>, e: ElementRef) -> String {
+    field.as_ref().map(|f| f.get(e)).unwrap_or_default()
+}
+
+fn option_value(options: &[CustomOption], idx: usize) -> &str {
+    options
+        .get(idx)
+        .map(|o| o.value.as_str())
+        .unwrap_or_default()
+}
+
+fn option_names(options: &[CustomOption], default: &str) -> Vec<String> {
+    match options.is_empty() {
+        true => vec![default.to_owned()],
+        false => options.iter().map(|o| o.name.to_owned()).collect(),
+    }
+}
+
+fn get_url(
+    custom: &CustomConfig,
+    search: &SearchQuery,
+) -> Result<Url, Box<dyn Error + Send + Sync>> {
+    let path = custom
+        .search_url
+        .replace("{query}", &encode(&search.query))
+        .replace("{page}", &search.page.to_string())
+        .replace("{page0}", &search.page.saturating_sub(1).to_string())
+        .replace(
+            "{category}",
+            option_value(&custom.categories, search.category),
+        )
+        .replace("{filter}", option_value(&custom.filters, search.filter))
+        .replace("{sort}", option_value(&custom.sorts, search.sort.sort))
+        .replace("{order}", &search.sort.dir.to_url())
+        .replace(
+            "{user}",
+            &encode(search.user.as_deref().unwrap_or_default()),
+        );
+    Ok(add_protocol(custom.base_url.clone(), true)?.join(&path)?)
+}
+
+/// Build items out of a results page using the selectors from the config
+pub fn parse_results(
+    custom: &CustomConfig,
+    base_url: &Url,
+    content: &str,
+    page: usize,
+) -> Result<ResultResponse, Box<dyn Error + Send + Sync>> {
+    let sels = &custom.selectors;
+    let row = Field::parse(&sels.row)?;
+    let title = Field::parse(&sels.title)?;
+    let post_link = Field::optional(&sels.post_link)?;
+    let size = Field::optional(&sels.size)?;
+    let date = Field::optional(&sels.date)?;
+    let seeders = Field::optional(&sels.seeders)?;
+    let leechers = Field::optional(&sels.leechers)?;
+    let downloads = Field::optional(&sels.downloads)?;
+    let magnet = Field::optional(&sels.magnet)?;
+    let torrent = Field::optional(&sels.torrent)?;
+    let total_results = Field::optional(&sels.total_results)?;
+
+    let join = |link: String| match link.is_empty() {
+        true => link,
+        false => base_url.join(&link).map(Into::into).unwrap_or_default(),
+    };
+
+    let doc = Html::parse_document(content);
+    let items = doc
+        .select(&row.sel)
+        .filter_map(|e| {
+            let title = title.get(e);
+            if title.is_empty() {
+                return None;
+            }
+            let magnet_link = get(&magnet, e);
+            let torrent_link = join(get(&torrent, e));
+            let post_link = join(get(&post_link, e));
+            let key = get_hash(magnet_link.clone())
+                .or_else(|| Some(post_link.clone()).filter(|p| !p.is_empty()))
+                .unwrap_or_else(|| torrent_link.clone());
+            let id = format!("custom-{}", key);
+            let size = get(&size, e);
+            Some(Item {
+                file_name: format!("{}.torrent", key.rsplit('/').next().unwrap_or_default()),
+                id,
+                date: get(&date, e),
+                timestamp: to_timestamp(&get(&date, e)),
+                seeders: as_type(get(&seeders, e)).unwrap_or_default(),
+                leechers: as_type(get(&leechers, e)).unwrap_or_default(),
+                downloads: as_type(get(&downloads, e)).unwrap_or_default(),
+                bytes: to_bytes(&size),
+                size,
+                title,
+                torrent_link,
+                magnet_link,
+                post_link,
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<Item>>();
+
+    let per_page = custom.per_page.unwrap_or(items.len()).max(1);
+    let root = doc.root_element();
+    let (total_results, last_page) = match total_results
+        .as_ref()
+        .and_then(|t| as_type::<usize>(t.get(root)))
+    {
+        Some(total) => (total, total.div_ceil(per_page)),
+        // Without a total, allow going to the next page until one comes back short
+        None if items.len() >= per_page && !items.is_empty() => ((page + 1) * per_page, page + 1),
+        None => ((page.max(1) - 1) * per_page + items.len(), page),
+    };
+    Ok(ResultResponse {
+        items,
+        total_results,
+        last_page,
+    })
+}
+
+pub struct CustomSource;
+
+impl CustomSource {
+    /// Categories, sorts and filters are all defined by the user
+    pub fn config_info(config: &SourceConfig) -> SourceInfo {
+        let custom = config.custom().unwrap_or_default();
+        let entries = match custom.categories.is_empty() {
+            true => vec![CatEntry::new(
+                "All Categories",
+                "AllCategories",
+                0,
+                "---",
+                |t| t.fg,
+            )],
+            false => custom
+                .categories
+                .iter()
+                .enumerate()
+                .map(|(i, c)| CatEntry::new(&c.name, &c.name, i, "---", |t| t.fg))
+                .collect(),
+        };
+        SourceInfo {
+            cats: vec![CatStruct {
+                name: "Categories".to_owned(),
+                entries,
+            }],
+            filters: option_names(&custom.filters, "No Filter"),
+            sorts: option_names(&custom.sorts, "Default"),
+        }
+    }
+}
+
+impl Source for CustomSource {
+    async fn search(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        let custom = config
+            .custom()
+            .ok_or("The custom source is not configured, add [source.custom] to config.toml")?;
+        let url = get_url(&custom, search)?;
+
+        let mut request = client.get(url.to_owned());
+        if let Some(timeout) = custom.timeout {
+            request = request.timeout(Duration::from_secs(timeout));
+        }
+        let (status, content) = extra.cache.fetch(client, request).await?;
+        if status != StatusCode::OK {
+            // Throw error if response code is not OK
+            let code = status.as_u16();
+            return Err(format!("{}\nInvalid response code: {}", url, code).into());
+        }
+        let content = String::from_utf8(content)?;
+        Ok(SourceResponse::Results(parse_results(
+            &custom,
+            &url,
+            &content,
+            search.page,
+        )?))
+    }
+
+    async fn sort(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        CustomSource::search(client, search, config, extra).await
+    }
+
+    async fn filter(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        CustomSource::search(client, search, config, extra).await
+    }
+
+    async fn categorize(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        CustomSource::search(client, search, config, extra).await
+    }
+
+    async fn solve(
+        _solution: String,
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        CustomSource::search(client, search, config, extra).await
+    }
+
+    async fn details(
+        _client: &reqwest::Client,
+        _item: &Item,
+        _config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        Err("Details are not available for the custom source".into())
+    }
+
+    fn info() -> SourceInfo {
+        Self::config_info(&SourceConfig::default())
+    }
+
+    fn load_config(_config: &mut SourceConfig) {
+        // There is no sensible default site, so leave it unset until the user adds one
+    }
+
+    fn default_category(cfg: &SourceConfig) -> usize {
+        let default = cfg.custom().map(|c| c.default_category).unwrap_or_default();
+        Self::config_info(cfg).entry_from_cfg(&default).id
+    }
+
+    fn default_sort(cfg: &SourceConfig) -> SelectedSort {
+        cfg.custom()
+            .map(|c| SelectedSort {
+                sort: Self::config_info(cfg)
+                    .find_sort(&c.default_sort)
+                    .unwrap_or_default(),
+                dir: c.default_sort_dir,
+            })
+            .unwrap_or_default()
+    }
+
+    fn default_filter(cfg: &SourceConfig) -> usize {
+        cfg.custom()
+            .and_then(|c| Self::config_info(cfg).find_filter(&c.default_filter))
+            .unwrap_or_default()
+    }
+
+    fn default_search(cfg: &SourceConfig) -> String {
+        cfg.custom().map(|c| c.default_search).unwrap_or_default()
+    }
+
+    fn format_table(
+        items: &[Item],
+        search: &SearchQuery,
+        config: &SourceConfig,
+        theme: &Theme,
+    ) -> ResultTable {
+        let info = Self::config_info(config);
+        let date_width = items
+            .iter()
+            .map(|i| i.date.len())
+            .max()
+            .unwrap_or_default()
+            .max(6) as u16;
+        // Columns are only sortable if the user defined a sort with the same name
+        let column = |name: &str, width: u16| match info.find_sort(name) {
+            Some(sort) => ResultColumn::Sorted(name.to_owned(), width, sort),
+            None => ResultColumn::Normal(name.to_owned(), Constraint::Length(width)),
+        };
+        let header = ResultHeader::new([
+            ResultColumn::Normal("Name".to_owned(), Constraint::Min(3)),
+            column("Size", 9),
+            column("Date", date_width),
+            column("Seeders", 7),
+            column("Leechers", 8),
+            column("Downloads", 9),
+        ]);
+        let align = [
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Right,
+            Alignment::Left,
+        ];
+        let rows = items
+            .iter()
+            .map(|item| {
+                ResultRow::new([
+                    item.title.to_owned().fg(theme.fg),
+                    item.size.to_owned().fg(theme.fg),
+                    item.date.to_owned().fg(theme.fg),
+                    item.seeders.to_string().fg(theme.success),
+                    item.leechers.to_string().fg(theme.error),
+                    shorten_number(item.downloads).fg(theme.fg),
+                ])
+                .aligned(align)
+                .fg(theme.fg)
+            })
+            .collect();
+        ResultTable {
+            headers: header.get_row(search.sort.dir, search.sort.sort),
+            rows,
+            binding: header.get_binding(),
+            sorts: header.get_sorts(|s| s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let custom = CustomConfig {
+            selectors: CustomSelectors {
+                row: "table.torrents tr.torrent".to_owned(),
+                title: "td.name > a".to_owned(),
+                post_link: Some("td.name > a@href".to_owned()),
+                size: Some("td.size".to_owned()),
+                seeders: Some("td.seeders".to_owned()),
+                magnet: Some("a.magnet@href".to_owned()),
+                ..Default::default()
+            },
+            per_page: Some(2),
+            ..Default::default()
+        };
+        let content = r#"
+<table class="torrents">
+  <tr class="torrent">
+    <td class="name"><a href="/t/1">First</a></td>
+    <td class="size">1.5 GiB</td>
+    <td class="seeders">12</td>
+    <td><a class="magnet" href="magnet:?xt=urn:btih:abc&dn=first">m</a></td>
+  </tr>
+  <tr class="torrent">
+    <td class="name"><a href="/t/2">Second</a></td>
+    <td class="size">700 MiB</td>
+    <td class="seeders">3</td>
+  </tr>
+</table>"#;
+        let base_url = Url::parse("https://example.org/search").unwrap();
+        let res = parse_results(&custom, &base_url, content, 1).unwrap();
+
+        assert_eq!(res.items.len(), 2);
+        assert_eq!(res.items[0].title, "First");
+        assert_eq!(res.items[0].id, "custom-abc");
+        assert_eq!(res.items[0].seeders, 12);
+        assert_eq!(res.items[0].bytes, to_bytes("1.5 GiB"));
+        assert_eq!(res.items[1].post_link, "https://example.org/t/2");
+        assert_eq!(res.items[1].id, "custom-https://example.org/t/2");
+        // A full page without a total means there may be another page
+        assert_eq!(res.last_page, 2);
+
+        let res = parse_results(&custom, &base_url, "", 0).unwrap();
+        assert_eq!(res.total_results, 0);
+    }
+
+    #[test]
+    fn test_custom_sources() {
+        let config: SourceConfig = toml::from_str(
+            r#"
+custom_source = "other"
+
+[[custom]]
+name = "Example"
+base_url = "https://example.org/"
+
+[[custom]]
+name = "Other"
+base_url = "https://other.example.net/"
+"#,
+        )
+        .unwrap();
+        let custom = config.custom.as_ref().unwrap();
+        assert_eq!(custom.names(), vec!["Example", "Other"]);
+        assert_eq!(
+            config.custom().unwrap().base_url,
+            "https://other.example.net/"
+        );
+        assert_eq!(custom.get(Some("missing")).unwrap().name, "Example");
+
+        // A single table still works, without a name
+        let config: SourceConfig = toml::from_str(
+            r#"
+[custom]
+base_url = "https://example.org/"
+"#,
+        )
+        .unwrap();
+        assert!(config.custom.as_ref().unwrap().names().is_empty());
+        assert_eq!(config.custom().unwrap().base_url, "https://example.org/");
+    }
+}
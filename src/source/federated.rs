@@ -0,0 +1,307 @@
+use std::{cmp::max, collections::HashSet, error::Error};
+
+use ratatui::{
+    layout::{Alignment, Constraint},
+    style::Stylize as _,
+};
+use serde::{Deserialize, Serialize};
+use strum::{Display, VariantArray};
+
+use crate::{
+    app::LoadType,
+    results::{ResultColumn, ResultHeader, ResultResponse, ResultRow, ResultTable},
+    sync::SearchQuery,
+    theme::Theme,
+    util::conv::{get_hash, shorten_number},
+    widget::sort::SortDir,
+};
+
+use super::{Item, ItemType, SourceConfig, SourceExtraConfig, SourceResponse, Sources};
+
+type SearchResult = Result<SourceResponse, Box<dyn Error + Send + Sync>>;
+
+/// Sorts that mean the same thing for every source
+#[derive(Serialize, Deserialize, Display, Clone, Copy, PartialEq, Default, VariantArray)]
+pub enum FederatedSort {
+    #[default]
+    Seeders,
+    Size,
+    Downloads,
+    Date,
+}
+
+impl FederatedSort {
+    pub fn next(self) -> Self {
+        match self {
+            FederatedSort::Seeders => FederatedSort::Size,
+            FederatedSort::Size => FederatedSort::Downloads,
+            FederatedSort::Downloads => FederatedSort::Date,
+            FederatedSort::Date => FederatedSort::Seeders,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FederatedConfig {
+    /// Searched in order, so earlier sources win when a torrent is on several of them
+    pub sources: Vec<Sources>,
+    pub sort: FederatedSort,
+    pub sort_dir: SortDir,
+}
+
+impl Default for FederatedConfig {
+    fn default() -> Self {
+        FederatedConfig {
+            sources: vec![Sources::Nyaa, Sources::TorrentGalaxy],
+            sort: FederatedSort::Seeders,
+            sort_dir: SortDir::Desc,
+        }
+    }
+}
+
+/// Short enough to fit in the source column
+pub fn label(src: Sources) -> &'static str {
+    match src {
+        Sources::Nyaa => "Nyaa",
+        Sources::SukebeiNyaa => "Sukebei",
+        Sources::TorrentGalaxy => "TGx",
+        Sources::Custom => "Custom",
+        Sources::Torznab => "Torznab",
+    }
+}
+
+/// Run the query against every source at once with their default category, filter and sort.
+/// Torrents found on more than one source (by info hash) are only kept once. When searching a
+/// user's uploads, sources that can't filter by user are skipped.
+///
+/// Returns the errors of any sources that failed alongside the rest of the results,
+/// and only fails if every source did.
+pub async fn search(
+    sources: Vec<Sources>,
+    client: reqwest::Client,
+    search: SearchQuery,
+    mut config: SourceConfig,
+    extra: SourceExtraConfig,
+) -> Result<(ResultResponse, Vec<String>), Box<dyn Error + Send + Sync>> {
+    if sources.is_empty() {
+        return Err("No sources to search, add some to `sources` under [federated]".into());
+    }
+    for src in sources.iter() {
+        src.load_config(&mut config);
+    }
+
+    let mut tasks = vec![];
+    for src in sources.iter().copied() {
+        if search.user.is_some() && !src.has_user_filter() {
+            let err = format!("{} can't search a user's uploads", src);
+            tasks.push((src, Err(err)));
+            continue;
+        }
+        let query = SearchQuery {
+            query: search.query.clone(),
+            page: search.page,
+            category: src.default_category(&config),
+            filter: src.default_filter(&config),
+            sort: src.default_sort(&config),
+            user: search.user.clone(),
+        };
+        let (client, config, extra) = (client.clone(), config.clone(), extra.clone());
+        let task = tokio::spawn(async move {
+            src.load(LoadType::Searching, &client, &query, &config, &extra)
+                .await
+        });
+        tasks.push((src, Ok(task)));
+    }
+    // Awaited in order, so each result stays with its source even if a task panics
+    let mut results = vec![];
+    for (src, task) in tasks {
+        let res = match task {
+            Ok(task) => task
+                .await
+                .unwrap_or_else(|e| Err(format!("Search failed: {}", e).into())),
+            Err(e) => Err(e.into()),
+        };
+        results.push((src, res));
+    }
+    merge(results)
+}
+
+/// Combine each source's response in order, dropping torrents an earlier source already had
+fn merge(
+    results: Vec<(Sources, SearchResult)>,
+) -> Result<(ResultResponse, Vec<String>), Box<dyn Error + Send + Sync>> {
+    let mut response = ResultResponse::default();
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let count = results.len();
+    for (src, res) in results {
+        match res {
+            Ok(SourceResponse::Results(res)) => {
+                response.last_page = max(response.last_page, res.last_page);
+                response.total_results += res.total_results;
+                for mut item in res.items {
+                    let hash = get_hash(item.magnet_link.clone()).map(|h| h.to_lowercase());
+                    if hash.is_some_and(|h| !seen.insert(h)) {
+                        continue;
+                    }
+                    item.source = Some(src);
+                    response.items.push(item);
+                }
+            }
+            #[cfg(feature = "captcha")]
+            Ok(SourceResponse::Captcha(_)) => errors.push(format!(
+                "{} needs a captcha solved, search it on its own first",
+                src
+            )),
+            Err(e) => errors.push(format!("{}:\n{}", src, e)),
+        }
+    }
+    if errors.len() == count {
+        return Err(errors.join("\n").into());
+    }
+    Ok((response, errors))
+}
+
+pub fn sort_items(items: &mut [Item], sort: FederatedSort, dir: SortDir) {
+    items.sort_by(|a, b| {
+        let ord = match sort {
+            FederatedSort::Seeders => a.seeders.cmp(&b.seeders),
+            FederatedSort::Size => a.bytes.cmp(&b.bytes),
+            FederatedSort::Downloads => a.downloads.cmp(&b.downloads),
+            FederatedSort::Date => a.timestamp.cmp(&b.timestamp),
+        };
+        match dir {
+            SortDir::Asc => ord,
+            SortDir::Desc => ord.reverse(),
+        }
+    });
+}
+
+pub fn format_table(items: &[Item], config: &FederatedConfig, theme: &Theme) -> ResultTable {
+    let raw_date_width = items.iter().map(|i| i.date.len()).max().unwrap_or_default() as u16;
+    let date_width = max(raw_date_width, 6);
+
+    let header = ResultHeader::new([
+        ResultColumn::Normal("Source".to_owned(), Constraint::Length(7)),
+        ResultColumn::Normal("Cat".to_owned(), Constraint::Length(3)),
+        ResultColumn::Normal("Name".to_owned(), Constraint::Min(3)),
+        ResultColumn::Sorted("Size".to_owned(), 9, FederatedSort::Size),
+        ResultColumn::Sorted("Date".to_owned(), date_width, FederatedSort::Date),
+        ResultColumn::Sorted("".to_owned(), 4, FederatedSort::Seeders),
+        ResultColumn::Normal("".to_owned(), Constraint::Length(4)),
+        ResultColumn::Sorted("".to_owned(), 5, FederatedSort::Downloads),
+    ]);
+    let binding = header.get_binding();
+    let align = [
+        Alignment::Left,
+        Alignment::Left,
+        Alignment::Left,
+        Alignment::Right,
+        Alignment::Left,
+        Alignment::Right,
+        Alignment::Right,
+        Alignment::Left,
+    ];
+    let rows = items
+        .iter()
+        .map(|item| {
+            ResultRow::new([
+                item.source.map(label).unwrap_or_default().fg(theme.fg),
+                item.icon.label.fg((item.icon.color)(theme)),
+                item.title.as_str().fg(match item.item_type {
+                    ItemType::Trusted => theme.success,
+                    ItemType::Remake => theme.error,
+                    ItemType::None => theme.fg,
+                }),
+                item.size.as_str().fg(theme.fg),
+                item.date.as_str().fg(theme.fg),
+                item.seeders.to_string().fg(theme.success),
+                item.leechers.to_string().fg(theme.error),
+                shorten_number(item.downloads).fg(theme.fg),
+            ])
+            .aligned(align)
+            .fg(theme.fg)
+        })
+        .collect();
+
+    ResultTable {
+        headers: header.get_row(config.sort_dir, config.sort),
+        rows,
+        binding,
+        sorts: header.get_sorts(|s| s as usize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_items() {
+        let item = |id: &str, seeders: u32, bytes: usize| Item {
+            id: id.to_owned(),
+            seeders,
+            bytes,
+            ..Default::default()
+        };
+        let mut items = vec![item("a", 5, 300), item("b", 20, 100), item("c", 5, 200)];
+
+        sort_items(&mut items, FederatedSort::Seeders, SortDir::Desc);
+        let ids = items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["b", "a", "c"]);
+
+        sort_items(&mut items, FederatedSort::Size, SortDir::Asc);
+        let ids = items.iter().map(|i| i.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let item = |id: &str, hash: &str, timestamp: i64| Item {
+            id: id.to_owned(),
+            magnet_link: format!("magnet:?xt=urn:btih:{}&dn={}", hash, id),
+            timestamp,
+            ..Default::default()
+        };
+        let response = |items: Vec<Item>| {
+            Ok(SourceResponse::Results(ResultResponse {
+                total_results: items.len(),
+                items,
+                last_page: 1,
+            }))
+        };
+        let results = vec![
+            (
+                Sources::Nyaa,
+                response(vec![item("nyaa-1", "AAAA", 10), item("nyaa-2", "bbbb", 30)]),
+            ),
+            // Same torrent as nyaa-1, with the hash in another case
+            (
+                Sources::TorrentGalaxy,
+                response(vec![item("tgx-1", "aaaa", 10), item("tgx-2", "cccc", 20)]),
+            ),
+            (Sources::Torznab, Err("Connection refused".into())),
+        ];
+        let (mut response, errors) = merge(results).unwrap();
+        let ids = response
+            .items
+            .iter()
+            .map(|i| i.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["nyaa-1", "nyaa-2", "tgx-2"]);
+        assert!(response.items[2].source == Some(Sources::TorrentGalaxy));
+        assert_eq!(errors, ["Torznab:\nConnection refused"]);
+
+        sort_items(&mut response.items, FederatedSort::Date, SortDir::Desc);
+        let ids = response
+            .items
+            .iter()
+            .map(|i| i.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["nyaa-2", "tgx-2", "nyaa-1"]);
+
+        let failed = vec![(Sources::Nyaa, Err("Timed out".into()))];
+        assert!(merge(failed).is_err());
+    }
+}
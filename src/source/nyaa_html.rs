@@ -6,8 +6,8 @@ use ratatui::{
     layout::{Alignment, Constraint},
     style::{Color, Stylize as _},
 };
-use reqwest::StatusCode;
-use scraper::{Html, Selector};
+use reqwest::{StatusCode, Url};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
 use strum::{Display, FromRepr, VariantArray};
 use urlencoding::encode;
@@ -21,15 +21,15 @@ use crate::{
     theme::Theme,
     util::{
         colors::color_to_tui,
-        conv::{shorten_number, to_bytes},
-        html::{as_type, attr, inner},
+        conv::{get_hash, shorten_number, to_bytes},
+        html::{as_type, attr, inner, labeled, text},
     },
     widget::sort::{SelectedSort, SortDir},
 };
 
 use super::{
-    add_protocol, nyaa_rss, Item, ItemType, Source, SourceConfig, SourceExtraConfig, SourceInfo,
-    SourceResponse,
+    add_protocol, nyaa_rss, DetailsComment, DetailsFile, Item, ItemDetails, ItemType, Source,
+    SourceConfig, SourceExtraConfig, SourceInfo, SourceResponse,
 };
 
 #[derive(Serialize, Deserialize, Clone, Copy, Default)]
@@ -219,6 +219,7 @@ pub fn nyaa_table(
         ResultColumn::Sorted("".to_owned(), 5, NyaaSort::Downloads as u32),
     ]);
     let mut binding = header.get_binding();
+    let mut sorts = header.get_sorts(|s| s as usize);
     let align = [
         Alignment::Left,
         Alignment::Left,
@@ -263,14 +264,117 @@ pub fn nyaa_table(
             })
             .collect::<Vec<ResultRow>>();
         binding = cond_vec!(cols ; binding);
+        sorts = cond_vec!(cols ; sorts);
     }
     ResultTable {
         headers,
         rows,
         binding,
+        sorts,
     }
 }
 
+fn children(e: ElementRef) -> Vec<ElementRef> {
+    e.children().filter_map(ElementRef::wrap).collect()
+}
+
+fn nyaa_files(list: ElementRef, depth: usize, files: &mut Vec<DetailsFile>) {
+    for li in children(list)
+        .into_iter()
+        .filter(|e| e.value().name() == "li")
+    {
+        let inner = children(li);
+        if let Some(folder) = inner
+            .iter()
+            .find(|e| e.value().classes().any(|c| c == "folder"))
+        {
+            files.push(DetailsFile {
+                name: text(*folder),
+                depth,
+                folder: true,
+                ..Default::default()
+            });
+            if let Some(sub) = inner.iter().find(|e| e.value().name() == "ul") {
+                nyaa_files(*sub, depth + 1, files);
+            }
+        } else {
+            // File names are loose text next to the icon and size
+            let name = li
+                .children()
+                .filter_map(|c| c.value().as_text().map(|t| t.to_string()))
+                .collect::<String>();
+            let size = inner
+                .iter()
+                .find(|e| e.value().classes().any(|c| c == "file-size"))
+                .map(|e| text(*e).trim_matches(['(', ')']).to_owned());
+            files.push(DetailsFile {
+                name: name.trim().to_owned(),
+                size,
+                depth,
+                folder: false,
+            });
+        }
+    }
+}
+
+/// Parse the view page shared by nyaa and sukebei
+pub fn parse_nyaa_details(content: &str) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+    let doc = Html::parse_document(content);
+    let root = doc.root_element();
+
+    let label_sel = &sel!("div.panel-body > div.row > div.col-md-1")?;
+    let hash_sel = &sel!("div.panel-body kbd")?;
+    let desc_sel = &sel!("#torrent-description")?;
+    let files_sel = &sel!("div.torrent-file-list > ul")?;
+    let comment_sel = &sel!("div.comment-panel")?;
+    let user_sel = &sel!("div.col-md-2 a")?;
+    let date_sel = &sel!(".comment-details small")?;
+    let content_sel = &sel!(".comment-content")?;
+
+    let mut files = vec![];
+    if let Some(list) = root.select(files_sel).next() {
+        nyaa_files(list, 0, &mut files);
+    }
+    let comments = root
+        .select(comment_sel)
+        .map(|c| DetailsComment {
+            user: c.select(user_sel).next().map(text).unwrap_or_default(),
+            date: c.select(date_sel).next().map(text).unwrap_or_default(),
+            text: c.select(content_sel).next().map(text).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(ItemDetails {
+        description: root.select(desc_sel).next().map(text).unwrap_or_default(),
+        hash: root.select(hash_sel).next().map(text),
+        submitter: labeled(root, label_sel, "Submitter:"),
+        files,
+        comments,
+    })
+}
+
+pub async fn nyaa_details(
+    client: &reqwest::Client,
+    item: &Item,
+    timeout: Option<u64>,
+) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+    let url = Url::parse(&item.post_link)?;
+    let mut request = client.get(url.to_owned());
+    if let Some(timeout) = timeout {
+        request = request.timeout(Duration::from_secs(timeout));
+    }
+    let response = request.send().await?;
+    if response.status() != StatusCode::OK {
+        let code = response.status().as_u16();
+        return Err(format!("{}\nInvalid response code: {}", url, code).into());
+    }
+    let mut details = parse_nyaa_details(&response.text().await?)?;
+    if details.hash.is_none() {
+        details.hash = get_hash(item.magnet_link.clone());
+    }
+    Ok(details)
+}
+
 impl Source for NyaaHtmlSource {
     async fn search(
         client: &reqwest::Client,
@@ -312,13 +416,12 @@ impl Source for NyaaHtmlSource {
         if let Some(timeout) = nyaa.timeout {
             request = request.timeout(Duration::from_secs(timeout));
         }
-        let response = request.send().await?;
-        if response.status() != StatusCode::OK {
+        let (status, content) = extra.cache.fetch(client, request).await?;
+        if status != StatusCode::OK {
             // Throw error if response code is not OK
-            let code = response.status().as_u16();
+            let code = status.as_u16();
             return Err(format!("{}\nInvalid response code: {}", url, code).into());
         }
-        let content = response.bytes().await?;
         let doc = Html::parse_document(std::str::from_utf8(&content[..])?);
 
         // let item_sel = &Selector::parse("table.torrent-list > tbody > tr")?;
@@ -341,7 +444,7 @@ impl Source for NyaaHtmlSource {
             // 6th word in pagination description contains total number of results
             if let Some(num_results_str) = pagination.inner_html().split(' ').nth(5) {
                 if let Ok(num_results) = num_results_str.parse::<usize>() {
-                    last_page = (num_results + 74) / 75;
+                    last_page = num_results.div_ceil(75);
                     total_results = num_results;
                 }
             }
@@ -351,7 +454,7 @@ impl Source for NyaaHtmlSource {
             .select(item_sel)
             .filter_map(|e| {
                 let cat_str = attr(e, icon_sel, "href");
-                let cat_str = cat_str.split('=').last().unwrap_or("");
+                let cat_str = cat_str.split('=').next_back().unwrap_or("");
                 let cat = Self::info().entry_from_str(cat_str);
                 let category = cat.id;
                 let icon = cat.icon.clone();
@@ -359,7 +462,7 @@ impl Source for NyaaHtmlSource {
                 let torrent = attr(e, torrent_sel, "href");
                 let id = torrent
                     .split('/')
-                    .last()?
+                    .next_back()?
                     .split('.')
                     .next()?
                     .parse::<usize>()
@@ -415,6 +518,7 @@ impl Source for NyaaHtmlSource {
                 Some(Item {
                     id,
                     date,
+                    timestamp: date_time.timestamp(),
                     seeders,
                     leechers,
                     downloads,
@@ -482,6 +586,15 @@ impl Source for NyaaHtmlSource {
         NyaaHtmlSource::search(client, search, config, extra).await
     }
 
+    async fn details(
+        client: &reqwest::Client,
+        item: &Item,
+        config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        let nyaa = config.nyaa.to_owned().unwrap_or_default();
+        nyaa_details(client, item, nyaa.timeout).await
+    }
+
     fn info() -> SourceInfo {
         let cats = cats! {
             "All Categories" => {
@@ -582,3 +695,141 @@ impl Source for NyaaHtmlSource {
         nyaa_table(items.into(), theme, &search.sort, &nyaa.columns)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, util::cache::HttpCache};
+
+    #[test]
+    fn test_parse_nyaa_details() {
+        let content = r##"
+<div class="panel panel-success">
+  <div class="panel-body">
+    <div class="row">
+      <div class="col-md-1">Submitter:</div>
+      <div class="col-md-5"><a class="text-success" href="/user/group">group</a></div>
+    </div>
+    <div class="row">
+      <div class="col-md-1">Info hash:</div>
+      <div class="col-md-5"><kbd>0123456789abcdef</kbd></div>
+    </div>
+  </div>
+</div>
+<div class="panel panel-default">
+  <div markdown-text class="panel-body" id="torrent-description">Line one
+Line two</div>
+</div>
+<div class="panel panel-default">
+  <div class="torrent-file-list panel-body">
+    <ul>
+      <li><a href="" class="folder"><i class="fa fa-folder-open"></i>Show</a>
+        <ul>
+          <li><i class="fa fa-file"></i>Show - 01.mkv <span class="file-size">(1.4 GiB)</span></li>
+        </ul>
+      </li>
+      <li><i class="fa fa-file"></i>readme.txt <span class="file-size">(12 Bytes)</span></li>
+    </ul>
+  </div>
+</div>
+<div class="panel panel-default comment-panel" id="com-1">
+  <div class="panel-body">
+    <div class="col-md-2"><p><a class="text-default" href="/user/someone">someone</a></p></div>
+    <div class="col-md-10 comment">
+      <div class="row comment-details"><a href="#com-1"><small>2024-01-01 12:00 UTC</small></a></div>
+      <div class="row comment-body"><div markdown-text class="comment-content">Thanks!</div></div>
+    </div>
+  </div>
+</div>"##;
+        let details = parse_nyaa_details(content).unwrap();
+        assert_eq!(details.submitter.as_deref(), Some("group"));
+        assert_eq!(details.hash.as_deref(), Some("0123456789abcdef"));
+        assert_eq!(details.description, "Line one\nLine two");
+
+        let files = details
+            .files
+            .iter()
+            .map(|f| (f.name.as_str(), f.size.as_deref(), f.depth, f.folder))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![
+                ("Show", None, 0, true),
+                ("Show - 01.mkv", Some("1.4 GiB"), 1, false),
+                ("readme.txt", Some("12 Bytes"), 0, false),
+            ]
+        );
+
+        assert_eq!(details.comments.len(), 1);
+        assert_eq!(details.comments[0].user, "someone");
+        assert_eq!(details.comments[0].date, "2024-01-01 12:00 UTC");
+        assert_eq!(details.comments[0].text, "Thanks!");
+    }
+
+    #[tokio::test]
+    async fn test_search_cached_page() {
+        let content = r##"
+<div class="pagination-page-info">Displaying results 1-2 out of 2 results.</div>
+<table class="table torrent-list">
+  <tbody>
+    <tr class="success">
+      <td><a href="/?c=1_2" title="Anime - English-translated"></a></td>
+      <td colspan="2"><a href="/view/100" title="[Group] Show - 01 [1080p].mkv">[Group] Show - 01 [1080p].mkv</a></td>
+      <td class="text-center"><a href="/download/100.torrent"></a><a href="magnet:?xt=urn:btih:aaaa"></a></td>
+      <td class="text-center">1.4 GiB</td>
+      <td class="text-center">2024-01-01 12:00</td>
+      <td class="text-center">120</td>
+      <td class="text-center">4</td>
+      <td class="text-center">900</td>
+    </tr>
+    <tr class="danger">
+      <td><a href="/?c=1_2" title="Anime - English-translated"></a></td>
+      <td colspan="2"><a href="/view/99" title="Show - 01">Show - 01</a></td>
+      <td class="text-center"><a href="/download/99.torrent"></a><a href="magnet:?xt=urn:btih:bbbb"></a></td>
+      <td class="text-center">350.2 MiB</td>
+      <td class="text-center">2024-01-01 11:00</td>
+      <td class="text-center">3</td>
+      <td class="text-center">0</td>
+      <td class="text-center">15</td>
+    </tr>
+  </tbody>
+</table>"##;
+        let dir = std::env::temp_dir().join(format!("nyaa-scraper-test-{}", std::process::id()));
+        let search = SearchQuery {
+            query: "show".to_owned(),
+            page: 1,
+            ..Default::default()
+        };
+        let url = Url::parse("https://nyaa.si/?q=show&c=0_0&f=0&p=1&s=id&o=desc&u=").unwrap();
+        HttpCache::new(60)
+            .with_dir(dir.clone(), false)
+            .put(&url, content.as_bytes());
+
+        // Offline never makes a request, so the page can only come from the cache
+        let mut extra: SourceExtraConfig = Config::default().into();
+        extra.cache = HttpCache::new(60).with_dir(dir.clone(), true);
+        let client = reqwest::Client::new();
+        let res = NyaaHtmlSource::search(&client, &search, &SourceConfig::default(), &extra).await;
+        let _ = std::fs::remove_dir_all(dir);
+        let Ok(SourceResponse::Results(res)) = res else {
+            panic!("Failed to load the cached page");
+        };
+
+        assert_eq!((res.total_results, res.last_page), (2, 1));
+        let items = res
+            .items
+            .iter()
+            .map(|i| (i.id.as_str(), i.seeders, i.size.as_str(), i.item_type))
+            .collect::<Vec<_>>();
+        assert!(
+            items
+                == vec![
+                    ("nyaa-100", 120, "1.4 GB", ItemType::Trusted),
+                    ("nyaa-99", 3, "350.2 MB", ItemType::Remake),
+                ]
+        );
+        assert_eq!(res.items[0].title, "[Group] Show - 01 [1080p].mkv");
+        assert_eq!(res.items[0].magnet_link, "magnet:?xt=urn:btih:aaaa");
+        assert_eq!(res.items[1].post_link, "https://nyaa.si/view/99");
+    }
+}
@@ -17,7 +17,7 @@ use super::{
     add_protocol, nyaa_html::NyaaSort, Item, ItemType, Source, SourceExtraConfig, SourceResponse,
 };
 
-type ExtensionMap = BTreeMap<String, Vec<Extension>>;
+pub type ExtensionMap = BTreeMap<String, Vec<Extension>>;
 
 pub fn get_ext_value<T: Default + FromStr>(ext_map: &ExtensionMap, key: &str) -> T {
     ext_map
@@ -42,6 +42,23 @@ pub fn sort_items(items: &mut [Item], sort: SelectedSort) {
     }
 }
 
+pub fn format_date(date_time: DateTime<Local>, extra: &SourceExtraConfig) -> String {
+    let date_format = extra
+        .date_format
+        .to_owned()
+        .unwrap_or("%Y-%m-%d %H:%M".to_owned());
+
+    if extra.relative_date.unwrap_or(false) {
+        util::conv::to_relative_date(date_time, extra.relative_date_short.unwrap_or(false))
+    } else {
+        let mut newstr = String::new();
+        if write!(newstr, "{}", date_time.format(&date_format)).is_err() {
+            newstr = format!("Invalid format string: `{}`", date_format);
+        }
+        newstr
+    }
+}
+
 pub async fn search_rss<S: Source>(
     base_url: String,
     timeout: Option<u64>,
@@ -69,14 +86,13 @@ pub async fn search_rss<S: Source>(
     if let Some(timeout) = timeout {
         request = request.timeout(Duration::from_secs(timeout));
     }
-    let response = request.send().await?;
-    let code = response.status().as_u16();
+    let (status, bytes) = extra.cache.fetch(client, request).await?;
+    let code = status.as_u16();
     if code != StatusCode::OK {
         // Throw error if response code is not OK
         return Err(format!("{}\nInvalid response code: {}", url, code).into());
     }
 
-    let bytes = response.bytes().await?;
     let channel = Channel::read_from(&bytes[..])?;
 
     let mut items: Vec<Item> = channel
@@ -110,24 +126,12 @@ pub async fn search_rss<S: Source>(
                 (_, true) => ItemType::Remake,
                 _ => ItemType::None,
             };
-            let date_format = extra
-                .date_format
-                .to_owned()
-                .unwrap_or("%Y-%m-%d %H:%M".to_owned());
-
-            let date = if extra.relative_date.unwrap_or(false) {
-                util::conv::to_relative_date(date_time, extra.relative_date_short.unwrap_or(false))
-            } else {
-                let mut newstr = String::new();
-                if write!(newstr, "{}", date_time.format(&date_format)).is_err() {
-                    newstr = format!("Invalid format string: `{}`", date_format);
-                }
-                newstr
-            };
+            let date = format_date(date_time, extra);
 
             Some(Item {
                 id: format!("nyaa-{}", id_usize),
                 date,
+                timestamp: date_time.timestamp(),
                 seeders: get_ext_value(ext, "seeders"),
                 leechers: get_ext_value(ext, "leechers"),
                 downloads: get_ext_value(ext, "downloads"),
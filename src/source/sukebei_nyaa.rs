@@ -27,8 +27,9 @@ use crate::{
 use super::SourceExtraConfig;
 use super::{
     add_protocol,
-    nyaa_html::{nyaa_table, NyaaColumns, NyaaFilter, NyaaSort},
-    nyaa_rss, Item, ItemType, ResultTable, Source, SourceConfig, SourceInfo, SourceResponse,
+    nyaa_html::{nyaa_details, nyaa_table, NyaaColumns, NyaaFilter, NyaaSort},
+    nyaa_rss, Item, ItemDetails, ItemType, ResultTable, Source, SourceConfig, SourceInfo,
+    SourceResponse,
 };
 
 #[derive(Serialize, Deserialize, Clone, Copy, Default)]
@@ -178,13 +179,12 @@ impl Source for SukebeiHtmlSource {
         if let Some(timeout) = sukebei.timeout {
             request = request.timeout(Duration::from_secs(timeout));
         }
-        let response = request.send().await?;
-        if response.status() != StatusCode::OK {
+        let (status, content) = extra.cache.fetch(client, request).await?;
+        if status != StatusCode::OK {
             // Throw error if response code is not OK
-            let code = response.status().as_u16();
+            let code = status.as_u16();
             return Err(format!("{}\nInvalid response code: {}", url_query, code).into());
         }
-        let content = response.bytes().await?;
         let doc = Html::parse_document(std::str::from_utf8(&content[..])?);
 
         let item_sel = &sel!("table.torrent-list > tbody > tr")?;
@@ -206,7 +206,7 @@ impl Source for SukebeiHtmlSource {
             // 6th word in pagination description contains total number of results
             if let Some(num_results_str) = pagination.inner_html().split(' ').nth(5) {
                 if let Ok(num_results) = num_results_str.parse::<usize>() {
-                    last_page = (num_results + 74) / 75;
+                    last_page = num_results.div_ceil(75);
                     total_results = num_results;
                 }
             }
@@ -216,7 +216,7 @@ impl Source for SukebeiHtmlSource {
             .select(item_sel)
             .filter_map(|e| {
                 let cat_str = attr(e, icon_sel, "href");
-                let cat_str = cat_str.split('=').last().unwrap_or("");
+                let cat_str = cat_str.split('=').next_back().unwrap_or("");
                 let cat = Self::info().entry_from_str(cat_str);
                 let category = cat.id;
                 let icon = cat.icon.clone();
@@ -226,7 +226,7 @@ impl Source for SukebeiHtmlSource {
                     .join(&attr(e, title_sel, "href"))
                     .map(Into::into)
                     .unwrap_or("null".to_owned());
-                let id = post_link.split('/').last()?.parse::<usize>().ok()?;
+                let id = post_link.split('/').next_back()?.parse::<usize>().ok()?;
                 let id = format!("sukebei-{}", id);
                 let file_name = format!("{}.torrent", id);
 
@@ -273,6 +273,7 @@ impl Source for SukebeiHtmlSource {
                 Some(Item {
                     id,
                     date,
+                    timestamp: date_time.timestamp(),
                     seeders,
                     leechers,
                     downloads,
@@ -315,6 +316,15 @@ impl Source for SukebeiHtmlSource {
         SukebeiHtmlSource::search(client, search, config, extra).await
     }
 
+    async fn details(
+        client: &reqwest::Client,
+        item: &Item,
+        config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        let sukebei = config.sukebei.to_owned().unwrap_or_default();
+        nyaa_details(client, item, sukebei.timeout).await
+    }
+
     fn info() -> SourceInfo {
         let cats = cats! {
             "All Categories" => {
@@ -23,15 +23,15 @@ use crate::{
     theme::Theme,
     util::{
         colors::color_to_tui,
-        conv::{shorten_number, to_bytes},
-        html::{as_type, attr, inner},
+        conv::{get_hash, shorten_number, to_bytes, to_timestamp},
+        html::{as_type, attr, inner, labeled, text},
     },
     widget::sort::{SelectedSort, SortDir},
 };
 
 use super::{
-    add_protocol, Item, ItemType, Source, SourceConfig, SourceExtraConfig, SourceInfo,
-    SourceResponse,
+    add_protocol, DetailsFile, Item, ItemDetails, ItemType, Source, SourceConfig,
+    SourceExtraConfig, SourceInfo, SourceResponse,
 };
 
 #[derive(Serialize, Deserialize, Clone, Copy, Default)]
@@ -295,7 +295,7 @@ fn get_url(
     Ok((base_url, url))
 }
 
-async fn try_get_content(
+pub async fn try_get_content(
     client: &reqwest::Client,
     timeout: Option<u64>,
     url: &Url,
@@ -365,6 +365,154 @@ fn get_status_color(status: String) -> Option<Color> {
     }
 }
 
+/// Read the items and pages from a page with the results table
+fn parse_results(
+    content: &str,
+    base_url: &Url,
+) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+    // Results table found, can start parsing
+    let doc = Html::parse_document(content);
+
+    let item_sel = &sel!("div.tgxtablerow")?;
+    let title_sel = &sel!("div.tgxtablecell:nth-of-type(4) > div > a.txlight")?;
+    let imdb_sel = &sel!("div.tgxtablecell:nth-of-type(4) > div > a:last-of-type")?;
+    let cat_sel = &sel!("div.tgxtablecell:nth-of-type(1) > a")?;
+    let date_sel = &sel!("div.tgxtablecell:nth-of-type(12)")?;
+    let seed_sel = &sel!("div.tgxtablecell:nth-of-type(11) > span > font:first-of-type > b")?;
+    let leech_sel = &sel!("div.tgxtablecell:nth-of-type(11) > span > font:last-of-type > b")?;
+    let size_sel = &sel!("div.tgxtablecell:nth-of-type(8) > span")?;
+    let trust_sel = &sel!("div.tgxtablecell:nth-of-type(2) > i")?;
+    let views_sel = &sel!("div.tgxtablecell:nth-of-type(10) > span > font > b")?;
+    let torrent_sel = &sel!("div.tgxtablecell:nth-of-type(5) > a:first-of-type")?;
+    let magnet_sel = &sel!("div.tgxtablecell:nth-of-type(5) > a:last-of-type")?;
+    let lang_sel = &sel!("div.tgxtablecell:nth-of-type(3) > img")?;
+    let uploader_sel = &sel!("div.tgxtablecell:nth-of-type(7) > span > a > span")?;
+    let uploader_status_sel = &sel!("div.tgxtablecell:nth-of-type(7) > span > a")?;
+
+    let pagination_sel = &sel!("div#filterbox2 > span.badge")?;
+
+    let items = doc
+        .select(item_sel)
+        .filter_map(|e| {
+            let cat_id = attr(e, cat_sel, "href")
+                .rsplit_once('=')
+                .map(|v| v.1)
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or_default();
+            let icon = TorrentGalaxyHtmlSource::info().entry_from_id(cat_id).icon;
+            let date: String = e
+                .select(date_sel)
+                .next()
+                .map(|e| e.text().collect())
+                .unwrap_or_default();
+            let seeders = as_type(inner(e, seed_sel, "0")).unwrap_or_default();
+            let leechers = as_type(inner(e, leech_sel, "0")).unwrap_or_default();
+            let views = as_type(inner(e, views_sel, "0")).unwrap_or_default();
+            let mut size = inner(e, size_sel, "0 MB");
+
+            // Convert numbers like 1,015 KB => 1.01 MB
+            if let Some((x, y)) = size.split_once(',') {
+                if let Some((y, unit)) = y.split_once(' ') {
+                    let y = y.get(0..2).unwrap_or("00");
+                    // find next unit up
+                    let unit = match unit.to_lowercase().as_str() {
+                        "b" => "kB",
+                        "kb" => "MB",
+                        "mb" => "GB",
+                        "gb" => "TB",
+                        _ => "??",
+                    };
+                    size = format!("{}.{} {}", x, y, unit);
+                }
+            }
+
+            let item_type = match e
+                .select(trust_sel)
+                .next()
+                .map(|v| v.value().classes().any(|e| e == "fa-check"))
+                .unwrap_or(false)
+            {
+                true => ItemType::None,
+                false => ItemType::Remake,
+            };
+
+            let torrent_link: String = base_url
+                .join(&attr(e, torrent_sel, "href"))
+                .map(Into::into)
+                .unwrap_or_default();
+            let magnet_link = attr(e, magnet_sel, "href");
+            let post_link = attr(e, title_sel, "href");
+
+            let binding = post_link.split('/').collect::<Vec<&str>>();
+            let id = format!("tgx-{}", binding.get(2)?);
+
+            let post_link = base_url
+                .join(&post_link)
+                .map(Into::into)
+                .unwrap_or_default();
+            let hash = torrent_link.split('/').nth(4).unwrap_or("unknown");
+            let file_name = format!("{}.torrent", hash);
+
+            let imdb = attr(e, imdb_sel, "href");
+            let imdb = match imdb.rsplit_once('=').map(|r| r.1).unwrap_or("") {
+                "tt2000000" => "", // For some reason, most XXX titles use this ID
+                i => i,
+            };
+
+            let extra: HashMap<String, String> = collection![
+                "uploader".to_owned() => inner(e, uploader_sel, "???"),
+                "uploader_status".to_owned() => attr(e, uploader_status_sel, "title"),
+                "lang".to_owned() => attr(e, lang_sel, "title"),
+                "imdb".to_owned() => imdb.to_owned(),
+            ];
+
+            Some(Item {
+                id,
+                timestamp: to_timestamp(&date),
+                date,
+                seeders,
+                leechers,
+                downloads: views,
+                bytes: to_bytes(&size),
+                size,
+                title: attr(e, title_sel, "title"),
+                torrent_link,
+                magnet_link,
+                post_link,
+                file_name,
+                category: cat_id,
+                icon,
+                item_type,
+                extra,
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<Item>>();
+
+    let mut last_page = 50;
+    let mut total_results = 2500;
+    if let Some(pagination) = doc.select(pagination_sel).next() {
+        if let Ok(num_results) = pagination
+            .inner_html()
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<usize>()
+        {
+            if num_results != 0 || items.is_empty() {
+                last_page = num_results.div_ceil(50);
+                total_results = num_results;
+            }
+        }
+    }
+
+    Ok(SourceResponse::Results(ResultResponse {
+        items,
+        total_results,
+        last_page,
+    }))
+}
+
 impl Source for TorrentGalaxyHtmlSource {
     async fn filter(
         client: &reqwest::Client,
@@ -395,13 +543,18 @@ impl Source for TorrentGalaxyHtmlSource {
         client: &reqwest::Client,
         search: &SearchQuery,
         config: &SourceConfig,
-        _extra: &SourceExtraConfig,
+        extra: &SourceExtraConfig,
     ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
         let tgx = config.tgx.to_owned().unwrap_or_default();
         let (base_url, url) = get_url(tgx.base_url.clone(), search)?;
 
         let table_sel = &sel!(".tgxtable")?;
 
+        // Only pages with the results table are cached, not the checkpoint or captcha
+        if let Some(content) = extra.cache.get(&url)? {
+            return parse_results(&String::from_utf8(content)?, &base_url);
+        }
+
         // First try checkpoint
         let content = try_get_content(client, tgx.timeout, &url).await?;
         if Html::parse_document(&content).select(table_sel).count() == 0 {
@@ -450,146 +603,8 @@ impl Source for TorrentGalaxyHtmlSource {
                 return Ok(SourceResponse::Captcha(image));
             }
         }
-
-        // Results table found, can start parsing
-        let doc = Html::parse_document(&content);
-
-        let item_sel = &sel!("div.tgxtablerow")?;
-        let title_sel = &sel!("div.tgxtablecell:nth-of-type(4) > div > a.txlight")?;
-        let imdb_sel = &sel!("div.tgxtablecell:nth-of-type(4) > div > a:last-of-type")?;
-        let cat_sel = &sel!("div.tgxtablecell:nth-of-type(1) > a")?;
-        let date_sel = &sel!("div.tgxtablecell:nth-of-type(12)")?;
-        let seed_sel = &sel!("div.tgxtablecell:nth-of-type(11) > span > font:first-of-type > b")?;
-        let leech_sel = &sel!("div.tgxtablecell:nth-of-type(11) > span > font:last-of-type > b")?;
-        let size_sel = &sel!("div.tgxtablecell:nth-of-type(8) > span")?;
-        let trust_sel = &sel!("div.tgxtablecell:nth-of-type(2) > i")?;
-        let views_sel = &sel!("div.tgxtablecell:nth-of-type(10) > span > font > b")?;
-        let torrent_sel = &sel!("div.tgxtablecell:nth-of-type(5) > a:first-of-type")?;
-        let magnet_sel = &sel!("div.tgxtablecell:nth-of-type(5) > a:last-of-type")?;
-        let lang_sel = &sel!("div.tgxtablecell:nth-of-type(3) > img")?;
-        let uploader_sel = &sel!("div.tgxtablecell:nth-of-type(7) > span > a > span")?;
-        let uploader_status_sel = &sel!("div.tgxtablecell:nth-of-type(7) > span > a")?;
-
-        let pagination_sel = &sel!("div#filterbox2 > span.badge")?;
-
-        let items = doc
-            .select(item_sel)
-            .filter_map(|e| {
-                let cat_id = attr(e, cat_sel, "href")
-                    .rsplit_once('=')
-                    .map(|v| v.1)
-                    .and_then(|v| v.parse::<usize>().ok())
-                    .unwrap_or_default();
-                let icon = Self::info().entry_from_id(cat_id).icon;
-                let date = e
-                    .select(date_sel)
-                    .nth(0)
-                    .map(|e| e.text().collect())
-                    .unwrap_or_default();
-                let seeders = as_type(inner(e, seed_sel, "0")).unwrap_or_default();
-                let leechers = as_type(inner(e, leech_sel, "0")).unwrap_or_default();
-                let views = as_type(inner(e, views_sel, "0")).unwrap_or_default();
-                let mut size = inner(e, size_sel, "0 MB");
-
-                // Convert numbers like 1,015 KB => 1.01 MB
-                if let Some((x, y)) = size.split_once(',') {
-                    if let Some((y, unit)) = y.split_once(' ') {
-                        let y = y.get(0..2).unwrap_or("00");
-                        // find next unit up
-                        let unit = match unit.to_lowercase().as_str() {
-                            "b" => "kB",
-                            "kb" => "MB",
-                            "mb" => "GB",
-                            "gb" => "TB",
-                            _ => "??",
-                        };
-                        size = format!("{}.{} {}", x, y, unit);
-                    }
-                }
-
-                let item_type = match e
-                    .select(trust_sel)
-                    .nth(0)
-                    .map(|v| v.value().classes().any(|e| e == "fa-check"))
-                    .unwrap_or(false)
-                {
-                    true => ItemType::None,
-                    false => ItemType::Remake,
-                };
-
-                let torrent_link: String = base_url
-                    .join(&attr(e, torrent_sel, "href"))
-                    .map(Into::into)
-                    .unwrap_or_default();
-                let magnet_link = attr(e, magnet_sel, "href");
-                let post_link = attr(e, title_sel, "href");
-
-                let binding = post_link.split('/').collect::<Vec<&str>>();
-                let id = format!("tgx-{}", binding.get(2)?);
-
-                let post_link = base_url
-                    .join(&post_link)
-                    .map(Into::into)
-                    .unwrap_or_default();
-                let hash = torrent_link.split('/').nth(4).unwrap_or("unknown");
-                let file_name = format!("{}.torrent", hash);
-
-                let imdb = attr(e, imdb_sel, "href");
-                let imdb = match imdb.rsplit_once('=').map(|r| r.1).unwrap_or("") {
-                    "tt2000000" => "", // For some reason, most XXX titles use this ID
-                    i => i,
-                };
-
-                let extra: HashMap<String, String> = collection![
-                    "uploader".to_owned() => inner(e, uploader_sel, "???"),
-                    "uploader_status".to_owned() => attr(e, uploader_status_sel, "title"),
-                    "lang".to_owned() => attr(e, lang_sel, "title"),
-                    "imdb".to_owned() => imdb.to_owned(),
-                ];
-
-                Some(Item {
-                    id,
-                    date,
-                    seeders,
-                    leechers,
-                    downloads: views,
-                    bytes: to_bytes(&size),
-                    size,
-                    title: attr(e, title_sel, "title"),
-                    torrent_link,
-                    magnet_link,
-                    post_link,
-                    file_name,
-                    category: cat_id,
-                    icon,
-                    item_type,
-                    extra,
-                })
-            })
-            .collect::<Vec<Item>>();
-
-        let mut last_page = 50;
-        let mut total_results = 2500;
-        if let Some(pagination) = doc.select(pagination_sel).nth(0) {
-            if let Ok(num_results) = pagination
-                .inner_html()
-                .chars()
-                .filter(|c| c.is_ascii_digit())
-                .collect::<String>()
-                .parse::<usize>()
-            {
-                if num_results != 0 || items.is_empty() {
-                    last_page = (num_results + 49) / 50;
-                    total_results = num_results;
-                }
-            }
-        }
-
-        Ok(SourceResponse::Results(ResultResponse {
-            items,
-            total_results,
-            last_page,
-        }))
+        extra.cache.put(&url, content.as_bytes());
+        parse_results(&content, &base_url)
     }
 
     async fn solve(
@@ -651,6 +666,48 @@ impl Source for TorrentGalaxyHtmlSource {
         TorrentGalaxyHtmlSource::search(client, search, config, extra).await
     }
 
+    async fn details(
+        client: &reqwest::Client,
+        item: &Item,
+        config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        let tgx = config.tgx.to_owned().unwrap_or_default();
+        let url = Url::parse(&item.post_link)?;
+        let content = try_get_content(client, tgx.timeout, &url).await?;
+        let doc = Html::parse_document(&content);
+        let root = doc.root_element();
+
+        let label_sel = &sel!("div.tprow > div:first-child")?;
+        let desc_sel = &sel!("div#description")?;
+        let file_sel = &sel!("td.table_col1")?;
+        let file_size_sel = &sel!("td.table_col2")?;
+
+        // Files are listed with their full path rather than as a tree
+        let files = root
+            .select(file_sel)
+            .zip(root.select(file_size_sel))
+            .map(|(name, size)| DetailsFile {
+                name: text(name),
+                size: Some(text(size)),
+                ..Default::default()
+            })
+            .collect();
+
+        // Comments are loaded separately by the page's scripts, so they aren't available here
+        Ok(ItemDetails {
+            description: root.select(desc_sel).next().map(text).unwrap_or_default(),
+            hash: get_hash(item.magnet_link.clone())
+                .or_else(|| labeled(root, label_sel, "Info Hash:")),
+            submitter: item
+                .extra
+                .get("uploader")
+                .cloned()
+                .or_else(|| labeled(root, label_sel, "Added By:")),
+            files,
+            comments: vec![],
+        })
+    }
+
     fn info() -> SourceInfo {
         let cats = cats! {
             "All Categories" => { 0 => ("---", "All Categories", "AllCategories", source.tgx.cat.all_categories); }
@@ -776,6 +833,7 @@ impl Source for TorrentGalaxyHtmlSource {
             ResultColumn::Normal("  󰈈".to_owned(), Constraint::Length(5)),
         ]);
         let mut binding = header.get_binding();
+        let mut sorts = header.get_sorts(|s| s as usize);
         let align = [
             Alignment::Left,
             Alignment::Left,
@@ -841,12 +899,14 @@ impl Source for TorrentGalaxyHtmlSource {
                 })
                 .collect::<Vec<ResultRow>>();
             binding = cond_vec!(cols ; binding);
+            sorts = cond_vec!(cols ; sorts);
         }
 
         ResultTable {
             headers,
             rows,
             binding,
+            sorts,
         }
     }
 }
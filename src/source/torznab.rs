@@ -0,0 +1,562 @@
+use std::{cmp::Ordering, error::Error, time::Duration};
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::{Alignment, Constraint},
+    style::Stylize as _,
+};
+use reqwest::StatusCode;
+use rss::Channel;
+use serde::{Deserialize, Serialize};
+use strum::{FromRepr, VariantArray};
+use urlencoding::encode;
+
+use crate::{
+    cats,
+    results::{ResultColumn, ResultHeader, ResultResponse, ResultRow, ResultTable},
+    sync::SearchQuery,
+    theme::Theme,
+    util::conv::{get_hash, shorten_number, to_size},
+    widget::sort::{SelectedSort, SortDir},
+};
+
+use super::{
+    add_protocol,
+    nyaa_rss::{format_date, ExtensionMap},
+    Item, ItemDetails, Source, SourceConfig, SourceExtraConfig, SourceInfo, SourceResponse,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TorznabConfig {
+    /// Full url of the Torznab api, e.g. `http://localhost:9696/1/api` for Prowlarr
+    pub base_url: String,
+    pub api_key: String,
+    pub default_sort: TorznabSort,
+    pub default_sort_dir: SortDir,
+    pub default_filter: TorznabFilter,
+    pub default_category: String,
+    pub default_search: String,
+    /// Number of results requested per page
+    pub limit: Option<usize>,
+    pub timeout: Option<u64>,
+}
+
+impl Default for TorznabConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            api_key: String::new(),
+            default_sort: TorznabSort::Date,
+            default_sort_dir: SortDir::Desc,
+            default_filter: TorznabFilter::NoFilter,
+            default_category: "AllCategories".to_owned(),
+            default_search: Default::default(),
+            limit: None,
+            timeout: None,
+        }
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, strum::Display, Clone, Copy, VariantArray, PartialEq, Eq, FromRepr,
+)]
+#[repr(usize)]
+pub enum TorznabSort {
+    Date = 0,
+    Seeders = 1,
+    Leechers = 2,
+    Size = 3,
+    Grabs = 4,
+    Name = 5,
+}
+
+#[derive(
+    Serialize, Deserialize, strum::Display, Clone, Copy, VariantArray, PartialEq, Eq, FromRepr,
+)]
+pub enum TorznabFilter {
+    #[allow(clippy::enum_variant_names)]
+    #[strum(serialize = "NoFilter")]
+    NoFilter = 0,
+    #[strum(serialize = "Freeleech")]
+    Freeleech = 1,
+    #[strum(serialize = "Hide dead")]
+    HideDead = 2,
+}
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// All values of `<torznab:attr name="..." value="..."/>` with the given name
+fn attrs<'a>(ext: Option<&'a ExtensionMap>, name: &'a str) -> impl Iterator<Item = &'a str> {
+    ext.and_then(|e| e.get("attr"))
+        .into_iter()
+        .flatten()
+        .filter(move |a| a.attrs.get("name").is_some_and(|n| n == name))
+        .filter_map(|a| a.attrs.get("value").map(String::as_str))
+}
+
+fn attr<T: std::str::FromStr>(ext: Option<&ExtensionMap>, name: &str) -> Option<T> {
+    attrs(ext, name).find_map(|v| v.parse().ok())
+}
+
+/// Torznab reports errors like bad api keys as `<error code=".." description=".."/>`
+fn api_error(content: &str) -> Option<String> {
+    let error = &content[content.find("<error")?..];
+    let desc = error.split_once("description=\"")?.1.split_once('"')?.0;
+    Some(desc.to_owned())
+}
+
+type DatedItem = (DateTime<Local>, Item);
+
+fn sort_items(items: &mut [DatedItem], sort: SelectedSort) {
+    let f: fn(&DatedItem, &DatedItem) -> Ordering = match TorznabSort::from_repr(sort.sort) {
+        Some(TorznabSort::Seeders) => |a, b| b.1.seeders.cmp(&a.1.seeders),
+        Some(TorznabSort::Leechers) => |a, b| b.1.leechers.cmp(&a.1.leechers),
+        Some(TorznabSort::Size) => |a, b| b.1.bytes.cmp(&a.1.bytes),
+        Some(TorznabSort::Grabs) => |a, b| b.1.downloads.cmp(&a.1.downloads),
+        Some(TorznabSort::Name) => |a, b| b.1.title.cmp(&a.1.title),
+        _ => |a, b| b.0.cmp(&a.0),
+    };
+    items.sort_by(f);
+    if sort.dir == SortDir::Asc {
+        items.reverse();
+    }
+}
+
+/// Build items out of a Torznab response. Sorting and filtering are applied
+/// locally, since the api has no parameters for them, so they only cover this page.
+pub fn parse_torznab(
+    content: &[u8],
+    search: &SearchQuery,
+    limit: usize,
+    extra: &SourceExtraConfig,
+) -> Result<ResultResponse, Box<dyn Error + Send + Sync>> {
+    let channel = match Channel::read_from(content) {
+        Ok(channel) => channel,
+        Err(e) => {
+            return Err(match api_error(&String::from_utf8_lossy(content)) {
+                Some(desc) => format!("Torznab error: {}", desc).into(),
+                None => e.into(),
+            })
+        }
+    };
+    let info = TorznabSource::info();
+
+    let mut items: Vec<DatedItem> = channel
+        .items
+        .iter()
+        .filter_map(|item| {
+            let ext = item.extensions().get("torznab");
+            let title = item.title()?.to_owned();
+            let enclosure = item.enclosure();
+            let links = [item.link(), enclosure.map(|e| e.url())];
+
+            let magnet_link = attrs(ext, "magneturl")
+                .chain(links.into_iter().flatten())
+                .find(|l| l.starts_with("magnet:"))
+                .map(ToOwned::to_owned);
+            let torrent_link = links
+                .into_iter()
+                .flatten()
+                .find(|l| !l.starts_with("magnet:"))
+                .unwrap_or_default()
+                .to_owned();
+            let hash = attr::<String>(ext, "infohash")
+                .or_else(|| magnet_link.clone().and_then(get_hash))
+                .map(|h| h.to_lowercase());
+            let magnet_link = magnet_link
+                .or_else(|| {
+                    hash.as_ref()
+                        .map(|h| format!("magnet:?xt=urn:btih:{}&dn={}", h, encode(&title)))
+                })
+                .unwrap_or_default();
+            let guid = item.guid().map(|g| g.value().to_owned());
+            let post_link = item
+                .comments()
+                .map(ToOwned::to_owned)
+                .or(guid.clone())
+                .unwrap_or_default();
+            let key = hash.or(guid).unwrap_or_else(|| torrent_link.clone());
+
+            let bytes = attr::<usize>(ext, "size")
+                .or_else(|| enclosure.and_then(|e| e.length().parse().ok()))
+                .unwrap_or_default();
+            let seeders = attr::<u32>(ext, "seeders").unwrap_or_default();
+            let leechers = attr::<u32>(ext, "leechers").unwrap_or_else(|| {
+                attr::<u32>(ext, "peers")
+                    .unwrap_or_default()
+                    .saturating_sub(seeders)
+            });
+            // Indexers list both the parent (5000) and sub category (5070), prefer the most specific
+            let cat = attrs(ext, "category")
+                .filter_map(|c| info.find_category(c).ok())
+                .max_by_key(|c| c.id)
+                .unwrap_or_else(|| info.cats[0].entries[0].clone());
+
+            let date_time = DateTime::parse_from_rfc2822(item.pub_date().unwrap_or_default())
+                .unwrap_or_default()
+                .with_timezone(&Local);
+            let mut item = Item {
+                id: format!("torznab-{}", key),
+                date: format_date(date_time, extra),
+                timestamp: date_time.timestamp(),
+                seeders,
+                leechers,
+                downloads: attr(ext, "grabs").unwrap_or_default(),
+                size: to_size(bytes),
+                bytes,
+                file_name: format!("{}.torrent", key.rsplit('/').next().unwrap_or_default()),
+                title,
+                torrent_link,
+                magnet_link,
+                post_link,
+                category: cat.id,
+                icon: cat.icon,
+                ..Default::default()
+            };
+            if attr::<f64>(ext, "downloadvolumefactor") == Some(0.) {
+                item.extra.insert("freeleech".to_owned(), "true".to_owned());
+            }
+            Some((date_time, item))
+        })
+        .collect();
+
+    let response = channel
+        .extensions()
+        .get("newznab")
+        .or(channel.extensions().get("torznab"))
+        .and_then(|e| e.get("response"))
+        .and_then(|r| r.first());
+    let total = response
+        .and_then(|r| r.attrs.get("total"))
+        .and_then(|t| t.parse::<usize>().ok());
+    let limit = limit.max(1);
+    let page = search.page.max(1);
+    let (total_results, last_page) = match total {
+        Some(total) => (total, total.div_ceil(limit).max(1)),
+        // Jackett ignores the offset and returns everything at once
+        None if items.len() >= limit => ((page + 1) * limit, page + 1),
+        None => ((page - 1) * limit + items.len(), page),
+    };
+
+    items.retain(|(_, i)| match TorznabFilter::from_repr(search.filter) {
+        Some(TorznabFilter::Freeleech) => i.extra.contains_key("freeleech"),
+        Some(TorznabFilter::HideDead) => i.seeders > 0,
+        _ => true,
+    });
+    sort_items(&mut items, search.sort);
+    Ok(ResultResponse {
+        items: items.into_iter().map(|(_, i)| i).collect(),
+        total_results,
+        last_page,
+    })
+}
+
+pub struct TorznabSource;
+
+impl Source for TorznabSource {
+    async fn search(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        let torznab = config
+            .torznab
+            .to_owned()
+            .ok_or("The Torznab source is not configured, add [source.torznab] to config.toml")?;
+        let limit = torznab.limit.unwrap_or(DEFAULT_LIMIT);
+        let mut url = add_protocol(torznab.base_url, true)?;
+
+        let cat = match search.category {
+            0 => "".to_owned(),
+            x => format!("&cat={}", x),
+        };
+        let query = format!(
+            "t=search&apikey={}&q={}&offset={}&limit={}&extended=1{}",
+            encode(&torznab.api_key),
+            encode(&search.query),
+            (search.page.max(1) - 1) * limit,
+            limit,
+            cat,
+        );
+        url.set_query(Some(&query));
+
+        let mut request = client.get(url.to_owned());
+        if let Some(timeout) = torznab.timeout {
+            request = request.timeout(Duration::from_secs(timeout));
+        }
+        let (status, bytes) = extra.cache.fetch(client, request).await?;
+        if status != StatusCode::OK {
+            // Throw error if response code is not OK, without leaking the api key
+            let code = status.as_u16();
+            url.set_query(None);
+            return Err(format!("{}\nInvalid response code: {}", url, code).into());
+        }
+        Ok(SourceResponse::Results(parse_torznab(
+            &bytes, search, limit, extra,
+        )?))
+    }
+
+    async fn sort(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        TorznabSource::search(client, search, config, extra).await
+    }
+
+    async fn filter(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        TorznabSource::search(client, search, config, extra).await
+    }
+
+    async fn categorize(
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        TorznabSource::search(client, search, config, extra).await
+    }
+
+    async fn solve(
+        _solution: String,
+        client: &reqwest::Client,
+        search: &SearchQuery,
+        config: &SourceConfig,
+        extra: &SourceExtraConfig,
+    ) -> Result<SourceResponse, Box<dyn Error + Send + Sync>> {
+        TorznabSource::search(client, search, config, extra).await
+    }
+
+    async fn details(
+        _client: &reqwest::Client,
+        _item: &Item,
+        _config: &SourceConfig,
+    ) -> Result<ItemDetails, Box<dyn Error + Send + Sync>> {
+        Err("Details are not available for the Torznab source".into())
+    }
+
+    fn info() -> SourceInfo {
+        let cats = cats! {
+            "All Categories" => { 0 => ("---", "All Categories", "AllCategories", fg); }
+            "Movies" => {2000 => ("Mov", "All Movies", "Movies", info);
+                2030 => ("SdM", "SD Movies", "SdMovies", info);
+                2040 => ("HdM", "HD Movies", "HdMovies", info);
+                2045 => ("4kM", "UHD Movies", "UhdMovies", info);}
+            "TV" => {5000 => ("TV ", "All TV", "TV", success);
+                5030 => ("SdT", "SD TV", "SdTV", success);
+                5040 => ("HdT", "HD TV", "HdTV", success);
+                5045 => ("4kT", "UHD TV", "UhdTV", success);
+                5060 => ("Spo", "Sports", "SportsTV", success);
+                5070 => ("Ani", "Anime", "Anime", border_focused_color);}
+            "Audio" => {3000 => ("Aud", "All Audio", "Audio", warning);
+                3010 => ("Mp3", "MP3", "Mp3Audio", warning);
+                3030 => ("Abk", "Audiobooks", "Audiobooks", warning);
+                3040 => ("Los", "Lossless", "LosslessAudio", warning);}
+            "Books" => {7000 => ("Bok", "All Books", "Books", fg);
+                7020 => ("Ebk", "Ebooks", "Ebooks", fg);
+                7030 => ("Com", "Comics", "Comics", fg);}
+            "PC" => {4000 => ("PC ", "All PC", "PC", fg);
+                4050 => ("Gam", "PC Games", "PcGames", fg);}
+            "Other" => {1000 => ("Con", "Console", "Console", fg);
+                8000 => ("Oth", "Other", "Other", fg);
+                6000 => ("XXX", "XXX", "XXX", error);}
+        };
+        SourceInfo {
+            cats,
+            filters: TorznabFilter::VARIANTS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            sorts: TorznabSort::VARIANTS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
+    fn load_config(_config: &mut SourceConfig) {
+        // Needs an api key from the user's own instance, so there is no useful default
+    }
+
+    fn default_category(cfg: &SourceConfig) -> usize {
+        let default = cfg
+            .torznab
+            .as_ref()
+            .map(|c| c.default_category.to_owned())
+            .unwrap_or_default();
+        Self::info().entry_from_cfg(&default).id
+    }
+
+    fn default_sort(cfg: &SourceConfig) -> SelectedSort {
+        cfg.torznab
+            .as_ref()
+            .map(|c| SelectedSort {
+                sort: c.default_sort as usize,
+                dir: c.default_sort_dir,
+            })
+            .unwrap_or_default()
+    }
+
+    fn default_filter(cfg: &SourceConfig) -> usize {
+        cfg.torznab
+            .as_ref()
+            .map(|c| c.default_filter as usize)
+            .unwrap_or_default()
+    }
+
+    fn default_search(cfg: &SourceConfig) -> String {
+        cfg.torznab
+            .as_ref()
+            .map(|c| c.default_search.to_owned())
+            .unwrap_or_default()
+    }
+
+    fn format_table(
+        items: &[Item],
+        search: &SearchQuery,
+        _config: &SourceConfig,
+        theme: &Theme,
+    ) -> ResultTable {
+        let date_width = items
+            .iter()
+            .map(|i| i.date.len())
+            .max()
+            .unwrap_or_default()
+            .max(6) as u16;
+        let header = ResultHeader::new([
+            ResultColumn::Normal("Cat".to_owned(), Constraint::Length(3)),
+            ResultColumn::Normal("Name".to_owned(), Constraint::Min(3)),
+            ResultColumn::Sorted("Size".to_owned(), 9, TorznabSort::Size as u32),
+            ResultColumn::Sorted("Date".to_owned(), date_width, TorznabSort::Date as u32),
+            ResultColumn::Sorted("".to_owned(), 4, TorznabSort::Seeders as u32),
+            ResultColumn::Sorted("".to_owned(), 4, TorznabSort::Leechers as u32),
+            ResultColumn::Sorted("  ".to_owned(), 5, TorznabSort::Grabs as u32),
+        ]);
+        let align = [
+            Alignment::Left,
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Left,
+            Alignment::Right,
+            Alignment::Right,
+            Alignment::Left,
+        ];
+        let rows = items
+            .iter()
+            .map(|item| {
+                ResultRow::new([
+                    item.icon.label.fg((item.icon.color)(theme)),
+                    item.title
+                        .to_owned()
+                        .fg(match item.extra.contains_key("freeleech") {
+                            true => theme.success,
+                            false => theme.fg,
+                        }),
+                    item.size.to_owned().fg(theme.fg),
+                    item.date.to_owned().fg(theme.fg),
+                    item.seeders.to_string().fg(theme.success),
+                    item.leechers.to_string().fg(theme.error),
+                    shorten_number(item.downloads).fg(theme.fg),
+                ])
+                .aligned(align)
+                .fg(theme.fg)
+            })
+            .collect();
+        ResultTable {
+            headers: header.get_row(search.sort.dir, search.sort.sort as u32),
+            rows,
+            binding: header.get_binding(),
+            sorts: header.get_sorts(|s| s as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_torznab() {
+        let content = br#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <title>Prowlarr</title>
+    <link>http://localhost:9696/</link>
+    <description>Prowlarr</description>
+    <item>
+      <title>Show - 01 [1080p]</title>
+      <guid>http://indexer.example/t/1</guid>
+      <comments>http://indexer.example/t/1</comments>
+      <pubDate>Mon, 01 Jan 2024 12:00:00 +0000</pubDate>
+      <link>http://localhost:9696/1/download?id=1</link>
+      <enclosure url="http://localhost:9696/1/download?id=1" length="1503238553" type="application/x-bittorrent"/>
+      <torznab:attr name="category" value="5000"/>
+      <torznab:attr name="category" value="5070"/>
+      <torznab:attr name="seeders" value="12"/>
+      <torznab:attr name="peers" value="15"/>
+      <torznab:attr name="grabs" value="300"/>
+      <torznab:attr name="infohash" value="ABCDEF"/>
+    </item>
+    <item>
+      <title>Movie (2023)</title>
+      <guid>http://indexer.example/t/2</guid>
+      <pubDate>Tue, 02 Jan 2024 12:00:00 +0000</pubDate>
+      <link>magnet:?xt=urn:btih:123456&amp;dn=movie</link>
+      <torznab:attr name="category" value="2040"/>
+      <torznab:attr name="size" value="734003200"/>
+      <torznab:attr name="seeders" value="40"/>
+      <torznab:attr name="peers" value="41"/>
+      <torznab:attr name="downloadvolumefactor" value="0"/>
+    </item>
+  </channel>
+</rss>"#;
+        let extra = SourceExtraConfig {
+            date_format: None,
+            relative_date: None,
+            relative_date_short: None,
+            cache: Default::default(),
+        };
+        let search = SearchQuery {
+            page: 1,
+            ..Default::default()
+        };
+        let res = parse_torznab(content, &search, 100, &extra).unwrap();
+
+        assert_eq!(res.items.len(), 2);
+        assert_eq!(res.last_page, 1);
+        // Sorted by date, newest first
+        let movie = &res.items[0];
+        assert_eq!(movie.id, "torznab-123456");
+        assert_eq!(movie.magnet_link, "magnet:?xt=urn:btih:123456&dn=movie");
+        assert_eq!(movie.size, "700.0 MiB");
+        assert_eq!(movie.leechers, 1);
+        assert_eq!(movie.category, 2040);
+        assert!(movie.extra.contains_key("freeleech"));
+
+        let show = &res.items[1];
+        assert_eq!(show.id, "torznab-abcdef");
+        assert_eq!(show.torrent_link, "http://localhost:9696/1/download?id=1");
+        assert_eq!(show.post_link, "http://indexer.example/t/1");
+        assert_eq!(show.bytes, 1503238553);
+        assert_eq!((show.seeders, show.leechers, show.downloads), (12, 3, 300));
+        assert_eq!(show.category, 5070);
+        assert!(show
+            .magnet_link
+            .starts_with("magnet:?xt=urn:btih:abcdef&dn="));
+
+        let freeleech = SearchQuery {
+            filter: TorznabFilter::Freeleech as usize,
+            ..search
+        };
+        let res = parse_torznab(content, &freeleech, 100, &extra).unwrap();
+        assert_eq!(res.items.len(), 1);
+    }
+}
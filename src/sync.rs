@@ -5,16 +5,24 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use crossterm::event::{self, Event};
+use crossterm::event::{self, Event, MouseEventKind};
 use tokio::sync::mpsc;
 
 use crate::{
     app::LoadType,
-    client::{Client, ClientConfig, DownloadClientResult},
+    client::{
+        self, Client, ClientConfig, DownloadClientResult, DownloadResponse, TorrentAction,
+        TorrentStatus,
+    },
+    collection,
     config::CONFIG_FILE,
     results::Results,
-    source::{Item, SourceConfig, SourceExtraConfig, SourceResponse, SourceResults, Sources},
+    source::{
+        federated::{self, FederatedConfig},
+        Item, ItemDetails, SourceConfig, SourceExtraConfig, SourceResponse, SourceResults, Sources,
+    },
     theme::{Theme, THEMES_PATH},
+    util::cache::CACHE_DIR,
     widget::sort::SelectedSort,
 };
 
@@ -31,15 +39,57 @@ pub trait EventSync {
         theme: Theme,
         extra: SourceExtraConfig,
     ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
+    #[allow(clippy::too_many_arguments)]
+    fn federated_search(
+        self,
+        tx_res: mpsc::Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
+        client: reqwest::Client,
+        search: SearchQuery,
+        config: SourceConfig,
+        theme: Theme,
+        extra: SourceExtraConfig,
+        federated: FederatedConfig,
+    ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
+    fn load_details(
+        self,
+        tx_details: mpsc::Sender<Result<ItemDetails, Box<dyn Error + Send + Sync>>>,
+        src: Sources,
+        client: reqwest::Client,
+        item: Item,
+        config: SourceConfig,
+    ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
     fn download(
         self,
-        tx_dl: mpsc::Sender<DownloadClientResult>,
+        tx_dl: mpsc::Sender<DownloadResponse>,
         batch: bool,
         items: Vec<Item>,
         config: ClientConfig,
         rq_client: reqwest::Client,
+        clients: Vec<Client>,
+    ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
+    fn torrent_status(
+        self,
+        tx_status: mpsc::Sender<Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>>,
+        config: ClientConfig,
+        rq_client: reqwest::Client,
         client: Client,
     ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
+    fn torrent_action(
+        self,
+        tx_action: mpsc::Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        action: TorrentAction,
+        hashes: Vec<String>,
+        config: ClientConfig,
+        rq_client: reqwest::Client,
+        client: Client,
+    ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
+    fn export_torrents(
+        self,
+        tx_export: mpsc::Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        items: Vec<Item>,
+        dir: String,
+        rq_client: reqwest::Client,
+    ) -> impl std::future::Future<Output = ()> + std::marker::Send + 'static;
     fn read_event_loop(
         self,
         tx_evt: mpsc::Sender<Event>,
@@ -53,15 +103,29 @@ pub trait EventSync {
 #[derive(Clone)]
 pub struct AppSync {
     config_path: PathBuf,
+    /// Only browse pages in the cache, from `--offline`
+    offline: bool,
 }
 
 impl AppSync {
-    pub fn new(config_path: PathBuf) -> Self {
-        Self { config_path }
+    pub fn new(config_path: PathBuf, offline: bool) -> Self {
+        Self {
+            config_path,
+            offline,
+        }
+    }
+
+    fn with_cache(&self, extra: SourceExtraConfig) -> SourceExtraConfig {
+        SourceExtraConfig {
+            cache: extra
+                .cache
+                .with_dir(self.config_path.join(CACHE_DIR), self.offline),
+            ..extra
+        }
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq)]
 pub struct SearchQuery {
     pub query: String,
     pub page: usize,
@@ -100,6 +164,7 @@ impl EventSync for AppSync {
         theme: Theme,
         extra: SourceExtraConfig,
     ) {
+        let extra = self.with_cache(extra);
         let res = src.load(load_type, &client, &search, &config, &extra).await;
         let fmt = match res {
             Ok(SourceResponse::Results(res)) => Ok(SourceResults::Results(Results::new(
@@ -114,29 +179,123 @@ impl EventSync for AppSync {
         let _ = tx_res.send(fmt).await;
     }
 
+    async fn federated_search(
+        self,
+        tx_res: mpsc::Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
+        client: reqwest::Client,
+        search: SearchQuery,
+        config: SourceConfig,
+        theme: Theme,
+        extra: SourceExtraConfig,
+        federated: FederatedConfig,
+    ) {
+        let extra = self.with_cache(extra);
+        let sources = federated.sources.clone();
+        let res = match federated::search(sources, client, search.clone(), config, extra).await {
+            Ok((mut response, errors)) => {
+                // Report sources that failed before showing the results of the rest
+                for e in errors {
+                    let _ = tx_res.send(Err(e.into())).await;
+                }
+                federated::sort_items(&mut response.items, federated.sort, federated.sort_dir);
+                let table = federated::format_table(&response.items, &federated, &theme);
+                Ok(SourceResults::Results(Results::new(
+                    search, response, table,
+                )))
+            }
+            Err(e) => Err(e),
+        };
+        let _ = tx_res.send(res).await;
+    }
+
+    async fn load_details(
+        self,
+        tx_details: mpsc::Sender<Result<ItemDetails, Box<dyn Error + Send + Sync>>>,
+        src: Sources,
+        client: reqwest::Client,
+        item: Item,
+        config: SourceConfig,
+    ) {
+        let res = match self.offline {
+            true => Err("Details can't be loaded offline".into()),
+            false => src.details(&client, &item, &config).await,
+        };
+        let _ = tx_details.send(res).await;
+    }
+
     async fn download(
         self,
-        tx_dl: mpsc::Sender<DownloadClientResult>,
+        tx_dl: mpsc::Sender<DownloadResponse>,
         batch: bool,
         items: Vec<Item>,
         config: ClientConfig,
         rq_client: reqwest::Client,
-        client: Client,
+        clients: Vec<Client>,
     ) {
-        let res = match batch {
+        let ids = items.iter().map(|i| i.id.clone()).collect();
+        let (result, outcomes) = match batch {
             true => {
-                DownloadClientResult::Batch(client.batch_download(items, config, rq_client).await)
+                let (res, outcomes) =
+                    client::batch_download_each(clients, items, config, rq_client).await;
+                (DownloadClientResult::Batch(res), outcomes)
+            }
+            false => {
+                let (res, outcomes) =
+                    client::download_each(clients, items[0].clone(), config, rq_client).await;
+                (DownloadClientResult::Single(res), outcomes)
             }
-            false => DownloadClientResult::Single(
-                client.download(items[0].clone(), config, rq_client).await,
-            ),
         };
-        let _ = tx_dl.send(res).await;
+        let _ = tx_dl
+            .send(DownloadResponse {
+                ids,
+                result,
+                outcomes,
+            })
+            .await;
+    }
+
+    async fn torrent_status(
+        self,
+        tx_status: mpsc::Sender<Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>>,
+        config: ClientConfig,
+        rq_client: reqwest::Client,
+        client: Client,
+    ) {
+        let res = client.status(config, rq_client).await;
+        let _ = tx_status.send(res).await;
+    }
+
+    async fn torrent_action(
+        self,
+        tx_action: mpsc::Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        action: TorrentAction,
+        hashes: Vec<String>,
+        config: ClientConfig,
+        rq_client: reqwest::Client,
+        client: Client,
+    ) {
+        let res = client.action(action, hashes, config, rq_client).await;
+        let _ = tx_action.send(res).await;
+    }
+
+    async fn export_torrents(
+        self,
+        tx_export: mpsc::Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        items: Vec<Item>,
+        dir: String,
+        rq_client: reqwest::Client,
+    ) {
+        let res = collection::export_torrents(items, dir, rq_client).await;
+        let _ = tx_export.send(res).await;
     }
 
     async fn read_event_loop(self, tx_evt: mpsc::Sender<Event>) {
         loop {
             if let Ok(evt) = event::read() {
+                // Moving the mouse without a button held would redraw on every cell
+                if matches!(evt, Event::Mouse(m) if m.kind == MouseEventKind::Moved) {
+                    continue;
+                }
                 let _ = tx_evt.send(evt).await;
             }
         }
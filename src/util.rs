@@ -1,6 +1,8 @@
+pub mod cache;
 pub mod cmd;
 pub mod colors;
 pub mod conv;
+pub mod filter;
 pub mod html;
 pub mod strings;
 pub mod term;
@@ -0,0 +1,194 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::Once,
+    time::{Duration, SystemTime},
+};
+
+use reqwest::{RequestBuilder, StatusCode};
+use url::Url;
+
+pub static CACHE_DIR: &str = "cache";
+
+/// Pages are kept this long no matter the TTL, so they can still be browsed `--offline`
+pub const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+static EVICTED: Once = Once::new();
+
+/// Pages loaded by sources, saved on disk by their URL so going back to a recent
+/// search, sort or category doesn't request it again
+#[derive(Clone, Default)]
+pub struct HttpCache {
+    /// `None` until the config folder is known, which disables caching
+    dir: Option<PathBuf>,
+    ttl: Duration,
+    /// Only read pages that were already cached, no matter how old, and never request them
+    offline: bool,
+    /// Request pages again even if they're cached, from reloading
+    refresh: bool,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is the same between versions of Rust
+fn hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Remove pages older than `max_age`, done once per run when the first page is saved
+fn evict(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|m| SystemTime::now().duration_since(m).ok())
+            .is_some_and(|age| age >= max_age);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+impl HttpCache {
+    /// Pages are kept for `ttl` seconds, 0 disables the cache
+    pub fn new(ttl: u64) -> Self {
+        HttpCache {
+            dir: None,
+            ttl: Duration::from_secs(ttl),
+            offline: false,
+            refresh: false,
+        }
+    }
+
+    pub fn with_dir(self, dir: PathBuf, offline: bool) -> Self {
+        HttpCache {
+            dir: Some(dir),
+            offline,
+            ..self
+        }
+    }
+
+    /// Skip reading the cache, but still save the pages that are requested
+    pub fn refreshing(self) -> Self {
+        HttpCache {
+            refresh: true,
+            ..self
+        }
+    }
+
+    /// The file a page is saved in
+    pub fn path(&self, url: &Url) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}", hash(url.as_str()))))
+    }
+
+    /// A saved page that hasn't expired, or an error if it's missing while offline
+    pub fn get(&self, url: &Url) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        let cached = self.path(url).and_then(|path| {
+            let age = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|m| SystemTime::now().duration_since(m).ok())?;
+            match self.offline || (age < self.ttl && !self.refresh) {
+                true => fs::read(path).ok(),
+                false => None,
+            }
+        });
+        if cached.is_none() && self.offline {
+            // Don't show the query, it can have an api key
+            let mut url = url.clone();
+            url.set_query(None);
+            return Err(format!(
+                "{}\nThis page isn't cached, so it can't be loaded offline",
+                url
+            )
+            .into());
+        }
+        Ok(cached)
+    }
+
+    /// Save a page, ignoring errors since it can always be requested again
+    pub fn put(&self, url: &Url, content: &[u8]) {
+        if self.ttl.is_zero() || self.offline {
+            return;
+        }
+        if let Some(path) = self.path(url) {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+                EVICTED.call_once(|| evict(dir, MAX_AGE));
+            }
+            let _ = fs::write(path, content);
+        }
+    }
+
+    /// Read a page from the cache, or send the request and cache the response if it's OK
+    pub async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        request: RequestBuilder,
+    ) -> Result<(StatusCode, Vec<u8>), Box<dyn Error + Send + Sync>> {
+        let request = request.build()?;
+        let url = request.url().to_owned();
+        if let Some(content) = self.get(&url)? {
+            return Ok((StatusCode::OK, content));
+        }
+        let response = client.execute(request).await?;
+        let status = response.status();
+        let content = response.bytes().await?.to_vec();
+        if status == StatusCode::OK {
+            self.put(&url, &content);
+        }
+        Ok((status, content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("nyaa-cache-test-{}", std::process::id()));
+        let url = Url::parse("https://nyaa.si/?q=test&p=1").unwrap();
+        let other = Url::parse("https://nyaa.si/?q=test&p=2").unwrap();
+
+        let disabled = HttpCache::new(0).with_dir(dir.clone(), false);
+        disabled.put(&url, b"page");
+        assert!(disabled.get(&url).unwrap().is_none());
+
+        let cache = HttpCache::new(60).with_dir(dir.clone(), false);
+        cache.put(&url, b"page");
+        assert_eq!(cache.get(&url).unwrap(), Some(b"page".to_vec()));
+        assert!(cache.get(&other).unwrap().is_none());
+        assert!(cache.clone().refreshing().get(&url).unwrap().is_none());
+
+        // Offline ignores the TTL, but fails for anything that wasn't cached
+        let offline = HttpCache::new(0).with_dir(dir.clone(), true);
+        assert_eq!(offline.get(&url).unwrap(), Some(b"page".to_vec()));
+        let err = offline.get(&other).unwrap_err().to_string();
+        assert!(err.starts_with("https://nyaa.si/\n"));
+
+        // Expired pages are kept for offline, until they're older than `MAX_AGE`
+        let age = |path: &PathBuf, secs: u64| {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|f| f.set_modified(SystemTime::now() - Duration::from_secs(secs)))
+                .unwrap();
+        };
+        cache.put(&other, b"other page");
+        age(&cache.path(&url).unwrap(), 120);
+        age(&cache.path(&other).unwrap(), MAX_AGE.as_secs() + 60);
+        evict(&dir, MAX_AGE);
+        assert_eq!(offline.get(&url).unwrap(), Some(b"page".to_vec()));
+        assert!(!cache.path(&other).unwrap().exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
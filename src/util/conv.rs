@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use crossterm::event::{KeyCode, KeyModifiers, MediaKeyCode, ModifierKeyCode};
 use reqwest::Url;
 
@@ -49,6 +49,18 @@ pub fn to_relative_date(time: DateTime<Local>, short: bool) -> String {
     }
 }
 
+/// Unix timestamp of a date in one of the formats sources commonly use, or 0 if it can't be read
+pub fn to_timestamp(date: &str) -> i64 {
+    let date = date.trim();
+    if let Ok(d) = DateTime::parse_from_rfc3339(date).or(DateTime::parse_from_rfc2822(date)) {
+        return d.timestamp();
+    }
+    ["%Y-%m-%d %H:%M", "%d/%m/%y %H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(date, f).ok())
+        .map_or(0, |d| d.and_utc().timestamp())
+}
+
 pub fn get_hash(magnet: String) -> Option<String> {
     magnet
         .split_once("xt=urn:btih:")
@@ -88,6 +100,21 @@ pub fn to_bytes(size: &str) -> usize {
     (1024_f64.powi(power) * f) as usize
 }
 
+/// Format a number of bytes the same way nyaa does, e.g. `1.4 GiB`
+pub fn to_size(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < units.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
 pub fn shorten_number(n: u32) -> String {
     if n >= 10000 {
         format!("{}K", n / 1000)
@@ -0,0 +1,273 @@
+use std::cmp::Ordering;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::source::{Item, ItemType};
+
+use super::conv::to_bytes;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Seeders,
+    Leechers,
+    Downloads,
+    Size,
+}
+
+impl Field {
+    fn value(self, item: &Item) -> usize {
+        match self {
+            Field::Seeders => item.seeders as usize,
+            Field::Leechers => item.leechers as usize,
+            Field::Downloads => item.downloads as usize,
+            Field::Size => item.bytes,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Pattern {
+    Text(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn matches(&self, s: &str) -> bool {
+        match self {
+            Pattern::Text(t) => s.to_lowercase().contains(t),
+            Pattern::Regex(r) => r.is_match(s),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Cond {
+    Compare(Field, Vec<Ordering>, usize),
+    Title(Pattern),
+    Uploader(Pattern),
+    Type(ItemType),
+}
+
+#[derive(Clone)]
+struct Term {
+    negate: bool,
+    cond: Cond,
+}
+
+/// A filter over results that have already been loaded, like
+/// `seeders>10 size<2GiB title~/1080p/ -"HEVC" trusted`.
+///
+/// Every term has to match. Bare words and quoted phrases are searched for in the title,
+/// ignoring case, and any term can be negated with a leading `-`.
+#[derive(Clone)]
+pub struct LocalFilter {
+    pub expr: String,
+    terms: Vec<Term>,
+}
+
+/// Split on whitespace, except inside quotes or `~/regex/`
+fn tokens(expr: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = expr.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(tokens);
+        }
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            token.push(c);
+            let close = match c {
+                '"' => '"',
+                '/' if token.ends_with("~/") => '/',
+                _ => continue,
+            };
+            loop {
+                match chars.next() {
+                    Some('\\') if close == '/' => {
+                        token.push('\\');
+                        token.extend(chars.next());
+                    }
+                    Some(c) => {
+                        token.push(c);
+                        if c == close {
+                            break;
+                        }
+                    }
+                    None => return Err(format!("Missing closing {} in {}", close, token)),
+                }
+            }
+        }
+        tokens.push(token);
+    }
+}
+
+fn pattern(s: &str) -> Result<Pattern, String> {
+    match s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+        Some(re) => RegexBuilder::new(re)
+            .case_insensitive(true)
+            .build()
+            .map(Pattern::Regex)
+            .map_err(|e| e.to_string()),
+        None => Ok(Pattern::Text(s.trim_matches('"').to_lowercase())),
+    }
+}
+
+/// Sizes like `2GiB`, `700 MB` or `1.5g`; anything else is a plain number
+fn number(field: Field, s: &str) -> Result<usize, String> {
+    let err = || format!("\"{}\" is not a valid number", s);
+    if field != Field::Size {
+        return s.parse().map_err(|_| err());
+    }
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    num.parse::<f64>().map_err(|_| err())?;
+    Ok(to_bytes(&format!("{} {}", num, unit.trim().to_uppercase())))
+}
+
+fn comparison(token: &str) -> Option<Result<Cond, String>> {
+    let (idx, op) = [">=", "<=", ">", "<", "="]
+        .iter()
+        .find_map(|op| token.find(op).map(|i| (i, *op)))?;
+    let field = match &token[..idx] {
+        "seeders" | "seeds" => Field::Seeders,
+        "leechers" | "leeches" => Field::Leechers,
+        "downloads" | "dl" => Field::Downloads,
+        "size" => Field::Size,
+        _ => return None,
+    };
+    let ord = match op {
+        ">=" => vec![Ordering::Greater, Ordering::Equal],
+        "<=" => vec![Ordering::Less, Ordering::Equal],
+        ">" => vec![Ordering::Greater],
+        "<" => vec![Ordering::Less],
+        _ => vec![Ordering::Equal],
+    };
+    Some(number(field, &token[idx + op.len()..]).map(|n| Cond::Compare(field, ord, n)))
+}
+
+impl LocalFilter {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let mut terms = vec![];
+        for token in tokens(expr)? {
+            let (negate, token) = match token.strip_prefix('-') {
+                Some(t) if !t.is_empty() => (true, t),
+                _ => (false, token.as_str()),
+            };
+            let cond = if let Some(cond) = comparison(token) {
+                cond?
+            } else if let Some((field, pat)) = token.split_once('~') {
+                match field {
+                    "title" => Cond::Title(pattern(pat)?),
+                    "uploader" | "user" => Cond::Uploader(pattern(pat)?),
+                    _ => return Err(format!("Unknown field \"{}\"", field)),
+                }
+            } else {
+                match token {
+                    "trusted" => Cond::Type(ItemType::Trusted),
+                    "remake" => Cond::Type(ItemType::Remake),
+                    _ => Cond::Title(pattern(token)?),
+                }
+            };
+            terms.push(Term { negate, cond });
+        }
+        Ok(LocalFilter {
+            expr: expr.to_owned(),
+            terms,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, item: &Item) -> bool {
+        self.terms.iter().all(|t| {
+            let matched = match &t.cond {
+                Cond::Compare(field, ord, n) => ord.contains(&field.value(item).cmp(n)),
+                Cond::Title(p) => p.matches(&item.title),
+                Cond::Uploader(p) => item.extra.get("uploader").is_some_and(|u| p.matches(u)),
+                Cond::Type(ty) => *ty == item.item_type,
+            };
+            matched != t.negate
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, seeders: u32, size: &str, item_type: ItemType) -> Item {
+        Item {
+            title: title.to_owned(),
+            seeders,
+            size: size.to_owned(),
+            bytes: to_bytes(size),
+            item_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            tokens(r#"  seeders>10 title~/10 80p\/x/ -"HEVC x265"  "#).unwrap(),
+            vec!["seeders>10", r"title~/10 80p\/x/", r#"-"HEVC x265""#]
+        );
+        assert!(tokens(r#""unclosed"#).is_err());
+    }
+
+    #[test]
+    fn test_local_filter() {
+        let items = [
+            item(
+                "[Group] Show - 01 [1080p HEVC]",
+                50,
+                "1.2 GiB",
+                ItemType::Trusted,
+            ),
+            item("[Group] Show - 01 [1080p]", 5, "1.4 GiB", ItemType::None),
+            item("[Other] Show - 01 [720p]", 80, "600 MiB", ItemType::Remake),
+            item(
+                "[Other] Show Batch [1080p]",
+                200,
+                "12 GiB",
+                ItemType::Trusted,
+            ),
+        ];
+        let matching = |expr: &str| {
+            let filter = LocalFilter::parse(expr).unwrap();
+            items
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| filter.matches(i))
+                .map(|(i, _)| i)
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(matching(""), [0, 1, 2, 3]);
+        assert_eq!(matching("seeders>10"), [0, 2, 3]);
+        assert_eq!(matching("seeders>=50 seeders<=80"), [0, 2]);
+        assert_eq!(matching("size<2GiB"), [0, 1, 2]);
+        assert_eq!(matching("size>1.3g"), [1, 3]);
+        assert_eq!(matching("title~/1080p/"), [0, 1, 3]);
+        assert_eq!(matching(r#"-"HEVC""#), [1, 2, 3]);
+        assert_eq!(matching("trusted"), [0, 3]);
+        assert_eq!(matching("-remake other"), [3]);
+        assert_eq!(
+            matching(r#"seeders>10 size<2GiB title~/1080p/ -"HEVC" trusted"#),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            matching(r#"seeders>10 size<2GiB title~/1080p/ trusted"#),
+            [0]
+        );
+
+        assert!(LocalFilter::parse("seeders>ten").is_err());
+        assert!(LocalFilter::parse("title~/(/").is_err());
+        assert!(LocalFilter::parse("name~show").is_err());
+    }
+}
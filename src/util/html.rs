@@ -24,3 +24,18 @@ pub fn attr(e: ElementRef, s: &Selector, attr: &str) -> String {
         .unwrap_or("")
         .to_owned()
 }
+
+/// All text inside an element, with surrounding whitespace removed
+pub fn text(e: ElementRef) -> String {
+    e.text().collect::<String>().trim().to_owned()
+}
+
+/// Find the value next to a label such as `Info hash:` in a page laid out as label/value pairs
+pub fn labeled(e: ElementRef, s: &Selector, label: &str) -> Option<String> {
+    e.select(s)
+        .find(|l| text(*l).eq_ignore_ascii_case(label))?
+        .next_siblings()
+        .find_map(ElementRef::wrap)
+        .map(text)
+        .filter(|v| !v.is_empty())
+}
@@ -111,7 +111,7 @@ pub fn truncate_ellipsis(
         ['…'].repeat(repeat).iter().collect()
     });
 
-    return (el, chars.iter().collect::<String>(), er);
+    (el, chars.iter().collect::<String>(), er)
 }
 
 pub fn back_word(input: &str, start: usize) -> usize {
@@ -155,7 +155,7 @@ pub fn forward_word(input: &str, start: usize) -> usize {
         .unwrap_or(input.chars().count())
 }
 
-pub fn minimal_magnet_link(magnet_link: &String) -> Result<String, String> {
+pub fn minimal_magnet_link(magnet_link: &str) -> Result<String, String> {
     let url = Url::parse(magnet_link).map_err(|e| e.to_string())?;
 
     // Extract the query parameters into a HashMap.
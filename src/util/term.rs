@@ -2,7 +2,7 @@ use std::io::{self, stdout};
 
 use crossterm::{
     cursor::SetCursorStyle,
-    event::{DisableBracketedPaste, EnableBracketedPaste},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand as _,
 };
@@ -35,6 +35,16 @@ pub fn reset_terminal() -> io::Result<()> {
     stdout().execute(SetCursorStyle::DefaultUserShape)?;
     stdout().execute(LeaveAlternateScreen)?;
     stdout().execute(DisableBracketedPaste)?;
+    stdout().execute(DisableMouseCapture)?;
+    Ok(())
+}
+
+/// Only captured when enabled in the config, since it takes over selecting text
+pub fn set_mouse_capture(enabled: bool) -> io::Result<()> {
+    match enabled {
+        true => stdout().execute(EnableMouseCapture)?,
+        false => stdout().execute(DisableMouseCapture)?,
+    };
     Ok(())
 }
 
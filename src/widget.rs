@@ -1,9 +1,13 @@
-use std::{cmp::min, slice::Iter};
+use std::{
+    cmp::min,
+    slice::Iter,
+    time::{Duration, Instant},
+};
 
-use crossterm::event::Event;
+use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Position, Rect},
     style::{Color, Stylize as _},
     widgets::{
         Block, Borders, Clear, Scrollbar, ScrollbarOrientation, ScrollbarState, TableState,
@@ -22,17 +26,26 @@ pub mod captcha;
 pub mod batch;
 pub mod category;
 pub mod clients;
+pub mod collections;
+pub mod command;
+pub mod details;
+pub mod download_options;
 pub mod filter;
 pub mod help;
+pub mod history;
 pub mod input;
+pub mod local_filter;
 pub mod notifications;
 pub mod notify_box;
 pub mod page;
+pub mod pending;
 pub mod results;
+pub mod saved;
 pub mod search;
 pub mod sort;
 pub mod sources;
 pub mod themes;
+pub mod torrents;
 pub mod user;
 
 pub trait Widget {
@@ -140,7 +153,7 @@ pub fn centered_rect(mut x_len: u16, mut y_len: u16, r: Rect) -> Rect {
     .split(popup_layout[1])[1]
 }
 
-pub fn border_block(theme: &Theme, focused: bool) -> Block {
+pub fn border_block(theme: &Theme, focused: bool) -> Block<'_> {
     Block::new()
         .border_style(match focused {
             true => style!(fg:theme.border_focused_color),
@@ -182,6 +195,101 @@ pub fn clear(area: Rect, buf: &mut Buffer, fill: Color) {
     Block::new().bg(fill).render(area, buf);
 }
 
+/// What the mouse did to a table with borders
+pub enum TableClick {
+    Row(usize),
+    DoubleClick(usize),
+    /// Clicked the header, this many cells from the left border
+    Header(u16),
+    Scroll(isize),
+    /// Clicked or dragged the scrollbar to the row at this index
+    Scrollbar(usize),
+}
+
+/// Remembers where a table was drawn to find what the mouse is over
+#[derive(Default)]
+pub struct TableMouse {
+    pub area: Rect,
+    header: u16,
+    dragging: bool,
+    last_click: Option<(Instant, usize)>,
+}
+
+impl TableMouse {
+    pub fn set_area(&mut self, area: Rect, header: u16) {
+        self.area = area;
+        self.header = header;
+    }
+
+    pub fn contains(&self, m: &MouseEvent) -> bool {
+        self.area.contains(Position::new(m.column, m.row))
+    }
+
+    fn scrollbar_row(&self, m: &MouseEvent, len: usize) -> usize {
+        let track = self.area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
+        });
+        let row = m.row.clamp(track.top(), track.bottom().saturating_sub(1)) - track.top();
+        match track.height {
+            0 | 1 => 0,
+            h => row as usize * (len - 1) / (h as usize - 1),
+        }
+    }
+
+    /// `offset` is the first row drawn and `len` is the number of rows
+    pub fn event(&mut self, m: &MouseEvent, offset: usize, len: usize) -> Option<TableClick> {
+        match m.kind {
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging && len > 0 => {
+                return Some(TableClick::Scrollbar(self.scrollbar_row(m, len)));
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.dragging = false,
+            _ => {}
+        }
+        if !self.contains(m) {
+            return None;
+        }
+        match m.kind {
+            MouseEventKind::ScrollDown => Some(TableClick::Scroll(1)),
+            MouseEventKind::ScrollUp => Some(TableClick::Scroll(-1)),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let body = self.area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 1,
+                });
+                let top = body.top() + self.header;
+                if m.column + 1 == self.area.right() && m.row >= top && len > 0 {
+                    self.dragging = true;
+                    return Some(TableClick::Scrollbar(self.scrollbar_row(m, len)));
+                }
+                if !body.contains(Position::new(m.column, m.row)) {
+                    return None;
+                }
+                if m.row < top {
+                    return Some(TableClick::Header(m.column - body.left()));
+                }
+                let idx = offset + (m.row - top) as usize;
+                if idx >= len {
+                    return None;
+                }
+                let now = Instant::now();
+                let double = self.last_click.is_some_and(|(time, last)| {
+                    last == idx && now.duration_since(time) < Duration::from_millis(400)
+                });
+                self.last_click = match double {
+                    true => None,
+                    false => Some((now, idx)),
+                };
+                Some(match double {
+                    true => TableClick::DoubleClick(idx),
+                    false => TableClick::Row(idx),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
 pub struct StatefulTable<T> {
     pub state: TableState,
     pub scrollbar_state: ScrollbarState,
@@ -1,4 +1,4 @@
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, KeyEvent, KeyEventKind};
 use human_bytes::human_bytes;
 use ratatui::{
     layout::{Constraint, Margin, Rect},
@@ -10,20 +10,25 @@ use ratatui::{
 
 use crate::{
     app::{Context, LoadType, Mode},
+    collection::CollectionOp,
+    keymap::Action,
     source::ItemType,
     title,
+    util::conv::key_to_string,
 };
 
-use super::{border_block, VirtualStatefulTable};
+use super::{border_block, TableClick, TableMouse, VirtualStatefulTable};
 
 pub struct BatchWidget {
     table: VirtualStatefulTable,
+    pub mouse: TableMouse,
 }
 
 impl Default for BatchWidget {
     fn default() -> Self {
         BatchWidget {
             table: VirtualStatefulTable::new(),
+            mouse: TableMouse::default(),
         }
     }
 }
@@ -78,6 +83,7 @@ impl super::Widget for BatchWidget {
         Clear.render(area, buf);
 
         let num_items = rows.len();
+        self.mouse.set_area(area, 1);
         super::scroll_padding(
             self.table.selected().unwrap_or(0),
             area.height as usize,
@@ -104,6 +110,22 @@ impl super::Widget for BatchWidget {
     }
 
     fn handle_event(&mut self, ctx: &mut Context, evt: &Event) {
+        if let Event::Mouse(m) = evt {
+            match self
+                .mouse
+                .event(m, self.table.state.offset(), ctx.batch.len())
+            {
+                Some(
+                    TableClick::Row(idx)
+                    | TableClick::DoubleClick(idx)
+                    | TableClick::Scrollbar(idx),
+                ) => self.table.select(idx),
+                Some(TableClick::Scroll(amt)) => {
+                    self.table.next(ctx.batch.len(), amt);
+                }
+                _ => {}
+            }
+        }
         if let Event::Key(KeyEvent {
             code,
             kind: KeyEventKind::Press,
@@ -111,62 +133,59 @@ impl super::Widget for BatchWidget {
             ..
         }) = evt
         {
-            use KeyCode::*;
-            match (code, modifiers) {
-                (Esc | Tab | BackTab, _) => {
+            let key = key_to_string(*code, *modifiers);
+            match ctx.keymap.action(&Mode::Batch, &key) {
+                Some(Action::FocusResults) => {
                     ctx.mode = Mode::Normal;
                 }
-                (Char('q'), &KeyModifiers::NONE) => {
+                Some(Action::Quit) => {
                     ctx.quit();
                 }
-                (Char('j') | Down, &KeyModifiers::NONE) => {
+                Some(Action::Down) => {
                     self.table.next(ctx.batch.len(), 1);
                 }
-                (Char('k') | Up, &KeyModifiers::NONE) => {
+                Some(Action::Up) => {
                     self.table.next(ctx.batch.len(), -1);
                 }
-                (Char('J'), &KeyModifiers::SHIFT) => {
+                Some(Action::DownFour) => {
                     self.table.next(ctx.batch.len(), 4);
                 }
-                (Char('K'), &KeyModifiers::SHIFT) => {
+                Some(Action::UpFour) => {
                     self.table.next(ctx.batch.len(), -4);
                 }
-                (Char('g'), &KeyModifiers::NONE) => {
+                Some(Action::Top) => {
                     self.table.select(0);
                 }
-                (Char('G'), &KeyModifiers::SHIFT) => {
+                Some(Action::Bottom) => {
                     self.table.select(ctx.batch.len() - 1);
                 }
-                (Char(' '), &KeyModifiers::NONE) => {
+                Some(Action::ToggleBatch) => {
                     if let Some(i) = self.table.selected() {
                         self.table.next(ctx.batch.len(), 0);
                         ctx.batch.remove(i);
                         self.table.next(ctx.batch.len(), 0);
                     }
                 }
-                (Char('a'), &KeyModifiers::CONTROL) => {
+                Some(Action::DownloadBatch) => {
                     ctx.mode = Mode::Loading(LoadType::Batching);
                 }
-                (Char('x'), &KeyModifiers::CONTROL) => {
+                Some(Action::DownloadBatchWithOptions) => {
+                    ctx.configure_download(true);
+                }
+                Some(Action::ClearBatch) => {
                     ctx.batch.clear();
                 }
+                Some(Action::Collections) => {
+                    ctx.mode = Mode::Collections;
+                    ctx.collection(CollectionOp::Refresh);
+                }
                 _ => {}
             };
         }
     }
 
     fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
-        Some(vec![
-            ("Enter", "Download single torrent"),
-            ("Ctrl-A", "Download all torrents"),
-            ("Ctrl-X", "Clear batch"),
-            ("Esc/Tab/Shift-Tab", "Back to results"),
-            ("q", "Exit app"),
-            ("g/G", "Goto Top/Bottom"),
-            ("k, ↑", "Up"),
-            ("j, ↓", "Down"),
-            ("K, J", "Up/Down 4 items"),
-            ("Space", "Toggle item for batch download"),
-        ])
+        // Generated from the keymap instead
+        None
     }
 }
@@ -1,4 +1,4 @@
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent};
 use ratatui::{
     layout::{Constraint, Margin, Rect},
     style::{Color, Style, Stylize as _},
@@ -14,7 +14,7 @@ use crate::{
     title,
 };
 
-use super::{border_block, VirtualStatefulTable, Widget};
+use super::{border_block, TableClick, TableMouse, VirtualStatefulTable, Widget};
 
 #[derive(Clone)]
 pub struct CatEntry {
@@ -68,28 +68,92 @@ pub struct CategoryPopup {
     pub major: usize,
     pub minor: usize,
     pub table: VirtualStatefulTable,
+    mouse: TableMouse,
 }
 
 impl CategoryPopup {
-    fn next_tab(&mut self, max_cat: usize) {
-        self.major = match self.major + 1 >= max_cat {
-            true => 0,
-            false => self.major + 1,
-        };
+    fn open_tab(&mut self, major: usize) {
+        self.major = major;
         self.minor = 0;
         if self.table.state.offset() > self.major {
             *self.table.state.offset_mut() = self.major;
         }
     }
 
+    fn next_tab(&mut self, max_cat: usize) {
+        self.open_tab(match self.major + 1 >= max_cat {
+            true => 0,
+            false => self.major + 1,
+        });
+    }
+
     fn prev_tab(&mut self, max_cat: usize) {
-        self.major = match self.major == 0 {
+        self.open_tab(match self.major == 0 {
             true => max_cat - 1,
             false => self.major - 1,
+        });
+    }
+
+    fn confirm(&mut self, ctx: &mut Context) {
+        if let Some(cat) = ctx.src_info.cats.get(self.major) {
+            if let Some(item) = cat.entries.get(self.minor) {
+                self.selected = item.id;
+                ctx.notify_info(format!("Category \"{}\"", item.name));
+            }
+        }
+        ctx.mode = Mode::Loading(LoadType::Categorizing);
+    }
+
+    fn down(&mut self, ctx: &Context) {
+        if let Some(cat) = ctx.src_info.cats.get(self.major) {
+            self.minor = match self.minor + 1 >= cat.entries.len() {
+                true => {
+                    self.next_tab(ctx.src_info.cats.len());
+                    0
+                }
+                false => self.minor + 1,
+            };
+            self.table.select(self.major + self.minor + 1);
+        }
+    }
+
+    fn up(&mut self, ctx: &Context) {
+        self.minor = match self.minor < 1 {
+            true => {
+                self.prev_tab(ctx.src_info.cats.len());
+                match ctx.src_info.cats.get(self.major) {
+                    Some(cat) => cat.entries.len() - 1,
+                    None => 0,
+                }
+            }
+            false => self.minor - 1,
         };
-        self.minor = 0;
-        if self.table.state.offset() > self.major {
-            *self.table.state.offset_mut() = self.major;
+        self.table.select(self.major + self.minor + 1);
+    }
+
+    fn on_mouse(&mut self, ctx: &mut Context, m: &MouseEvent) {
+        let Some(entries) = ctx.src_info.cats.get(self.major).map(|c| c.entries.len()) else {
+            return;
+        };
+        let len = entries + ctx.src_info.cats.len();
+        match self.mouse.event(m, self.table.state.offset(), len) {
+            // Rows are the headers of each category with the open one's entries below it
+            Some(TableClick::Row(row) | TableClick::DoubleClick(row)) => {
+                if row <= self.major {
+                    self.open_tab(row);
+                    self.table.select(self.major + 1);
+                } else if row <= self.major + entries {
+                    self.minor = row - self.major - 1;
+                    self.table.select(row);
+                    self.confirm(ctx);
+                } else {
+                    self.open_tab(row - entries);
+                    self.table.select(self.major + 1);
+                }
+            }
+            Some(TableClick::Scroll(amt)) if amt > 0 => self.down(ctx),
+            Some(TableClick::Scroll(_)) => self.up(ctx),
+            _ => {}
         }
     }
 }
@@ -138,6 +202,7 @@ impl Widget for CategoryPopup {
             );
 
             super::clear(center, f.buffer_mut(), ctx.theme.bg);
+            self.mouse.set_area(center, 0);
             let table = Table::new(tbl, [Constraint::Percentage(100)])
                 .block(border_block(&ctx.theme, true).title(title!("Category")))
                 .highlight_style(Style::default().bg(ctx.theme.hl_bg));
@@ -153,6 +218,9 @@ impl Widget for CategoryPopup {
     }
 
     fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Mouse(m) = e {
+            self.on_mouse(ctx, m);
+        }
         if let Event::Key(KeyEvent {
             code,
             kind: KeyEventKind::Press,
@@ -160,44 +228,13 @@ impl Widget for CategoryPopup {
         }) = e
         {
             match code {
-                KeyCode::Enter => {
-                    if let Some(cat) = ctx.src_info.cats.get(self.major) {
-                        if let Some(item) = cat.entries.get(self.minor) {
-                            self.selected = item.id;
-                            ctx.notify_info(format!("Category \"{}\"", item.name));
-                        }
-                    }
-                    ctx.mode = Mode::Loading(LoadType::Categorizing);
-                }
+                KeyCode::Enter => self.confirm(ctx),
                 KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => {
                     ctx.mode = Mode::Normal;
                 }
-                KeyCode::Char('j') | KeyCode::Down => {
-                    if let Some(cat) = ctx.src_info.cats.get(self.major) {
-                        self.minor = match self.minor + 1 >= cat.entries.len() {
-                            true => {
-                                self.next_tab(ctx.src_info.cats.len());
-                                0
-                            }
-                            false => self.minor + 1,
-                        };
-                        self.table.select(self.major + self.minor + 1);
-                    }
-                }
-                KeyCode::Char('k') | KeyCode::Up => {
-                    if ctx.src_info.cats.get(self.major).is_some() {
-                        self.minor = match self.minor < 1 {
-                            true => {
-                                self.prev_tab(ctx.src_info.cats.len());
-                                match ctx.src_info.cats.get(self.major) {
-                                    Some(cat) => cat.entries.len() - 1,
-                                    None => 0,
-                                }
-                            }
-                            false => self.minor - 1,
-                        };
-                        self.table.select(self.major + self.minor + 1);
-                    }
+                KeyCode::Char('j') | KeyCode::Down => self.down(ctx),
+                KeyCode::Char('k') | KeyCode::Up if ctx.src_info.cats.get(self.major).is_some() => {
+                    self.up(ctx)
                 }
                 KeyCode::Char('G') => {
                     if let Some(cat) = ctx.src_info.cats.get(self.major) {
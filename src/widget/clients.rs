@@ -26,6 +26,21 @@ impl Default for ClientsPopup {
     }
 }
 
+pub fn select_client(ctx: &mut Context, c: Client) {
+    ctx.client = c;
+    // Keep a list of clients if its first one is picked again
+    if ctx.config.download_client.primary() != c {
+        ctx.config.download_client = c.into();
+    }
+
+    c.load_config(&mut ctx.config.client);
+    match ctx.save_config() {
+        Ok(_) => ctx.notify_info(format!("Updated download client to \"{}\"", c)),
+        Err(e) => ctx.notify_error(format!("Failed to update config:\n{}", e)),
+    }
+    ctx.mode = Mode::Normal;
+}
+
 impl Widget for ClientsPopup {
     fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
         let buf = f.buffer_mut();
@@ -68,17 +83,7 @@ impl Widget for ClientsPopup {
                 }
                 KeyCode::Enter => {
                     if let Some(c) = self.table.selected() {
-                        ctx.client = *c;
-                        ctx.config.download_client = *c;
-
-                        c.load_config(&mut ctx.config.client);
-                        match ctx.save_config() {
-                            Ok(_) => {
-                                ctx.notify_info(format!("Updated download client to \"{}\"", c))
-                            }
-                            Err(e) => ctx.notify_error(format!("Failed to update config:\n{}", e)),
-                        }
-                        ctx.mode = Mode::Normal;
+                        select_client(ctx, *c);
                     }
                 }
                 _ => {}
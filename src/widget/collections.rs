@@ -0,0 +1,237 @@
+use std::cmp::min;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    widgets::{Paragraph, Row, ScrollbarOrientation, StatefulWidget, Table, Widget as _},
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    collection::CollectionOp,
+    style, title,
+};
+
+use super::{
+    border_block,
+    input::{self, InputWidget},
+    VirtualStatefulTable, Widget,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    Save,
+    ExportMagnets,
+    ExportTorrents,
+}
+
+impl Prompt {
+    fn title(self) -> &'static str {
+        match self {
+            Prompt::Save => "Save Batch As",
+            Prompt::ExportMagnets => "Export Magnet Links To File",
+            Prompt::ExportTorrents => "Export Torrent Files To Folder",
+        }
+    }
+}
+
+pub struct CollectionsPopup {
+    pub table: VirtualStatefulTable,
+    pub input: InputWidget,
+    prompt: Option<Prompt>,
+}
+
+impl Default for CollectionsPopup {
+    fn default() -> Self {
+        CollectionsPopup {
+            table: VirtualStatefulTable::new(),
+            input: InputWidget::new(256, None),
+            prompt: None,
+        }
+    }
+}
+
+impl CollectionsPopup {
+    fn selected_name(&self, ctx: &Context) -> Option<String> {
+        self.table
+            .selected()
+            .and_then(|i| ctx.collections.get(i))
+            .map(|c| c.name.clone())
+    }
+
+    fn ask(&mut self, ctx: &mut Context, prompt: Prompt) {
+        if prompt != Prompt::Save && ctx.batch.is_empty() {
+            return ctx.notify_warn("The batch is empty, there's nothing to export");
+        }
+        let name = self
+            .selected_name(ctx)
+            .unwrap_or_else(|| "batch".to_owned());
+        self.input.input = match prompt {
+            Prompt::Save => name,
+            Prompt::ExportMagnets => format!("~/Downloads/{}.txt", name),
+            Prompt::ExportTorrents => format!("~/Downloads/{}", name),
+        };
+        self.input.set_cursor(self.input.input.chars().count());
+        self.prompt = Some(prompt);
+    }
+
+    fn confirm(&mut self, ctx: &mut Context, prompt: Prompt) {
+        let input = self.input.input.trim().to_owned();
+        if input.is_empty() {
+            return;
+        }
+        self.prompt = None;
+        ctx.collection(match prompt {
+            Prompt::Save => CollectionOp::Save(input),
+            Prompt::ExportMagnets => CollectionOp::ExportMagnets(input),
+            Prompt::ExportTorrents => CollectionOp::ExportTorrents(input),
+        });
+    }
+}
+
+impl Widget for CollectionsPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let height = min(ctx.collections.len().max(1) as u16 + 2, 14);
+        let prompt_height = if self.prompt.is_some() { 3 } else { 0 };
+        let center = super::centered_rect(60, height + prompt_height, area);
+        let table_area = Rect::new(center.x, center.y, center.width, height);
+
+        let items = ctx
+            .collections
+            .iter()
+            .map(|c| Row::new(vec![c.name.to_owned(), format!("{:>5}", c.len)]));
+        let num_items = items.len();
+        super::scroll_padding(
+            self.table.selected().unwrap_or(0),
+            table_area.height as usize,
+            2,
+            num_items,
+            1,
+            self.table.state.offset_mut(),
+        );
+
+        super::clear(center, buf, ctx.theme.bg);
+        let table = Table::new(items, [Constraint::Fill(1), Constraint::Length(5)])
+            .block(border_block(&ctx.theme, self.prompt.is_none()).title(title!("Collections")))
+            .highlight_style(style!(bg:ctx.theme.hl_bg));
+        StatefulWidget::render(table, table_area, buf, &mut self.table.state);
+
+        if num_items == 0 {
+            Paragraph::new(" Press a to save the batch").render(
+                table_area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 1,
+                }),
+                buf,
+            );
+        }
+
+        // Only show scrollbar if content overflows
+        if num_items as u16 + 1 >= table_area.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = table_area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut self.table.scrollbar_state.content_length(num_items),
+            );
+        }
+
+        if let Some(prompt) = self.prompt {
+            let input_area = Rect::new(center.x, center.bottom() - 3, center.width, 3);
+            let block = border_block(&ctx.theme, true).title(title!(prompt.title()));
+            let inner = block.inner(input_area);
+            block.render(input_area, buf);
+            self.input.draw(f, ctx, inner);
+            self.input.show_cursor(f, inner);
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            if let Some(prompt) = self.prompt {
+                match code {
+                    KeyCode::Esc => self.prompt = None,
+                    KeyCode::Enter => self.confirm(ctx, prompt),
+                    _ => self.input.handle_event(ctx, e),
+                }
+                return;
+            }
+            let len = ctx.collections.len();
+            match code {
+                KeyCode::Esc | KeyCode::Char('B') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.table.next_wrap(len, 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.table.next_wrap(len, -1);
+                }
+                KeyCode::Char('G') => {
+                    self.table.select(len.saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    self.table.select(0);
+                }
+                KeyCode::Enter => {
+                    if let Some(name) = self.selected_name(ctx) {
+                        ctx.collection(CollectionOp::Load(name));
+                        ctx.mode = Mode::Batch;
+                    }
+                }
+                KeyCode::Char('m') => {
+                    if let Some(name) = self.selected_name(ctx) {
+                        ctx.collection(CollectionOp::Merge(name));
+                        ctx.mode = Mode::Batch;
+                    }
+                }
+                KeyCode::Char('a') => self.ask(ctx, Prompt::Save),
+                KeyCode::Char('e') => self.ask(ctx, Prompt::ExportMagnets),
+                KeyCode::Char('E') => self.ask(ctx, Prompt::ExportTorrents),
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    if let Some(name) = self.selected_name(ctx) {
+                        ctx.collection(CollectionOp::Remove(name));
+                        self.table.select(
+                            self.table
+                                .selected()
+                                .unwrap_or(0)
+                                .min(len.saturating_sub(2)),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        let mut help = vec![
+            ("Enter", "Load collection into batch"),
+            ("m", "Merge collection into batch"),
+            ("a", "Save batch as collection"),
+            ("e", "Export batch as magnet list"),
+            ("E", "Export batch as torrent files"),
+            ("x, Del", "Remove collection"),
+            ("Esc, B, q", "Close"),
+            ("j, ↓", "Down"),
+            ("k, ↑", "Up"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ];
+        if let Some(input_help) = input::InputWidget::get_help() {
+            help.extend(input_help);
+        }
+        Some(help)
+    }
+}
@@ -0,0 +1,145 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::Rect,
+    style::Stylize as _,
+    text::{Line, Span},
+    widgets::Widget as _,
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    command::{self, Command},
+    title,
+};
+
+use super::{
+    border_block,
+    input::{self, InputWidget},
+    Widget,
+};
+
+pub struct CommandPopup {
+    pub input: InputWidget,
+    completions: Vec<String>,
+    /// Which completion Tab last filled in
+    completion: Option<usize>,
+}
+
+impl Default for CommandPopup {
+    fn default() -> Self {
+        CommandPopup {
+            input: InputWidget::new(300, None),
+            completions: vec![],
+            completion: None,
+        }
+    }
+}
+
+impl CommandPopup {
+    fn complete(&mut self, ctx: &Context, step: isize) {
+        if self.completion.is_none() {
+            self.completions = command::completions(&self.input.input, ctx);
+        }
+        let len = self.completions.len();
+        if len == 0 {
+            return;
+        }
+        let idx = match self.completion {
+            Some(i) => (i as isize + step).rem_euclid(len as isize) as usize,
+            None if step < 0 => len - 1,
+            None => 0,
+        };
+        self.completion = Some(idx);
+        self.input.input.clone_from(&self.completions[idx]);
+        self.input.set_cursor(self.input.input.chars().count());
+    }
+
+    fn close(&mut self, ctx: &mut Context) {
+        self.input.clear();
+        self.completions.clear();
+        self.completion = None;
+        ctx.mode = Mode::Normal;
+    }
+}
+
+impl Widget for CommandPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let bar = Rect::new(
+            area.x,
+            area.bottom().saturating_sub(3),
+            area.width,
+            3.min(area.height),
+        );
+        super::clear(bar, f.buffer_mut(), ctx.theme.bg);
+        let mut block = border_block(&ctx.theme, true).title(title!("Command"));
+        if self.completions.len() > 1 {
+            let mut spans = vec![];
+            for (i, c) in self.completions.iter().enumerate() {
+                // Only show the part being completed
+                let word = c.rsplit(' ').next().unwrap_or(c);
+                let span = Span::raw(format!(" {} ", word));
+                spans.push(match Some(i) == self.completion {
+                    true => span.bg(ctx.theme.hl_bg),
+                    false => span,
+                });
+            }
+            block = block.title_bottom(Line::from(spans));
+        }
+        let inner = block.inner(bar);
+        block.render(bar, f.buffer_mut());
+        self.input.draw(f, ctx, inner);
+        self.input.show_cursor(f, inner);
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            match code {
+                KeyCode::Esc => self.close(ctx),
+                KeyCode::Enter => match Command::parse(&self.input.input, ctx) {
+                    Ok(cmd) => {
+                        self.close(ctx);
+                        ctx.run_command(cmd);
+                    }
+                    Err(e) => ctx.notify_error(e),
+                },
+                KeyCode::Tab => self.complete(ctx, 1),
+                KeyCode::BackTab => self.complete(ctx, -1),
+                KeyCode::Backspace if self.input.input.is_empty() => self.close(ctx),
+                _ => {
+                    self.input.handle_event(ctx, e);
+                    self.completions.clear();
+                    self.completion = None;
+                }
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        let mut help = vec![
+            ("Enter", "Run command"),
+            ("Esc", "Cancel"),
+            ("Tab, Shift-Tab", "Complete command or argument"),
+            (":search <query>", "Search"),
+            (":source <name>", "Select source"),
+            (":sort <name> [asc]", "Sort results"),
+            (":filter <name>", "Filter results"),
+            (":category <name>", "Select category"),
+            (":page <n>", "Goto page"),
+            (":user [name]", "Filter by user"),
+            (":client <name>", "Select download client"),
+            (":theme <name>", "Select theme"),
+            (":yank <link>", "Copy torrent/magnet/post/imdb/name"),
+            (":quit", "Exit app"),
+        ];
+        if let Some(input_help) = input::InputWidget::get_help() {
+            help.extend(input_help);
+        }
+        Some(help)
+    }
+}
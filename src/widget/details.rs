@@ -0,0 +1,189 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Margin, Rect},
+    style::{Style, Stylize as _},
+    text::{Line, Span},
+    widgets::{
+        Clear, Paragraph, ScrollbarOrientation, ScrollbarState, StatefulWidget as _, Widget as _,
+        Wrap,
+    },
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    source::{Item, ItemDetails},
+    title,
+};
+
+use super::{border_block, centered_rect};
+
+#[derive(Default)]
+pub struct DetailsWidget {
+    pub item: Option<Item>,
+    pub details: Option<ItemDetails>,
+    scroll: usize,
+    num_lines: usize,
+    height: usize,
+}
+
+impl DetailsWidget {
+    /// Show an item while its details are being fetched
+    pub fn load(&mut self, item: Item) {
+        self.item = Some(item);
+        self.details = None;
+        self.scroll = 0;
+    }
+
+    fn scroll_by(&mut self, amt: isize) {
+        let max = self.num_lines.saturating_sub(self.height);
+        self.scroll = self.scroll.saturating_add_signed(amt).min(max);
+    }
+
+    fn lines<'a>(ctx: &Context, item: &'a Item, details: &'a ItemDetails) -> Vec<Line<'a>> {
+        let header = |s: String| Line::from(s.fg(ctx.theme.border_focused_color).bold());
+        let field = |k: &'static str, v: String| {
+            Line::from(vec![
+                Span::styled(k, Style::new().fg(ctx.theme.border_color)),
+                Span::raw(v),
+            ])
+        };
+
+        let mut lines = vec![Line::from(item.title.as_str().bold()), Line::default()];
+        if let Some(submitter) = &details.submitter {
+            lines.push(field("Submitter: ", submitter.to_owned()));
+        }
+        lines.push(field("Date:      ", item.date.to_owned()));
+        lines.push(field("Size:      ", item.size.to_owned()));
+        lines.push(field(
+            "Peers:     ",
+            format!("{} seeders, {} leechers", item.seeders, item.leechers),
+        ));
+        if let Some(hash) = &details.hash {
+            lines.push(field("Info hash: ", hash.to_owned()));
+        }
+
+        lines.push(Line::default());
+        lines.push(header("Description".to_owned()));
+        match details.description.is_empty() {
+            true => lines.push(Line::from("No description".italic())),
+            false => lines.extend(details.description.lines().map(Line::from)),
+        }
+
+        lines.push(Line::default());
+        lines.push(header(format!("Files ({})", details.files.len())));
+        lines.extend(details.files.iter().map(|f| {
+            let indent = "  ".repeat(f.depth);
+            let name = match f.folder {
+                true => format!("{}{}/", indent, f.name).fg(ctx.theme.info),
+                false => format!("{}{}", indent, f.name).into(),
+            };
+            let mut spans = vec![name];
+            if let Some(size) = &f.size {
+                spans.push(format!(" ({})", size).fg(ctx.theme.border_color));
+            }
+            Line::from(spans)
+        }));
+
+        lines.push(Line::default());
+        lines.push(header(format!("Comments ({})", details.comments.len())));
+        for c in details.comments.iter() {
+            lines.push(Line::from(vec![
+                c.user.as_str().fg(ctx.theme.success),
+                format!(" {}", c.date).fg(ctx.theme.border_color),
+            ]));
+            lines.extend(c.text.lines().map(|l| Line::from(format!("  {}", l))));
+        }
+        lines
+    }
+}
+
+impl super::Widget for DetailsWidget {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let block = border_block(&ctx.theme, ctx.mode == Mode::Details).title(title!("Details"));
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let (Some(item), Some(details)) = (&self.item, &self.details) else {
+            let load_area = centered_rect(8, 1, inner);
+            Paragraph::new("Loading…").render(load_area, buf);
+            return;
+        };
+
+        let lines = Self::lines(ctx, item, details);
+        let width = inner.width.max(1) as usize;
+        // Count lines after wrapping so scrolling stops at the end of the text
+        self.num_lines = lines.iter().map(|l| l.width().div_ceil(width).max(1)).sum();
+        self.height = inner.height as usize;
+        self.scroll = self.scroll.min(self.num_lines.saturating_sub(self.height));
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll as u16, 0))
+            .render(inner, buf);
+
+        if self.num_lines > self.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut ScrollbarState::new(self.num_lines.saturating_sub(self.height))
+                    .position(self.scroll),
+            );
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            match code {
+                KeyCode::Esc | KeyCode::Char('I') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => self.scroll_by(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll_by(-1),
+                KeyCode::Char('J') => self.scroll_by(4),
+                KeyCode::Char('K') => self.scroll_by(-4),
+                KeyCode::Char('g') => self.scroll = 0,
+                KeyCode::Char('G') => self.scroll_by(isize::MAX),
+                KeyCode::Enter => {
+                    if let Some(item) = self.item.clone() {
+                        ctx.download(ctx.item_source(&item), item);
+                    }
+                }
+                KeyCode::Char('o') => {
+                    if let Some(link) = self.item.as_ref().map(|i| i.post_link.clone()) {
+                        match open::that_detached(&link) {
+                            Ok(()) => ctx.notify_info(format!("Opened {}", link)),
+                            Err(e) => ctx.notify_error(format!("Failed to open {}:\n{}", link, e)),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        Some(vec![
+            ("Enter", "Download"),
+            ("o", "Open in browser"),
+            ("Esc, I, q", "Close"),
+            ("j, ↓", "Scroll down"),
+            ("k, ↑", "Scroll up"),
+            ("K, J", "Scroll up/down 4 lines"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ])
+    }
+}
@@ -0,0 +1,235 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Margin, Rect},
+    style::Stylize as _,
+    text::Line,
+    widgets::{Paragraph, Widget as _},
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    client::{Client, DownloadOptions},
+    title,
+};
+
+use super::{
+    border_block,
+    input::{self, InputWidget},
+    Widget,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    SavePath,
+    Category,
+    Tags,
+    Paused,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::SavePath => "Save path",
+            Field::Category => "Category",
+            Field::Tags => "Tags",
+            Field::Paused => "Paused",
+        }
+    }
+}
+
+const LABEL_WIDTH: u16 = 11;
+
+pub struct DownloadOptionsPopup {
+    pub save_path: InputWidget,
+    pub category: InputWidget,
+    pub tags: InputWidget,
+    pub paused: bool,
+    options: DownloadOptions,
+    client: Client,
+    batch: bool,
+    focus: usize,
+}
+
+impl Default for DownloadOptionsPopup {
+    fn default() -> Self {
+        DownloadOptionsPopup {
+            save_path: InputWidget::new(256, None),
+            category: InputWidget::new(64, None),
+            tags: InputWidget::new(256, None),
+            paused: false,
+            options: DownloadOptions::default(),
+            client: Client::Cmd,
+            batch: false,
+            focus: 0,
+        }
+    }
+}
+
+fn fill(input: &mut InputWidget, value: Option<&String>) {
+    input.input = value.cloned().unwrap_or_default();
+    input.set_cursor(input.input.chars().count());
+}
+
+impl DownloadOptionsPopup {
+    /// Pre-fill the fields from the client's config
+    pub fn open(&mut self, client: Client, options: DownloadOptions, batch: bool) {
+        fill(&mut self.save_path, options.save_path.as_ref());
+        fill(&mut self.category, options.category.as_ref());
+        fill(&mut self.tags, options.tags.as_ref());
+        self.paused = options.paused.unwrap_or_default();
+        self.options = options;
+        self.client = client;
+        self.batch = batch;
+        self.focus = 0;
+    }
+
+    /// Only the fields the client has an equivalent setting for
+    fn fields(&self) -> Vec<Field> {
+        let o = &self.options;
+        [
+            (Field::SavePath, o.save_path.is_some()),
+            (Field::Category, o.category.is_some()),
+            (Field::Tags, o.tags.is_some()),
+            (Field::Paused, o.paused.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(f, supported)| supported.then_some(f))
+        .collect()
+    }
+
+    fn input(&mut self, field: Field) -> Option<&mut InputWidget> {
+        match field {
+            Field::SavePath => Some(&mut self.save_path),
+            Field::Category => Some(&mut self.category),
+            Field::Tags => Some(&mut self.tags),
+            Field::Paused => None,
+        }
+    }
+
+    fn chosen(&self) -> DownloadOptions {
+        let o = &self.options;
+        DownloadOptions {
+            save_path: o.save_path.as_ref().map(|_| self.save_path.input.clone()),
+            category: o.category.as_ref().map(|_| self.category.input.clone()),
+            tags: o.tags.as_ref().map(|_| self.tags.input.clone()),
+            paused: o.paused.map(|_| self.paused),
+        }
+    }
+
+    fn close(&self, ctx: &mut Context) {
+        ctx.mode = match self.batch {
+            true => Mode::Batch,
+            false => Mode::Normal,
+        };
+    }
+}
+
+impl Widget for DownloadOptionsPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let fields = self.fields();
+        let center = super::centered_rect(50, fields.len() as u16 + 2, area);
+        let title = match self.batch {
+            true => title!("Download batch with {}", self.client),
+            false => title!("Download with {}", self.client),
+        };
+        super::clear(center, f.buffer_mut(), ctx.theme.bg);
+        border_block(&ctx.theme, true)
+            .title(title)
+            .render(center, f.buffer_mut());
+
+        let inner = center.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        for (i, field) in fields.into_iter().enumerate() {
+            let row = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+            let label = format!("{:<width$}", field.label(), width = LABEL_WIDTH as usize);
+            let label = match i == self.focus {
+                true => label.fg(ctx.theme.border_focused_color).bold(),
+                false => label.fg(ctx.theme.fg),
+            };
+            Paragraph::new(label).render(row, f.buffer_mut());
+
+            let value_area = Rect::new(
+                row.x + LABEL_WIDTH,
+                row.y,
+                row.width.saturating_sub(LABEL_WIDTH),
+                1,
+            );
+            let focused = i == self.focus && ctx.mode == Mode::DownloadOptions;
+            match self.input(field) {
+                Some(input) => {
+                    input.draw(f, ctx, value_area);
+                    if focused {
+                        input.show_cursor(f, value_area);
+                    }
+                }
+                None => {
+                    let check = match self.paused {
+                        true => "[x]",
+                        false => "[ ]",
+                    };
+                    Paragraph::new(Line::from(check)).render(value_area, f.buffer_mut());
+                }
+            }
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            let fields = self.fields();
+            let len = fields.len();
+            match code {
+                KeyCode::Esc => self.close(ctx),
+                KeyCode::Enter => {
+                    let config = ctx
+                        .config
+                        .client
+                        .clone()
+                        .with_download_options(self.client, self.chosen());
+                    self.close(ctx);
+                    ctx.download_with(config, self.batch);
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    self.focus = (self.focus + 1) % len.max(1);
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    self.focus = (self.focus + len.max(1) - 1) % len.max(1);
+                }
+                _ => match fields.get(self.focus) {
+                    Some(Field::Paused) => {
+                        if let KeyCode::Char(' ') = code {
+                            self.paused = !self.paused;
+                        }
+                    }
+                    Some(field) => {
+                        if let Some(input) = self.input(*field) {
+                            input.handle_event(ctx, e);
+                        }
+                    }
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        let mut help = vec![
+            ("Enter", "Download"),
+            ("Esc", "Cancel"),
+            ("Tab, ↓", "Next field"),
+            ("Shift-Tab, ↑", "Previous field"),
+            ("Space", "Toggle paused"),
+        ];
+        if let Some(input_help) = input::InputWidget::get_help() {
+            help.extend(input_help);
+        }
+        Some(help)
+    }
+}
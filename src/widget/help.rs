@@ -16,7 +16,7 @@ use crate::{
 use super::{border_block, StatefulTable, Widget};
 
 pub struct HelpPopup {
-    pub table: StatefulTable<(&'static str, &'static str)>,
+    pub table: StatefulTable<(String, &'static str)>,
     pub prev_mode: Mode,
 }
 
@@ -30,7 +30,7 @@ impl Default for HelpPopup {
 }
 
 impl HelpPopup {
-    pub fn with_items(&mut self, items: Vec<(&'static str, &'static str)>, prev_mode: Mode) {
+    pub fn with_items(&mut self, items: Vec<(String, &'static str)>, prev_mode: Mode) {
         self.table.scrollbar_state = self.table.scrollbar_state.content_length(items.len());
         self.table.items = items;
         self.prev_mode = prev_mode;
@@ -52,7 +52,7 @@ impl Widget for HelpPopup {
         let center = super::centered_rect(key_max + map_max + 6, height, area);
         let items = self.table.items.iter().map(|(key, map)| {
             Row::new([
-                Line::from(*key).alignment(Alignment::Right),
+                Line::from(key.as_str()).alignment(Alignment::Right),
                 Line::from("⇒"),
                 Line::from(*map),
             ])
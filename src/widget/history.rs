@@ -0,0 +1,169 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Stylize as _,
+    widgets::{Paragraph, Row, ScrollbarOrientation, StatefulWidget, Table, Widget as _},
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    style, title,
+};
+
+use super::{border_block, VirtualStatefulTable, Widget};
+
+pub struct DownloadHistoryPopup {
+    pub table: VirtualStatefulTable,
+}
+
+impl Default for DownloadHistoryPopup {
+    fn default() -> Self {
+        DownloadHistoryPopup {
+            table: VirtualStatefulTable::new(),
+        }
+    }
+}
+
+impl DownloadHistoryPopup {
+    /// Entries are shown newest first, so map the selected row back to the history
+    fn selected_entry(&self, ctx: &Context) -> Option<usize> {
+        let len = ctx.download_history.entries.len();
+        self.table
+            .selected()
+            .filter(|i| *i < len)
+            .map(|i| len - 1 - i)
+    }
+}
+
+impl Widget for DownloadHistoryPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let center = super::centered_rect(area.width.saturating_sub(10).max(60), 20, area);
+        let entries = &ctx.download_history.entries;
+        let items = entries.iter().rev().map(|e| {
+            Row::new(vec![
+                e.date.to_owned(),
+                e.source.to_string(),
+                e.size.to_owned(),
+                e.title.to_owned(),
+            ])
+        });
+        let num_items = items.len();
+        super::scroll_padding(
+            self.table.selected().unwrap_or(0),
+            center.height as usize,
+            3,
+            num_items,
+            1,
+            self.table.state.offset_mut(),
+        );
+
+        super::clear(center, buf, ctx.theme.bg);
+        let header = Row::new(vec!["Date", "Source", "Size", "Title"])
+            .fg(ctx.theme.border_focused_color)
+            .underlined();
+        let table = Table::new(
+            items,
+            [
+                Constraint::Length(16),
+                Constraint::Length(13),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(border_block(&ctx.theme, true).title(title!("Download History")))
+        .highlight_style(style!(bg:ctx.theme.hl_bg));
+        StatefulWidget::render(table, center, buf, &mut self.table.state);
+
+        let inner = center.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if num_items == 0 {
+            let empty = super::centered_rect(19, 1, inner);
+            Paragraph::new("Nothing downloaded").render(empty, buf);
+        } else if num_items as u16 + 2 >= center.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = center.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut self.table.scrollbar_state.content_length(num_items),
+            );
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            let len = ctx.download_history.entries.len();
+            match code {
+                KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.table.next(len, 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.table.next(len, -1);
+                }
+                KeyCode::Char('J') => {
+                    self.table.next(len, 4);
+                }
+                KeyCode::Char('K') => {
+                    self.table.next(len, -4);
+                }
+                KeyCode::Char('G') => {
+                    self.table.select(len.saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    self.table.select(0);
+                }
+                KeyCode::Enter => {
+                    if let Some(entry) = self
+                        .selected_entry(ctx)
+                        .and_then(|i| ctx.download_history.entries.get(i))
+                    {
+                        let (src, item) = (entry.source, entry.item());
+                        ctx.download(src, item);
+                    }
+                }
+                KeyCode::Char('o') => {
+                    if let Some(link) = self
+                        .selected_entry(ctx)
+                        .and_then(|i| ctx.download_history.entries.get(i))
+                        .map(|e| e.post_link.clone())
+                    {
+                        match open::that_detached(&link) {
+                            Ok(()) => ctx.notify_info(format!("Opened {}", link)),
+                            Err(e) => ctx.notify_error(format!("Failed to open {}:\n{}", link, e)),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        Some(vec![
+            ("Enter", "Download again"),
+            ("o", "Open in browser"),
+            ("Esc, D, q", "Close"),
+            ("j, ↓", "Down"),
+            ("k, ↑", "Up"),
+            ("K, J", "Up/Down 4 items"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ])
+    }
+}
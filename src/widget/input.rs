@@ -111,16 +111,14 @@ impl super::Widget for InputWidget {
                     self.input = strings::without_range(&self.input, new_cursor..self.char_idx);
                     self.char_idx = new_cursor;
                 }
-                (Backspace, &KeyModifiers::NONE) => {
-                    if !self.input.is_empty() && self.char_idx > 0 {
-                        self.char_idx -= 1;
-                        self.input = strings::without_nth_char(&self.input, self.char_idx);
-                    }
+                (Backspace, &KeyModifiers::NONE) if !self.input.is_empty() && self.char_idx > 0 => {
+                    self.char_idx -= 1;
+                    self.input = strings::without_nth_char(&self.input, self.char_idx);
                 }
-                (Delete, &KeyModifiers::NONE) => {
-                    if !self.input.is_empty() && self.char_idx < self.input.chars().count() {
-                        self.input = strings::without_nth_char(&self.input, self.char_idx);
-                    }
+                (Delete, &KeyModifiers::NONE)
+                    if !self.input.is_empty() && self.char_idx < self.input.chars().count() =>
+                {
+                    self.input = strings::without_nth_char(&self.input, self.char_idx);
                 }
                 (Left, &KeyModifiers::NONE)
                 | (Char('h'), &KeyModifiers::CONTROL | &KeyModifiers::ALT) => {
@@ -0,0 +1,115 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{layout::Rect, style::Stylize as _, text::Line, widgets::Widget as _, Frame};
+
+use crate::{
+    app::{Context, Mode},
+    title,
+    util::filter::LocalFilter,
+};
+
+use super::{
+    border_block,
+    input::{self, InputWidget},
+    Widget,
+};
+
+pub struct LocalFilterPopup {
+    pub input: InputWidget,
+    /// Why the expression being typed can't be parsed, while the last valid one stays applied
+    error: Option<String>,
+}
+
+impl Default for LocalFilterPopup {
+    fn default() -> Self {
+        LocalFilterPopup {
+            input: InputWidget::new(300, None),
+            error: None,
+        }
+    }
+}
+
+impl LocalFilterPopup {
+    fn update(&mut self, ctx: &mut Context) {
+        match LocalFilter::parse(&self.input.input) {
+            Ok(filter) => {
+                self.error = None;
+                ctx.set_local_filter(Some(filter));
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+impl Widget for LocalFilterPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let bar = Rect::new(
+            area.x,
+            area.bottom().saturating_sub(3),
+            area.width,
+            3.min(area.height),
+        );
+        super::clear(bar, f.buffer_mut(), ctx.theme.bg);
+        let mut block = border_block(&ctx.theme, true).title(title!("Filter Results"));
+        block = match self.error.as_ref() {
+            Some(err) => block.title_bottom(
+                Line::from(title!(err.lines().next().unwrap_or_default())).fg(ctx.theme.error),
+            ),
+            None => block.title_bottom(
+                Line::from(title!(
+                    "{}/{} shown",
+                    ctx.results.response.items.len(),
+                    ctx.all_results().len()
+                ))
+                .right_aligned(),
+            ),
+        };
+        let inner = block.inner(bar);
+        block.render(bar, f.buffer_mut());
+        self.input.draw(f, ctx, inner);
+        if ctx.mode == Mode::LocalFilter {
+            self.input.show_cursor(f, inner);
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            match code {
+                KeyCode::Enter => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.error = None;
+                    ctx.set_local_filter(None);
+                    ctx.mode = Mode::Normal;
+                }
+                _ => {
+                    self.input.handle_event(ctx, e);
+                    self.update(ctx);
+                }
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        let mut help = vec![
+            ("Enter", "Keep filter"),
+            ("Esc", "Clear filter"),
+            ("seeders>10", "Compare seeders, leechers, downloads or size"),
+            ("size<2GiB", "Sizes can have a unit"),
+            ("title~/re/", "Match title or uploader with a regex"),
+            ("word, \"phrase\"", "Title contains text"),
+            ("trusted, remake", "Only trusted or remake torrents"),
+            ("-term", "Exclude matches"),
+        ];
+        if let Some(input_help) = input::InputWidget::get_help() {
+            help.extend(input_help);
+        }
+        Some(help)
+    }
+}
@@ -126,10 +126,10 @@ impl NotificationWidget {
     }
 
     pub fn update(&mut self, deltatime: f64, area: Rect) -> bool {
-        let res = self
-            .notifs
+        let mut res = false;
+        self.notifs
             .iter_mut()
-            .fold(false, |acc, x| x.update(deltatime, area) || acc);
+            .for_each(|x| res |= x.update(deltatime, area));
         let finished = self
             .notifs
             .iter()
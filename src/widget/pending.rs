@@ -0,0 +1,173 @@
+use chrono::Local;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Stylize as _,
+    widgets::{Paragraph, Row, ScrollbarOrientation, StatefulWidget, Table, Widget as _},
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    client::client_names,
+    style, title,
+};
+
+use super::{border_block, VirtualStatefulTable, Widget};
+
+pub struct PendingPopup {
+    pub table: VirtualStatefulTable,
+}
+
+impl Default for PendingPopup {
+    fn default() -> Self {
+        PendingPopup {
+            table: VirtualStatefulTable::new(),
+        }
+    }
+}
+
+fn format_wait(secs: i64) -> String {
+    match secs {
+        ..=0 => "now".to_owned(),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs / 60 % 60),
+    }
+}
+
+impl Widget for PendingPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let center = super::centered_rect(area.width.saturating_sub(10).max(60), 20, area);
+        let now = Local::now().timestamp();
+        let items = ctx.pending.entries.iter().map(|p| {
+            Row::new(vec![
+                p.entry.title.to_owned(),
+                client_names(&p.clients),
+                p.attempts.to_string(),
+                format_wait(p.next_retry - now),
+                p.error.lines().next().unwrap_or_default().to_owned(),
+            ])
+        });
+        let num_items = items.len();
+        super::scroll_padding(
+            self.table.selected().unwrap_or(0),
+            center.height as usize,
+            3,
+            num_items,
+            1,
+            self.table.state.offset_mut(),
+        );
+
+        super::clear(center, buf, ctx.theme.bg);
+        let header = Row::new(vec!["Title", "Clients", "Tries", "Retry in", "Error"])
+            .fg(ctx.theme.border_focused_color)
+            .underlined();
+        let table = Table::new(
+            items,
+            [
+                Constraint::Fill(2),
+                Constraint::Length(12),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(border_block(&ctx.theme, true).title(title!("Pending Downloads")))
+        .highlight_style(style!(bg:ctx.theme.hl_bg));
+        StatefulWidget::render(table, center, buf, &mut self.table.state);
+
+        let inner = center.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if num_items == 0 {
+            let empty = super::centered_rect(16, 1, inner);
+            Paragraph::new("Nothing to retry").render(empty, buf);
+        } else if num_items as u16 + 2 >= center.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = center.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut self.table.scrollbar_state.content_length(num_items),
+            );
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            let len = ctx.pending.entries.len();
+            match code {
+                KeyCode::Esc | KeyCode::Char('R') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.table.next(len, 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.table.next(len, -1);
+                }
+                KeyCode::Char('J') => {
+                    self.table.next(len, 4);
+                }
+                KeyCode::Char('K') => {
+                    self.table.next(len, -4);
+                }
+                KeyCode::Char('G') => {
+                    self.table.select(len.saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    self.table.select(0);
+                }
+                KeyCode::Enter => {
+                    if let Some(p) = self
+                        .table
+                        .selected()
+                        .and_then(|i| ctx.pending.entries.get_mut(i))
+                    {
+                        p.next_retry = 0;
+                        ctx.retry_pending();
+                    }
+                }
+                KeyCode::Char('r') => {
+                    ctx.pending.retry_now(None);
+                    ctx.retry_pending();
+                }
+                KeyCode::Char('x') => {
+                    if let Some(i) = self.table.selected().filter(|i| *i < len) {
+                        let p = ctx.pending.entries.remove(i);
+                        ctx.notify_info(format!("Stopped retrying \"{}\"", p.entry.title));
+                        ctx.save_pending();
+                        self.table.select(i.min(len.saturating_sub(2)));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        Some(vec![
+            ("Enter", "Retry now"),
+            ("r", "Retry all now"),
+            ("x", "Stop retrying"),
+            ("Esc, R, q", "Close"),
+            ("j, ↓", "Down"),
+            ("k, ↑", "Up"),
+            ("K, J", "Up/Down 4 items"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ])
+    }
+}
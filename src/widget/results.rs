@@ -1,8 +1,8 @@
 use core::str;
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, KeyEvent, KeyEventKind, MouseEvent};
 use ratatui::{
-    layout::{Margin, Rect},
+    layout::{Flex, Layout, Margin, Rect},
     style::{Style, Stylize as _},
     symbols,
     text::Line,
@@ -10,13 +10,20 @@ use ratatui::{
     Frame,
 };
 
+use strum::VariantArray as _;
+
 use crate::{
     app::{Context, LoadType, Mode},
+    collection::CollectionOp,
+    command::Command,
+    keymap::Action,
+    source::federated::{self, FederatedSort},
     title,
+    util::conv::key_to_string,
     widget::sort::SortDir,
 };
 
-use super::{border_block, centered_rect, VirtualStatefulTable};
+use super::{border_block, centered_rect, TableClick, TableMouse, VirtualStatefulTable};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum VisualMode {
@@ -30,6 +37,7 @@ pub struct ResultsWidget {
     pub table: VirtualStatefulTable,
     visual_mode: VisualMode,
     visual_anchor: usize,
+    pub mouse: TableMouse,
 }
 
 impl ResultsWidget {
@@ -38,13 +46,308 @@ impl ResultsWidget {
         *self.table.state.offset_mut() = 0;
     }
 
+    /// Run an action bound in the `[keys.normal]` config section
+    pub fn run(&mut self, ctx: &mut Context, action: Action) {
+        match action {
+            Action::Categories => {
+                ctx.mode = Mode::Category;
+            }
+            Action::Sort if ctx.federated => {
+                let fed = &mut ctx.config.federated;
+                fed.sort = fed.sort.next();
+                let sort = fed.sort;
+                ctx.format_results();
+                ctx.notify_info(format!("Sorting merged results by {}", sort));
+            }
+            Action::SortReversed if ctx.federated => {
+                let fed = &mut ctx.config.federated;
+                fed.sort_dir = fed.sort_dir.reverse();
+                ctx.format_results();
+            }
+            Action::ToggleFederated => {
+                ctx.federated = !ctx.federated;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Action::Sort => {
+                ctx.mode = Mode::Sort(SortDir::Desc);
+            }
+            Action::SortReversed => {
+                ctx.mode = Mode::Sort(SortDir::Asc);
+            }
+            Action::Filters => {
+                ctx.mode = Mode::Filter;
+            }
+            Action::Themes => {
+                ctx.mode = Mode::Theme;
+            }
+            Action::Search => {
+                ctx.mode = Mode::Search;
+            }
+            Action::LocalFilter => {
+                ctx.mode = Mode::LocalFilter;
+            }
+            Action::Command => {
+                ctx.mode = Mode::Command;
+            }
+            Action::GotoPage => {
+                ctx.mode = Mode::Page;
+            }
+            Action::PrevPage if ctx.page > 1 => {
+                ctx.page -= 1;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Action::NextPage if ctx.end_page < ctx.results.response.last_page => {
+                ctx.page = ctx.end_page + 1;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Action::Reload => {
+                ctx.mode = Mode::Loading(LoadType::Reloading);
+            }
+            Action::Quit => {
+                ctx.quit();
+            }
+            Action::Down => {
+                let prev = self.table.selected().unwrap_or(0);
+                let selected = self.table.next(ctx.results.response.items.len(), 1);
+                self.select_on_move(ctx, prev, selected, selected);
+                if prev == selected {
+                    ctx.load_more();
+                }
+            }
+            Action::Up => {
+                let prev = self.table.selected().unwrap_or(0);
+                let selected = self.table.next(ctx.results.response.items.len(), -1);
+                self.select_on_move(ctx, prev, selected, selected);
+            }
+            Action::DownFour => {
+                let prev = self.table.selected().unwrap_or(0);
+                let selected = self.table.next(ctx.results.response.items.len(), 4);
+                self.select_on_move(ctx, prev, prev + 1, selected);
+                if prev == selected {
+                    ctx.load_more();
+                }
+            }
+            Action::UpFour => {
+                let prev = self.table.selected().unwrap_or(0);
+                let selected = self.table.next(ctx.results.response.items.len(), -4);
+                self.select_on_move(ctx, prev, selected, prev.saturating_sub(1));
+            }
+            Action::Bottom => {
+                let prev = self.table.selected().unwrap_or(0);
+                let selected = ctx.results.response.items.len().saturating_sub(1);
+                self.table.select(selected);
+
+                if self.visual_mode != VisualMode::None && prev != selected {
+                    self.select_on_move(ctx, prev, prev + 1, selected);
+                }
+            }
+            Action::Top => {
+                let prev = self.table.selected().unwrap_or(0);
+                self.table.select(0);
+                self.select_on_move(ctx, prev, 0, prev.saturating_sub(1));
+            }
+            Action::FirstPage if ctx.page != 1 => {
+                ctx.page = 1;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Action::LastPage
+                if ctx.page != ctx.results.response.last_page
+                    && ctx.results.response.last_page > 0 =>
+            {
+                ctx.page = ctx.results.response.last_page;
+                ctx.mode = Mode::Loading(LoadType::Searching);
+            }
+            Action::Download => {
+                ctx.mode = Mode::Loading(LoadType::Downloading);
+            }
+            Action::Sources => {
+                ctx.mode = Mode::Sources;
+            }
+            Action::Clients => {
+                ctx.mode = Mode::Clients;
+            }
+            Action::DownloadWithOptions => {
+                ctx.configure_download(false);
+            }
+            Action::User => {
+                ctx.mode = Mode::User;
+            }
+            Action::SavedSearches => {
+                ctx.mode = Mode::SavedSearches;
+            }
+            Action::Collections => {
+                ctx.mode = Mode::Collections;
+                ctx.collection(CollectionOp::Refresh);
+            }
+            Action::DownloadHistory => {
+                ctx.mode = Mode::DownloadHistory;
+            }
+            Action::Torrents => {
+                ctx.mode = Mode::Torrents;
+                ctx.refresh_torrents();
+            }
+            Action::PendingDownloads => {
+                ctx.mode = Mode::PendingDownloads;
+            }
+            Action::Details => {
+                ctx.mode = Mode::Loading(LoadType::Details);
+            }
+            Action::OpenInBrowser => {
+                let link = ctx
+                    .results
+                    .response
+                    .items
+                    .get(self.table.state.selected().unwrap_or(0))
+                    .map(|item| item.post_link.clone())
+                    .unwrap_or("https://nyaa.si".to_owned());
+                let res = open::that_detached(&link);
+                if let Err(e) = res {
+                    ctx.notify_error(format!("Failed to open {}:\n{}", link, e));
+                } else {
+                    ctx.notify_info(format!("Opened {}", link));
+                }
+            }
+            Action::VisualToggle => {
+                if self.visual_mode != VisualMode::Toggle {
+                    ctx.notify_info("Entered VISUAL TOGGLE mode");
+                    self.visual_anchor = self.table.selected().unwrap_or(0);
+                    self.try_select_toggle(ctx, self.visual_anchor, self.visual_anchor);
+                    self.visual_mode = VisualMode::Toggle;
+                } else {
+                    ctx.notify_info("Exited VISUAL TOGGLE mode");
+                    self.visual_anchor = 0;
+                    self.visual_mode = VisualMode::None;
+                }
+            }
+            Action::VisualAdd => {
+                if self.visual_mode != VisualMode::Add {
+                    ctx.notify_info("Entered VISUAL ADD mode");
+                    self.visual_anchor = self.table.selected().unwrap_or(0);
+                    self.try_select_add(ctx, self.visual_anchor, self.visual_anchor);
+                    self.visual_mode = VisualMode::Add;
+                } else {
+                    ctx.notify_info("Exited VISUAL ADD mode");
+                    self.visual_anchor = 0;
+                    self.visual_mode = VisualMode::None;
+                }
+            }
+            Action::VisualRemove => {
+                if self.visual_mode != VisualMode::Remove {
+                    ctx.notify_info("Entered VISUAL REMOVE mode");
+                    self.visual_anchor = self.table.selected().unwrap_or(0);
+                    self.try_select_remove(ctx, self.visual_anchor, self.visual_anchor);
+                    self.visual_mode = VisualMode::Remove;
+                } else {
+                    ctx.notify_info("Exited VISUAL REMOVE mode");
+                    self.visual_anchor = 0;
+                    self.visual_mode = VisualMode::None;
+                }
+            }
+            Action::ToggleBatch => {
+                if let Some(sel) = self.table.state.selected() {
+                    if let Some(item) = ctx.results.response.items.get(sel).cloned() {
+                        if let Some(p) = ctx.batch.iter().position(|s| s.id == item.id) {
+                            ctx.batch.remove(p);
+                        } else {
+                            ctx.add_to_batch(item);
+                        }
+                    }
+                }
+            }
+            Action::FocusBatch => {
+                ctx.mode = Mode::Batch;
+            }
+            Action::Cancel => {
+                match self.visual_mode {
+                    VisualMode::Add => ctx.notify_info("Exited VISUAL ADD mode"),
+                    VisualMode::Remove => ctx.notify_info("Exited VISUAL REMOVE mode"),
+                    VisualMode::Toggle => ctx.notify_info("Exited VISUAL TOGGLE mode"),
+                    VisualMode::None => ctx.dismiss_notifications(),
+                }
+                self.visual_anchor = 0;
+                self.visual_mode = VisualMode::None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Select a row the mouse landed on, extending the selection in visual mode
+    fn select(&mut self, ctx: &mut Context, idx: usize) {
+        let prev = self.table.selected().unwrap_or(0);
+        self.table.select(idx);
+        match idx > prev {
+            true => self.select_on_move(ctx, prev, prev + 1, idx),
+            false => self.select_on_move(ctx, prev, idx, prev.saturating_sub(1)),
+        }
+    }
+
+    /// Sort by the column under `x`, reversing the direction if it's already sorted by it
+    fn click_header(&self, ctx: &mut Context, x: u16) {
+        // Lay out the columns the same way the table does
+        let width = self.mouse.area.width.saturating_sub(2);
+        let cols = Layout::horizontal(ctx.results.table.binding.to_owned())
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(Rect::new(0, 0, width, 1));
+        let Some(sort) = cols
+            .iter()
+            .position(|c| c.left() <= x && x < c.right())
+            .and_then(|i| ctx.results.table.sorts.get(i).copied().flatten())
+        else {
+            return;
+        };
+        let cmd = match ctx.federated {
+            true => {
+                let Some(sort) = FederatedSort::VARIANTS.get(sort).copied() else {
+                    return;
+                };
+                let fed = &ctx.config.federated;
+                match fed.sort == sort {
+                    true => Command::FederatedSort(sort, fed.sort_dir.reverse()),
+                    false => Command::FederatedSort(sort, SortDir::Desc),
+                }
+            }
+            false => {
+                let current = &ctx.results.search.sort;
+                match current.sort == sort {
+                    true => Command::Sort(sort, current.dir.reverse()),
+                    false => Command::Sort(sort, SortDir::Desc),
+                }
+            }
+        };
+        ctx.run_command(cmd);
+    }
+
+    fn handle_mouse(&mut self, ctx: &mut Context, m: &MouseEvent) {
+        let len = ctx.results.response.items.len();
+        match self.mouse.event(m, self.table.state.offset(), len) {
+            Some(TableClick::Row(idx) | TableClick::Scrollbar(idx)) => self.select(ctx, idx),
+            Some(TableClick::DoubleClick(idx)) => {
+                self.select(ctx, idx);
+                self.run(ctx, Action::Download);
+            }
+            Some(TableClick::Scroll(amt)) => match amt > 0 {
+                true => self.run(ctx, Action::Down),
+                false => self.run(ctx, Action::Up),
+            },
+            Some(TableClick::Header(x)) => self.click_header(ctx, x),
+            None => {}
+        }
+    }
+
     fn try_select_add(&self, ctx: &mut Context, start: usize, stop: usize) {
-        if let Some(item) = ctx.results.response.items.get(start..=stop) {
-            item.iter().for_each(|i| {
+        if let Some(items) = ctx
+            .results
+            .response
+            .items
+            .get(start..=stop)
+            .map(|i| i.to_vec())
+        {
+            for i in items {
                 if !ctx.batch.iter().any(|s| s.id == i.id) {
-                    ctx.batch.push(i.to_owned());
+                    ctx.add_to_batch(i);
                 }
-            });
+            }
         }
     }
 
@@ -59,14 +362,20 @@ impl ResultsWidget {
     }
 
     fn try_select_toggle(&self, ctx: &mut Context, start: usize, stop: usize) {
-        if let Some(item) = ctx.results.response.items.get(start..=stop) {
-            item.iter().for_each(|i| {
+        if let Some(items) = ctx
+            .results
+            .response
+            .items
+            .get(start..=stop)
+            .map(|i| i.to_vec())
+        {
+            for i in items {
                 if let Some(p) = ctx.batch.iter().position(|s| s.id == i.id) {
                     ctx.batch.remove(p);
                 } else {
-                    ctx.batch.push(i.to_owned());
+                    ctx.add_to_batch(i);
                 }
-            })
+            }
         }
     }
 
@@ -106,6 +415,7 @@ impl Default for ResultsWidget {
             table: VirtualStatefulTable::new(),
             visual_mode: VisualMode::None,
             visual_anchor: 0,
+            mouse: TableMouse::default(),
         }
     }
 }
@@ -134,7 +444,15 @@ impl super::Widget for ResultsWidget {
                 .rows
                 .clone()
                 .into_iter()
-                .map(Into::into)
+                .zip(ctx.results.response.items.iter())
+                .map(|(row, item)| {
+                    let row: Row = row.into();
+                    // Dim results that have already been downloaded
+                    match ctx.download_history.contains(ctx.item_source(item), item) {
+                        true => row.italic().dim(),
+                        false => row,
+                    }
+                })
                 .collect(),
         };
 
@@ -145,26 +463,56 @@ impl super::Widget for ResultsWidget {
         });
 
         let num_items = items.len();
+        // The local filter can leave fewer results than the selected index
+        if num_items > 0 && self.table.selected().is_some_and(|s| s >= num_items) {
+            self.table.select(num_items - 1);
+        }
         let first_item = (ctx.page - 1) * 75;
         let focused = matches!(ctx.mode, Mode::Normal | Mode::KeyCombo(_));
 
+        let extra_clients = match ctx.download_clients().len() {
+            1 => "".to_owned(),
+            n => format!(" +{}", n - 1),
+        };
+        let src = match ctx.federated {
+            true => ctx
+                .config
+                .federated
+                .sources
+                .iter()
+                .map(|s| federated::label(*s))
+                .collect::<Vec<&str>>()
+                .join("+"),
+            false => ctx.src.to_string(),
+        };
         let dl_src = title!(
-            "dl: {}, src: {}",
+            "dl: {}{}, src: {}",
             ctx.client.to_string(),
-            ctx.src.to_string()
+            extra_clients,
+            src
         );
 
+        let pages = match ctx.end_page > ctx.page {
+            true => format!("{}-{}", ctx.page, ctx.end_page),
+            false => ctx.page.to_string(),
+        };
         let title = title!(
             "Results {}-{} ({} total): Page {}/{}",
             first_item + 1,
             num_items + first_item,
             ctx.results.response.total_results,
-            ctx.page,
+            pages,
             ctx.results.response.last_page,
         );
         let mut block = border_block(&ctx.theme, focused)
             .title(title)
             .title_top(Line::from(dl_src).right_aligned());
+        if let Some(filter) = ctx.local_filter.as_ref() {
+            block = block.title_bottom(title!("filter: {}", filter.expr));
+        }
+        if ctx.loading_more() {
+            block = block.title_bottom(title!("Loading page {}…", ctx.end_page + 1));
+        }
         if !ctx.last_key.is_empty() {
             let key_str = title!(ctx.last_key);
             block = block.title_bottom(Line::from(key_str).right_aligned());
@@ -175,6 +523,7 @@ impl super::Widget for ResultsWidget {
             .block(block)
             .highlight_style(Style::default().bg(ctx.theme.hl_bg));
 
+        self.mouse.set_area(area, 1);
         let visible_height = area.height.saturating_sub(3) as usize;
         super::scroll_padding(
             self.table.selected().unwrap_or(0),
@@ -223,6 +572,9 @@ impl super::Widget for ResultsWidget {
     }
 
     fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Mouse(m) = e {
+            self.handle_mouse(ctx, m);
+        }
         if let Event::Key(KeyEvent {
             code,
             kind: KeyEventKind::Press,
@@ -230,220 +582,19 @@ impl super::Widget for ResultsWidget {
             ..
         }) = e
         {
-            use KeyCode::*;
-            match (code, modifiers) {
-                (Char('c'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Category;
-                }
-                (Char('s'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Sort(SortDir::Desc);
-                }
-                (Char('S'), &KeyModifiers::SHIFT) => {
-                    ctx.mode = Mode::Sort(SortDir::Asc);
-                }
-                (Char('f'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Filter;
-                }
-                (Char('t'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Theme;
-                }
-                (Char('/') | Char('i'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Search;
-                }
-                (Char('p'), &KeyModifiers::CONTROL) => {
-                    ctx.mode = Mode::Page;
-                }
-                (Char('p') | Char('h') | Left, &KeyModifiers::NONE) => {
-                    if ctx.page > 1 {
-                        ctx.page -= 1;
-                        ctx.mode = Mode::Loading(LoadType::Searching);
-                    }
-                }
-                (Char('n') | Char('l') | Right, &KeyModifiers::NONE) => {
-                    if ctx.page < ctx.results.response.last_page {
-                        ctx.page += 1;
-                        ctx.mode = Mode::Loading(LoadType::Searching);
-                    }
-                }
-                (Char('r'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Loading(LoadType::Searching);
-                }
-                (Char('q'), &KeyModifiers::NONE) => {
-                    ctx.quit();
-                }
-                (Char('j') | KeyCode::Down, &KeyModifiers::NONE) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    let selected = self.table.next(ctx.results.response.items.len(), 1);
-                    self.select_on_move(ctx, prev, selected, selected);
-                }
-                (Char('k') | KeyCode::Up, &KeyModifiers::NONE) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    let selected = self.table.next(ctx.results.response.items.len(), -1);
-                    self.select_on_move(ctx, prev, selected, selected);
-                }
-                (Char('J'), &KeyModifiers::SHIFT) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    let selected = self.table.next(ctx.results.response.items.len(), 4);
-                    self.select_on_move(ctx, prev, prev + 1, selected);
-                }
-                (Char('K'), &KeyModifiers::SHIFT) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    let selected = self.table.next(ctx.results.response.items.len(), -4);
-                    self.select_on_move(ctx, prev, selected, prev.saturating_sub(1));
-                }
-                (Char('G'), &KeyModifiers::SHIFT) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    let selected = ctx.results.response.items.len().saturating_sub(1);
-                    self.table.select(selected);
-
-                    if self.visual_mode != VisualMode::None && prev != selected {
-                        self.select_on_move(ctx, prev, prev + 1, selected);
-                    }
-                }
-                (Char('g'), &KeyModifiers::NONE) => {
-                    let prev = self.table.selected().unwrap_or(0);
-                    self.table.select(0);
-                    self.select_on_move(ctx, prev, 0, prev.saturating_sub(1));
-                }
-                (Char('H') | Char('P'), &KeyModifiers::SHIFT) => {
-                    if ctx.page != 1 {
-                        ctx.page = 1;
-                        ctx.mode = Mode::Loading(LoadType::Searching);
-                    }
-                }
-                (Char('L') | Char('N'), &KeyModifiers::SHIFT) => {
-                    if ctx.page != ctx.results.response.last_page
-                        && ctx.results.response.last_page > 0
-                    {
-                        ctx.page = ctx.results.response.last_page;
-                        ctx.mode = Mode::Loading(LoadType::Searching);
-                    }
-                }
-                (Enter, &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Loading(LoadType::Downloading);
-                }
-                (Char('s'), &KeyModifiers::CONTROL) => {
-                    ctx.mode = Mode::Sources;
-                }
-                (Char('d'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::Clients;
-                }
-                (Char('u'), &KeyModifiers::NONE) => {
-                    ctx.mode = Mode::User;
-                }
-                (Char('o'), &KeyModifiers::NONE) => {
-                    let link = ctx
-                        .results
-                        .response
-                        .items
-                        .get(self.table.state.selected().unwrap_or(0))
-                        .map(|item| item.post_link.clone())
-                        .unwrap_or("https://nyaa.si".to_owned());
-                    let res = open::that_detached(&link);
-                    if let Err(e) = res {
-                        ctx.notify_error(format!("Failed to open {}:\n{}", link, e));
-                    } else {
-                        ctx.notify_info(format!("Opened {}", link));
-                    }
-                }
-                (Char('y'), &KeyModifiers::NONE) => ctx.mode = Mode::KeyCombo("y".to_string()),
-                (Char(' '), &KeyModifiers::CONTROL) => {
-                    if self.visual_mode != VisualMode::Toggle {
-                        ctx.notify_info("Entered VISUAL TOGGLE mode");
-                        self.visual_anchor = self.table.selected().unwrap_or(0);
-                        self.try_select_toggle(ctx, self.visual_anchor, self.visual_anchor);
-                        self.visual_mode = VisualMode::Toggle;
-                    } else {
-                        ctx.notify_info("Exited VISUAL TOGGLE mode");
-                        self.visual_anchor = 0;
-                        self.visual_mode = VisualMode::None;
-                    }
-                }
-                (Char('v'), &KeyModifiers::NONE) => {
-                    if self.visual_mode != VisualMode::Add {
-                        ctx.notify_info("Entered VISUAL ADD mode");
-                        self.visual_anchor = self.table.selected().unwrap_or(0);
-                        self.try_select_add(ctx, self.visual_anchor, self.visual_anchor);
-                        self.visual_mode = VisualMode::Add;
-                    } else {
-                        ctx.notify_info("Exited VISUAL ADD mode");
-                        self.visual_anchor = 0;
-                        self.visual_mode = VisualMode::None;
-                    }
-                }
-                (Char('V'), &KeyModifiers::SHIFT) => {
-                    if self.visual_mode != VisualMode::Remove {
-                        ctx.notify_info("Entered VISUAL REMOVE mode");
-                        self.visual_anchor = self.table.selected().unwrap_or(0);
-                        self.try_select_remove(ctx, self.visual_anchor, self.visual_anchor);
-                        self.visual_mode = VisualMode::Remove;
-                    } else {
-                        ctx.notify_info("Exited VISUAL REMOVE mode");
-                        self.visual_anchor = 0;
-                        self.visual_mode = VisualMode::None;
-                    }
-                }
-                (Char(' '), &KeyModifiers::NONE) => {
-                    if let Some(sel) = self.table.state.selected() {
-                        if let Some(item) = &mut ctx.results.response.items.get_mut(sel) {
-                            if let Some(p) = ctx.batch.iter().position(|s| s.id == item.id) {
-                                ctx.batch.remove(p);
-                            } else {
-                                ctx.batch.push(item.to_owned());
-                            }
-                        }
-                    }
-                }
-                (Tab | BackTab, _) => {
-                    ctx.mode = Mode::Batch;
-                }
-                (Esc, &KeyModifiers::NONE) => {
-                    match self.visual_mode {
-                        VisualMode::Add => ctx.notify_info("Exited VISUAL ADD mode"),
-                        VisualMode::Remove => ctx.notify_info("Exited VISUAL REMOVE mode"),
-                        VisualMode::Toggle => ctx.notify_info("Exited VISUAL TOGGLE mode"),
-                        VisualMode::None => ctx.dismiss_notifications(),
-                    }
-                    self.visual_anchor = 0;
-                    self.visual_mode = VisualMode::None;
+            let key = key_to_string(*code, *modifiers);
+            match ctx.keymap.action(&Mode::Normal, &key) {
+                Some(action) => self.run(ctx, action),
+                None if ctx.keymap.is_prefix(&Mode::Normal, &key) => {
+                    ctx.mode = Mode::KeyCombo(key);
                 }
-                _ => {}
+                None => {}
             }
         }
     }
 
     fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
-        Some(vec![
-            ("Enter", "Confirm"),
-            ("Esc", "Dismiss notification"),
-            ("q", "Exit App"),
-            ("g/G", "Goto Top/Bottom"),
-            ("k, ↑", "Up"),
-            ("j, ↓", "Down"),
-            ("K, J", "Up/Down 4 items"),
-            ("n, l, →", "Next Page"),
-            ("p, h, ←", "Prev Page"),
-            ("N, L", "Last Page"),
-            ("P, H", "First Page"),
-            ("r", "Reload"),
-            ("o", "Open in browser"),
-            (
-                "yt, ym, yp, yi, yn",
-                "Copy torrent/magnet/post link/imdb id/name",
-            ),
-            ("Space", "Toggle item for batch download"),
-            ("v/V/Ctrl-Space", "Enter visual add/remove/toggle mode"),
-            ("Tab/Shift-Tab", "Switch to Batches"),
-            ("/, i", "Search"),
-            ("c", "Categories"),
-            ("f", "Filters"),
-            ("s", "Sort"),
-            ("S", "Sort reversed"),
-            ("t", "Themes"),
-            ("u", "Filter by User"),
-            ("d", "Select download client"),
-            ("Ctrl-p", "Goto page"),
-            ("Ctrl-s", "Select source"),
-        ])
+        // Generated from the keymap instead
+        None
     }
 }
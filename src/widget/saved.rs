@@ -0,0 +1,318 @@
+use std::cmp::min;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    widgets::{Paragraph, Row, ScrollbarOrientation, StatefulWidget, Table, Widget as _},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{Context, Mode, Widgets},
+    source::Sources,
+    style, title,
+    widget::sort::{SelectedSort, SortDir},
+};
+
+use super::{
+    border_block,
+    input::{self, InputWidget},
+    sources, VirtualStatefulTable, Widget,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub name: String,
+    pub source: Sources,
+    /// Name of the custom source, when there are several
+    pub custom: Option<String>,
+    pub query: String,
+    pub category: String,
+    pub filter: String,
+    pub sort: String,
+    pub sort_dir: SortDir,
+    pub user: Option<String>,
+}
+
+impl SavedSearch {
+    /// Save the search used for the results currently being shown
+    pub fn current(name: String, ctx: &Context) -> Self {
+        let search = &ctx.results.search;
+        let info = &ctx.src_info;
+        SavedSearch {
+            name,
+            source: ctx.src,
+            custom: ctx
+                .config
+                .sources
+                .custom()
+                .map(|c| c.name)
+                .filter(|name| ctx.src == Sources::Custom && !name.is_empty()),
+            query: search.query.clone(),
+            category: info.clone().entry_from_id(search.category).cfg,
+            filter: info.filters.get(search.filter).cloned().unwrap_or_default(),
+            sort: info
+                .sorts
+                .get(search.sort.sort)
+                .cloned()
+                .unwrap_or_default(),
+            sort_dir: search.sort.dir,
+            user: search.user.clone(),
+        }
+    }
+
+    pub fn apply(&self, ctx: &mut Context, w: &mut Widgets) {
+        if ctx.src != self.source {
+            ctx.src = self.source;
+            ctx.src.load_config(&mut ctx.config.sources);
+        }
+        // Start from the source defaults in case any part of the saved search no longer exists
+        ctx.src.apply(ctx, w);
+
+        if let Ok(cat) = ctx.src_info.find_category(&self.category) {
+            let (major, minor) = ctx.src_info.get_major_minor(cat.id);
+            w.category.selected = cat.id;
+            w.category.table.select(major + minor + 1);
+            w.category.major = major;
+            w.category.minor = minor;
+        }
+        if let Some(filter) = ctx.src_info.find_filter(&self.filter) {
+            w.filter.selected = filter;
+            w.filter.table.select(filter);
+        }
+        if let Some(sort) = ctx.src_info.find_sort(&self.sort) {
+            w.sort.selected = SelectedSort {
+                sort,
+                dir: self.sort_dir,
+            };
+            w.sort.table.select(sort);
+        }
+
+        w.search.input.input.clone_from(&self.query);
+        w.search
+            .input
+            .set_cursor(w.search.input.input.chars().count());
+        ctx.user.clone_from(&self.user);
+    }
+}
+
+pub struct SavedSearchesPopup {
+    pub table: VirtualStatefulTable,
+    pub input: InputWidget,
+    naming: bool,
+}
+
+impl Default for SavedSearchesPopup {
+    fn default() -> Self {
+        SavedSearchesPopup {
+            table: VirtualStatefulTable::new(),
+            input: InputWidget::new(40, None),
+            naming: false,
+        }
+    }
+}
+
+impl SavedSearchesPopup {
+    fn save(&mut self, ctx: &mut Context) {
+        let name = self.input.input.trim().to_owned();
+        if name.is_empty() {
+            return ctx.notify_error("Saved search name cannot be empty");
+        }
+        let saved = SavedSearch::current(name.clone(), ctx);
+        let searches = &mut ctx.config.saved_searches;
+        let idx = match searches.iter().position(|s| s.name == name) {
+            Some(idx) => {
+                searches[idx] = saved;
+                idx
+            }
+            None => {
+                searches.push(saved);
+                searches.len() - 1
+            }
+        };
+        self.table.select(idx);
+        self.naming = false;
+        match ctx.save_config() {
+            Ok(_) => ctx.notify_success(format!("Saved search \"{}\"", name)),
+            Err(e) => ctx.notify_error(format!("Failed to save search to config file:\n{}", e)),
+        }
+    }
+
+    fn run(&mut self, ctx: &mut Context, idx: usize) {
+        if let Some(saved) = ctx.config.saved_searches.get(idx).cloned() {
+            self.table.select(idx);
+            if let (Sources::Custom, Some(name)) = (saved.source, saved.custom.clone()) {
+                sources::select_custom(ctx, name);
+            }
+            ctx.load_saved_search(saved);
+        }
+    }
+
+    fn remove(&mut self, ctx: &mut Context) {
+        let idx = self.table.selected().unwrap_or(0);
+        if idx >= ctx.config.saved_searches.len() {
+            return;
+        }
+        let saved = ctx.config.saved_searches.remove(idx);
+        self.table
+            .select(idx.min(ctx.config.saved_searches.len().saturating_sub(1)));
+        match ctx.save_config() {
+            Ok(_) => ctx.notify_info(format!("Removed saved search \"{}\"", saved.name)),
+            Err(e) => ctx.notify_error(format!(
+                "Failed to remove saved search from config file:\n{}",
+                e
+            )),
+        }
+    }
+}
+
+impl Widget for SavedSearchesPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let searches = &ctx.config.saved_searches;
+        let height = min(searches.len().max(1) as u16 + 2, 14);
+        let center = super::centered_rect(70, height + if self.naming { 3 } else { 0 }, area);
+        let (table_area, input_area) = match self.naming {
+            true => (
+                Rect::new(center.x, center.y, center.width, center.height - 3),
+                Some(Rect::new(center.x, center.bottom() - 3, center.width, 3)),
+            ),
+            false => (center, None),
+        };
+
+        let items = searches.iter().enumerate().map(|(i, s)| {
+            Row::new(vec![
+                match i < 9 {
+                    true => format!(" {}", i + 1),
+                    false => "  ".to_owned(),
+                },
+                s.name.to_owned(),
+                s.custom.clone().unwrap_or_else(|| s.source.to_string()),
+                s.query.to_owned(),
+            ])
+        });
+        let num_items = items.len();
+        super::scroll_padding(
+            self.table.selected().unwrap_or(0),
+            table_area.height as usize,
+            2,
+            num_items,
+            1,
+            self.table.state.offset_mut(),
+        );
+
+        super::clear(center, buf, ctx.theme.bg);
+        let table = Table::new(
+            items,
+            [
+                Constraint::Length(3),
+                Constraint::Percentage(40),
+                Constraint::Length(14),
+                Constraint::Percentage(60),
+            ],
+        )
+        .block(border_block(&ctx.theme, !self.naming).title(title!("Saved Searches")))
+        .highlight_style(style!(bg:ctx.theme.hl_bg));
+        StatefulWidget::render(table, table_area, buf, &mut self.table.state);
+
+        if num_items == 0 {
+            Paragraph::new(" Press a to save the current search").render(
+                table_area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 1,
+                }),
+                buf,
+            );
+        }
+
+        // Only show scrollbar if content overflows
+        if num_items as u16 + 1 >= table_area.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = table_area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut self.table.scrollbar_state.content_length(num_items),
+            );
+        }
+
+        if let Some(input_area) = input_area {
+            let block = border_block(&ctx.theme, true).title(title!("Save Current Search As"));
+            let inner = block.inner(input_area);
+            block.render(input_area, buf);
+            Paragraph::new(self.input.input.clone()).render(inner, buf);
+            self.input.show_cursor(f, inner);
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            if self.naming {
+                match code {
+                    KeyCode::Esc => self.naming = false,
+                    KeyCode::Enter => self.save(ctx),
+                    _ => self.input.handle_event(ctx, e),
+                }
+                return;
+            }
+            let len = ctx.config.saved_searches.len();
+            match code {
+                KeyCode::Esc | KeyCode::Char('b') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.table.next_wrap(len, 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.table.next_wrap(len, -1);
+                }
+                KeyCode::Char('G') => {
+                    self.table.select(len.saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    self.table.select(0);
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    self.run(ctx, *c as usize - '1' as usize);
+                }
+                KeyCode::Enter => {
+                    self.run(ctx, self.table.selected().unwrap_or(0));
+                }
+                KeyCode::Char('a') => {
+                    self.naming = true;
+                    self.input.input.clone_from(&ctx.results.search.query);
+                    self.input.set_cursor(self.input.input.chars().count());
+                }
+                KeyCode::Char('x') | KeyCode::Delete => self.remove(ctx),
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        let mut help = vec![
+            ("Enter", "Run saved search"),
+            ("1-9", "Run saved search by number"),
+            ("a", "Save current search"),
+            ("x, Del", "Remove saved search"),
+            ("Esc, b, q", "Close"),
+            ("j, ↓", "Down"),
+            ("k, ↑", "Up"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ];
+        if let Some(input_help) = input::InputWidget::get_help() {
+            help.extend(input_help);
+        }
+        Some(help)
+    }
+}
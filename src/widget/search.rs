@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Margin, Rect},
@@ -9,6 +11,7 @@ use ratatui::{
 
 use crate::{
     app::{Context, LoadType, Mode},
+    history::SearchHistory,
     title,
 };
 
@@ -19,14 +22,140 @@ use super::{
 
 pub struct SearchWidget {
     pub input: InputWidget,
+    pub history: SearchHistory,
+    /// Position in the history for the current source while recalling with Up/Down, newest first
+    history_idx: Option<usize>,
+    /// Pattern typed while in reverse-incremental search (Ctrl-R)
+    reverse_search: Option<String>,
+    /// What was typed before recalling from history, restored when backing out
+    draft: String,
+    /// Recalled queries that were edited, by position in the history, kept until the search ends
+    edits: HashMap<usize, String>,
 }
 
 impl Default for SearchWidget {
     fn default() -> Self {
         SearchWidget {
             input: InputWidget::new(300, Some(|_| true)),
+            history: SearchHistory::default(),
+            history_idx: None,
+            reverse_search: None,
+            draft: String::new(),
+            edits: HashMap::new(),
+        }
+    }
+}
+
+impl SearchWidget {
+    fn set_input(&mut self, query: &str) {
+        query.clone_into(&mut self.input.input);
+        self.input.set_cursor(self.input.input.chars().count());
+    }
+
+    fn start_recall(&mut self) {
+        if self.history_idx.is_none() && self.reverse_search.is_none() {
+            self.draft.clone_from(&self.input.input);
+        }
+    }
+
+    fn stop_recall(&mut self) {
+        self.history_idx = None;
+        self.reverse_search = None;
+        self.edits.clear();
+    }
+
+    /// Keep any changes to the recalled query before moving to another one, like shells do
+    fn stash_edit(&mut self, ctx: &Context) {
+        let Some(idx) = self.history_idx else {
+            return;
+        };
+        match self.history.queries(ctx.src).get(idx) == Some(&self.input.input.as_str()) {
+            true => self.edits.remove(&idx),
+            false => self.edits.insert(idx, self.input.input.clone()),
+        };
+    }
+
+    fn recall(&mut self, ctx: &Context, amt: isize) {
+        let idx = match self.history_idx {
+            Some(idx) => idx.checked_add_signed(amt),
+            None if amt > 0 => Some(0),
+            None => None,
+        };
+        let query = idx.and_then(|i| self.history.queries(ctx.src).get(i).map(|q| q.to_string()));
+        match (idx, query) {
+            (Some(idx), Some(query)) => {
+                self.start_recall();
+                self.stash_edit(ctx);
+                self.history_idx = Some(idx);
+                let query = self.edits.get(&idx).cloned().unwrap_or(query);
+                self.set_input(&query);
+            }
+            // Moved past the newest entry, restore what was typed
+            (None, _) if self.history_idx.is_some() => {
+                self.stash_edit(ctx);
+                self.history_idx = None;
+                let draft = self.draft.clone();
+                self.set_input(&draft);
+            }
+            _ => {}
         }
     }
+
+    /// Find the next query at or after `start` (newest first) containing the reverse search pattern
+    fn reverse_find(&mut self, ctx: &Context, start: usize) {
+        let Some(pattern) = self.reverse_search.as_ref().map(|p| p.to_lowercase()) else {
+            return;
+        };
+        let queries = self.history.queries(ctx.src);
+        if let Some((idx, query)) = queries
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, q)| q.to_lowercase().contains(&pattern))
+        {
+            let query = query.to_string();
+            self.history_idx = Some(idx);
+            self.set_input(&query);
+        }
+    }
+
+    /// Handle a key while in reverse-incremental search, returning `true` if it was consumed
+    fn handle_reverse_search(
+        &mut self,
+        ctx: &mut Context,
+        code: &KeyCode,
+        mods: &KeyModifiers,
+    ) -> bool {
+        use KeyCode::*;
+        let Some(pattern) = self.reverse_search.as_mut() else {
+            return false;
+        };
+        match (code, mods) {
+            (Char('r'), &KeyModifiers::CONTROL) => {
+                let start = self.history_idx.map(|i| i + 1).unwrap_or(0);
+                self.reverse_find(ctx, start);
+            }
+            (Char(c), &KeyModifiers::NONE | &KeyModifiers::SHIFT) => {
+                pattern.push(*c);
+                self.reverse_find(ctx, self.history_idx.unwrap_or(0));
+            }
+            (Backspace, &KeyModifiers::NONE) => {
+                pattern.pop();
+                self.reverse_find(ctx, 0);
+            }
+            (Esc, _) | (Char('g'), &KeyModifiers::CONTROL) => {
+                let draft = self.draft.clone();
+                self.set_input(&draft);
+                self.stop_recall();
+            }
+            // Any other key accepts the match and is handled as normal
+            _ => {
+                self.stop_recall();
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl super::Widget for SearchWidget {
@@ -39,8 +168,12 @@ impl super::Widget for SearchWidget {
             "?".bold();
             " for help".into();
         );
+        let title = match &self.reverse_search {
+            Some(pattern) => title!("Search (reverse-i-search: {})", pattern),
+            None => title!("Search"),
+        };
         let block = border_block(&ctx.theme, ctx.mode == Mode::Search)
-            .title(title!("Search"))
+            .title(title)
             .title_top(Line::from(help_title).right_aligned());
         Clear.render(area, buf);
         block.render(area, buf);
@@ -64,14 +197,32 @@ impl super::Widget for SearchWidget {
         }) = evt
         {
             use KeyCode::*;
+            if self.handle_reverse_search(ctx, code, modifiers) {
+                return;
+            }
             match (code, modifiers) {
                 (Esc, &KeyModifiers::NONE) => {
+                    self.stop_recall();
                     ctx.mode = Mode::Normal;
                 }
                 (Enter, &KeyModifiers::NONE) => {
+                    self.stop_recall();
+                    if self
+                        .history
+                        .push(ctx.src, &self.input.input, ctx.config.search_history_size)
+                    {
+                        ctx.save_search_history();
+                    }
                     ctx.mode = Mode::Loading(LoadType::Searching);
                     ctx.page = 1; // Go back to first page
                 }
+                (Up, &KeyModifiers::NONE) => return self.recall(ctx, 1),
+                (Down, &KeyModifiers::NONE) => return self.recall(ctx, -1),
+                (Char('r'), &KeyModifiers::CONTROL) => {
+                    self.start_recall();
+                    self.reverse_search = Some(String::new());
+                    return;
+                }
                 _ => {}
             };
         }
@@ -79,7 +230,12 @@ impl super::Widget for SearchWidget {
     }
 
     fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
-        let mut search_help = vec![("Enter", "Confirm"), ("Esc", "Stop")];
+        let mut search_help = vec![
+            ("Enter", "Confirm"),
+            ("Esc", "Stop"),
+            ("↑, ↓", "Previous/next search"),
+            ("Ctrl-r", "Search history"),
+        ];
         if let Some(input_help) = input::InputWidget::get_help() {
             search_help.extend(input_help);
         }
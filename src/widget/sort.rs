@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
@@ -13,9 +13,9 @@ use crate::{
     style, title,
 };
 
-use super::{border_block, VirtualStatefulTable, Widget};
+use super::{border_block, TableClick, TableMouse, VirtualStatefulTable, Widget};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct SelectedSort {
     pub sort: usize,
     pub dir: SortDir,
@@ -46,6 +46,25 @@ impl SortDir {
         }
         .to_owned()
     }
+
+    pub fn reverse(self) -> Self {
+        match self {
+            SortDir::Desc => SortDir::Asc,
+            SortDir::Asc => SortDir::Desc,
+        }
+    }
+}
+
+impl FromStr for SortDir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "desc" | "descending" => Ok(SortDir::Desc),
+            "asc" | "ascending" => Ok(SortDir::Asc),
+            _ => Err(format!("Unknown sort direction \"{}\"", s)),
+        }
+    }
 }
 
 impl Display for SortDir {
@@ -64,6 +83,7 @@ impl Display for SortDir {
 pub struct SortPopup {
     pub table: VirtualStatefulTable,
     pub selected: SelectedSort,
+    mouse: TableMouse,
 }
 
 impl Default for SortPopup {
@@ -71,6 +91,23 @@ impl Default for SortPopup {
         SortPopup {
             table: VirtualStatefulTable::new(),
             selected: SelectedSort::default(),
+            mouse: TableMouse::default(),
+        }
+    }
+}
+
+impl SortPopup {
+    fn confirm(&mut self, ctx: &mut Context) {
+        if let Some(i) = self.table.state.selected() {
+            self.selected.sort = i;
+            self.selected.dir = match ctx.mode == Mode::Sort(SortDir::Asc) {
+                true => SortDir::Asc,
+                false => SortDir::Desc,
+            };
+            ctx.mode = Mode::Loading(LoadType::Sorting);
+            if let Some(s) = ctx.src_info.sorts.get(i) {
+                ctx.notify_info(format!("Sort by \"{}\" {}", s, self.selected.dir));
+            }
         }
     }
 }
@@ -94,10 +131,24 @@ impl Widget for SortPopup {
             })))
             .highlight_style(style!(bg:ctx.theme.hl_bg));
         super::clear(center, buf, ctx.theme.bg);
+        self.mouse.set_area(center, 0);
         table.render(center, buf, &mut self.table.state);
     }
 
     fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Mouse(m) = e {
+            let len = ctx.src_info.sorts.len();
+            match self.mouse.event(m, self.table.state.offset(), len) {
+                Some(TableClick::Row(i) | TableClick::DoubleClick(i)) => {
+                    self.table.select(i);
+                    self.confirm(ctx);
+                }
+                Some(TableClick::Scroll(amt)) => {
+                    self.table.next(len, amt);
+                }
+                _ => {}
+            }
+        }
         if let Event::Key(KeyEvent {
             code,
             kind: KeyEventKind::Press,
@@ -120,19 +171,7 @@ impl Widget for SortPopup {
                 KeyCode::Char('g') => {
                     self.table.select(0);
                 }
-                KeyCode::Enter => {
-                    if let Some(i) = self.table.state.selected() {
-                        self.selected.sort = i;
-                        self.selected.dir = match ctx.mode == Mode::Sort(SortDir::Asc) {
-                            true => SortDir::Asc,
-                            false => SortDir::Desc,
-                        };
-                        ctx.mode = Mode::Loading(LoadType::Sorting);
-                        if let Some(s) = ctx.src_info.sorts.get(i) {
-                            ctx.notify_info(format!("Sort by \"{}\" {}", s, self.selected.dir));
-                        }
-                    }
-                }
+                KeyCode::Enter => self.confirm(ctx),
                 _ => {}
             }
         }
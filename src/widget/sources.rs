@@ -8,32 +8,92 @@ use strum::VariantArray;
 
 use crate::{
     app::{Context, LoadType, Mode},
-    source::Sources,
+    source::{SourceConfig, Sources},
     style, title,
 };
 
 use super::{border_block, StatefulTable, Widget};
 
 pub struct SourcesPopup {
-    pub table: StatefulTable<Sources>,
+    /// Each source, with a row for every named custom source
+    pub table: StatefulTable<(Sources, Option<String>)>,
 }
 
 impl Default for SourcesPopup {
     fn default() -> Self {
         SourcesPopup {
-            table: StatefulTable::new(Sources::VARIANTS),
+            table: StatefulTable::new(&entries(&SourceConfig::default())),
         }
     }
 }
 
+fn entries(config: &SourceConfig) -> Vec<(Sources, Option<String>)> {
+    Sources::VARIANTS
+        .iter()
+        .flat_map(|src| {
+            let names = match src {
+                Sources::Custom => config
+                    .custom
+                    .as_ref()
+                    .map(|c| c.names())
+                    .unwrap_or_default(),
+                _ => vec![],
+            };
+            match names.is_empty() {
+                true => vec![(*src, None)],
+                false => names.into_iter().map(|n| (*src, Some(n))).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Switch to one of several custom sources by name
+pub fn select_custom(ctx: &mut Context, name: String) {
+    let changed = ctx.config.sources.custom().map(|c| c.name).as_ref() != Some(&name);
+    ctx.config.sources.custom_source = Some(name);
+    switch_source(ctx, Sources::Custom, changed);
+}
+
+pub fn select_source(ctx: &mut Context, src: Sources) {
+    switch_source(ctx, src, false);
+}
+
+fn switch_source(ctx: &mut Context, src: Sources, force: bool) {
+    // Picking a source also leaves federated search
+    if force || ctx.federated || src != ctx.src {
+        ctx.federated = false;
+        ctx.src = src;
+        ctx.config.source = src;
+        ctx.mode = Mode::Loading(LoadType::Sourcing);
+        src.load_config(&mut ctx.config.sources);
+        match ctx.save_config() {
+            Ok(_) => ctx.notify_info(format!("Updated source to \"{}\"", src)),
+            Err(e) => ctx.notify_error(format!(
+                "Failed to update default source in config file:\n{}",
+                e
+            )),
+        }
+    } else {
+        // If source is the same, do nothing
+        ctx.mode = Mode::Normal;
+    }
+}
+
 impl Widget for SourcesPopup {
     fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
         let buf = f.buffer_mut();
+        self.table.items = entries(&ctx.config.sources);
+        let current = ctx.config.sources.custom().map(|c| c.name);
         let center = super::centered_rect(30, self.table.items.len() as u16 + 2, area);
-        let items = self.table.items.iter().map(|item| {
-            Row::new(vec![match item == &ctx.src {
-                true => format!("  {}", item),
-                false => format!("   {}", item),
+        let items = self.table.items.iter().map(|(src, name)| {
+            let label = match name {
+                Some(name) => format!("{}: {}", src, name),
+                None => src.to_string(),
+            };
+            let selected = *src == ctx.src && (name.is_none() || name == &current);
+            Row::new(vec![match selected {
+                true => format!("  {}", label),
+                false => format!("   {}", label),
             }])
         });
         super::clear(center, buf, ctx.theme.bg);
@@ -66,26 +126,11 @@ impl Widget for SourcesPopup {
                 KeyCode::Char('g') => {
                     self.table.select(0);
                 }
-                KeyCode::Enter => {
-                    if let Some(src) = self.table.selected() {
-                        if !src.eq(&ctx.src) {
-                            ctx.src = *src;
-                            ctx.config.source = *src;
-                            ctx.mode = Mode::Loading(LoadType::Sourcing);
-                            src.load_config(&mut ctx.config.sources);
-                            match ctx.save_config() {
-                                Ok(_) => ctx.notify_info(format!("Updated source to \"{}\"", src)),
-                                Err(e) => ctx.notify_error(format!(
-                                    "Failed to update default source in config file:\n{}",
-                                    e
-                                )),
-                            }
-                        } else {
-                            // If source is the same, do nothing
-                            ctx.mode = Mode::Normal;
-                        }
-                    }
-                }
+                KeyCode::Enter => match self.table.selected().cloned() {
+                    Some((_, Some(name))) => select_custom(ctx, name),
+                    Some((src, None)) => select_source(ctx, src),
+                    None => {}
+                },
                 _ => {}
             }
         }
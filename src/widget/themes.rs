@@ -31,12 +31,28 @@ impl Default for ThemePopup {
 fn preview_theme(idx: usize, ctx: &mut Context) {
     if let Some((_, theme)) = ctx.themes.get_index(idx) {
         ctx.theme = theme.clone();
-        ctx.results.table = ctx.src.format_table(
-            &ctx.results.response.items,
-            &ctx.results.search,
-            &ctx.config.sources,
-            &ctx.theme,
-        );
+        ctx.format_results();
+    }
+}
+
+impl ThemePopup {
+    pub fn select(&mut self, ctx: &mut Context, idx: usize) {
+        if let Some((_, theme)) = ctx.themes.get_index(idx) {
+            let theme_name = theme.name.clone();
+            self.selected = idx;
+            self.table.select(idx);
+            ctx.theme = theme.clone();
+            ctx.config.theme.clone_from(&theme.name);
+            ctx.format_results();
+            match ctx.save_config() {
+                Ok(_) => ctx.notify_info(format!("Updated theme to \"{}\"", theme_name)),
+                Err(e) => ctx.notify_error(format!(
+                    "Failed to update default theme in config file:\n{}",
+                    e
+                )),
+            }
+        }
+        ctx.mode = Mode::Normal;
     }
 }
 
@@ -118,29 +134,7 @@ impl Widget for ThemePopup {
                     preview_theme(0, ctx);
                 }
                 KeyCode::Enter => {
-                    let idx = self.table.selected().unwrap_or(0);
-                    if let Some((_, theme)) = ctx.themes.get_index(idx) {
-                        let theme_name = theme.name.clone();
-                        self.selected = idx;
-                        ctx.theme = theme.clone();
-                        ctx.config.theme.clone_from(&theme.name);
-                        ctx.results.table = ctx.src.format_table(
-                            &ctx.results.response.items,
-                            &ctx.results.search,
-                            &ctx.config.sources,
-                            &ctx.theme,
-                        );
-                        match ctx.save_config() {
-                            Ok(_) => {
-                                ctx.notify_info(format!("Updated theme to \"{}\"", theme_name))
-                            }
-                            Err(e) => ctx.notify_error(format!(
-                                "Failed to update default theme in config file:\n{}",
-                                e
-                            )),
-                        }
-                    }
-                    ctx.mode = Mode::Normal;
+                    self.select(ctx, self.table.selected().unwrap_or(0));
                 }
                 _ => {}
             }
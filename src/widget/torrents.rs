@@ -0,0 +1,264 @@
+use std::error::Error;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use human_bytes::human_bytes;
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Stylize as _,
+    widgets::{Paragraph, Row, ScrollbarOrientation, StatefulWidget, Table, Widget as _},
+    Frame,
+};
+
+use crate::{
+    app::{Context, Mode},
+    client::{Client, TorrentAction, TorrentStatus},
+    style, title,
+};
+
+use super::{border_block, VirtualStatefulTable, Widget};
+
+pub struct TorrentsPopup {
+    pub table: VirtualStatefulTable,
+    pub torrents: Vec<TorrentStatus>,
+    pub error: Option<String>,
+    /// Which client the torrents were loaded from, if any
+    pub client: Option<Client>,
+    /// Show every torrent in the client, not just those downloaded from nyaa
+    pub show_all: bool,
+    /// Removing needs the key pressed twice
+    confirm: Option<TorrentAction>,
+}
+
+impl Default for TorrentsPopup {
+    fn default() -> Self {
+        TorrentsPopup {
+            table: VirtualStatefulTable::new(),
+            torrents: vec![],
+            error: None,
+            client: None,
+            show_all: false,
+            confirm: None,
+        }
+    }
+}
+
+fn format_eta(secs: u64) -> String {
+    let (d, h, m, s) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (d, h, m) {
+        (0, 0, 0) => format!("{}s", s),
+        (0, 0, _) => format!("{}m {}s", m, s),
+        (0, _, _) => format!("{}h {}m", h, m),
+        _ => format!("{}d {}h", d, h),
+    }
+}
+
+impl TorrentsPopup {
+    /// Clear out torrents from a different client before they're refreshed
+    pub fn loading(&mut self, client: Client) {
+        if self.client != Some(client) {
+            self.torrents.clear();
+            self.error = None;
+            self.client = None;
+            self.table.select(0);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        client: Client,
+        res: Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>,
+    ) {
+        self.client = Some(client);
+        match res {
+            Ok(torrents) => {
+                self.torrents = torrents;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn visible<'a>(&'a self, ctx: &'a Context) -> impl Iterator<Item = &'a TorrentStatus> {
+        self.torrents
+            .iter()
+            .filter(|t| self.show_all || ctx.download_history.contains_hash(&t.hash))
+    }
+
+    fn manage(&mut self, ctx: &mut Context, action: TorrentAction) {
+        let Some(torrent) = self.table.selected().and_then(|i| self.visible(ctx).nth(i)) else {
+            return;
+        };
+        let (hash, name) = (torrent.hash.clone(), torrent.name.clone());
+        let needs_confirm = matches!(
+            action,
+            TorrentAction::Remove | TorrentAction::RemoveWithData
+        );
+        if needs_confirm && self.confirm != Some(action) {
+            self.confirm = Some(action);
+            let (key, what) = match action {
+                TorrentAction::RemoveWithData => ("X", "and delete its data"),
+                _ => ("x", "but keep its data"),
+            };
+            ctx.notify_warn(format!(
+                "Press {} again to remove \"{}\" {}",
+                key, name, what
+            ));
+            return;
+        }
+        self.confirm = None;
+        ctx.manage_torrents(action, vec![hash]);
+    }
+}
+
+impl Widget for TorrentsPopup {
+    fn draw(&mut self, f: &mut Frame, ctx: &Context, area: Rect) {
+        let buf = f.buffer_mut();
+        let center = super::centered_rect(area.width.saturating_sub(10).max(60), 20, area);
+        let items = self
+            .visible(ctx)
+            .map(|t| {
+                Row::new(vec![
+                    t.name.to_owned(),
+                    format!("{:.1}%", t.progress * 100.),
+                    format!("{}/s", human_bytes(t.dl_speed as f64)),
+                    format!("{}/s", human_bytes(t.up_speed as f64)),
+                    t.eta.map(format_eta).unwrap_or("∞".to_owned()),
+                    t.state.to_owned(),
+                ])
+            })
+            .collect::<Vec<Row>>();
+        let num_items = items.len();
+        super::scroll_padding(
+            self.table.selected().unwrap_or(0),
+            center.height as usize,
+            3,
+            num_items,
+            1,
+            self.table.state.offset_mut(),
+        );
+
+        super::clear(center, buf, ctx.theme.bg);
+        let header = Row::new(vec!["Name", "Done", "Down", "Up", "ETA", "State"])
+            .fg(ctx.theme.border_focused_color)
+            .underlined();
+        let table = Table::new(
+            items,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(7),
+                Constraint::Length(11),
+            ],
+        )
+        .header(header)
+        .block(border_block(&ctx.theme, true).title(title!("Torrents in {}", ctx.client)))
+        .highlight_style(style!(bg:ctx.theme.hl_bg));
+        StatefulWidget::render(table, center, buf, &mut self.table.state);
+
+        let inner = center.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if let Some(err) = self.error.as_ref() {
+            let lines = err.lines().count() as u16;
+            let empty = super::centered_rect(inner.width, lines, inner);
+            Paragraph::new(err.as_str())
+                .fg(ctx.theme.error)
+                .centered()
+                .render(empty, buf);
+        } else if num_items == 0 {
+            let msg = match (self.client.is_some(), self.show_all) {
+                (false, _) => "Loading…",
+                (true, false) => "No torrents from nyaa",
+                (true, true) => "No torrents",
+            };
+            let empty = super::centered_rect(msg.chars().count() as u16, 1, inner);
+            Paragraph::new(msg).render(empty, buf);
+        } else if num_items as u16 + 2 >= center.height {
+            let sb = super::scrollbar(ctx, ScrollbarOrientation::VerticalRight);
+            let sb_area = center.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+            sb.render(
+                sb_area,
+                buf,
+                &mut self.table.scrollbar_state.content_length(num_items),
+            );
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, e: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = e
+        {
+            let len = self.visible(ctx).count();
+            let confirm = self.confirm.take();
+            match code {
+                KeyCode::Esc | KeyCode::Char('T') | KeyCode::Char('q') => {
+                    ctx.mode = Mode::Normal;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.table.next(len, 1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.table.next(len, -1);
+                }
+                KeyCode::Char('J') => {
+                    self.table.next(len, 4);
+                }
+                KeyCode::Char('K') => {
+                    self.table.next(len, -4);
+                }
+                KeyCode::Char('G') => {
+                    self.table.select(len.saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    self.table.select(0);
+                }
+                KeyCode::Char('r') => {
+                    ctx.refresh_torrents();
+                }
+                KeyCode::Char('a') => {
+                    self.show_all = !self.show_all;
+                    self.table.select(0);
+                }
+                KeyCode::Char('p') => self.manage(ctx, TorrentAction::Pause),
+                KeyCode::Char('s') => self.manage(ctx, TorrentAction::Resume),
+                KeyCode::Char('v') => self.manage(ctx, TorrentAction::Recheck),
+                KeyCode::Char('x') | KeyCode::Char('X') => {
+                    self.confirm = confirm;
+                    let action = match code {
+                        KeyCode::Char('X') => TorrentAction::RemoveWithData,
+                        _ => TorrentAction::Remove,
+                    };
+                    self.manage(ctx, action);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get_help() -> Option<Vec<(&'static str, &'static str)>> {
+        Some(vec![
+            ("r", "Refresh"),
+            ("a", "Toggle all torrents"),
+            ("p", "Pause"),
+            ("s", "Resume"),
+            ("v", "Force recheck"),
+            ("x", "Remove (press twice)"),
+            ("X", "Remove with data (press twice)"),
+            ("Esc, T, q", "Close"),
+            ("j, ↓", "Down"),
+            ("k, ↑", "Up"),
+            ("K, J", "Up/Down 4 items"),
+            ("g", "Top"),
+            ("G", "Bottom"),
+        ])
+    }
+}
@@ -1,13 +1,27 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use nyaa::{
     app::App,
-    client::{Client, ClientConfig, DownloadClientResult},
+    client::{
+        BatchDownloadResult, Client, ClientConfig, ClientOutcome, DownloadClientResult,
+        DownloadResponse, SingleDownloadResult, TorrentAction, TorrentStatus,
+    },
     config::{Config, ConfigManager},
-    results::Results,
-    source::{Item, SourceExtraConfig, SourceResults},
+    results::{ResultResponse, Results},
+    source::{federated::FederatedConfig, Item, ItemDetails, SourceExtraConfig, SourceResults},
     sync::{EventSync, ReloadType},
+    widget::notifications::Notification,
 };
 use ratatui::{
     backend::{Backend as _, TestBackend},
@@ -18,19 +32,47 @@ use ratatui::{
 };
 use tokio::sync::mpsc::Sender;
 
+/// How long `wait_for` gives the app to handle a response before sending the next event anyway
+static WAIT_TIMEOUT_MILLIS: u64 = 5000;
+
+/// Responses from the sync that a test can wait for the app to handle
+#[derive(Clone, Copy)]
+pub enum Response {
+    Results,
+    Download,
+    Status,
+}
+
+#[derive(Clone)]
+enum Step {
+    Event(Event),
+    /// Wait until the app has handled this many responses of a kind
+    Wait(Response, usize),
+}
+
 #[derive(Clone)]
 pub struct TestSync {
-    events: Vec<Event>,
+    events: Vec<Step>,
+    /// Number of responses of each kind taken by the app, indexed by `Response`
+    handled: Arc<[AtomicUsize; 3]>,
+    /// Returned by every search, which otherwise never finishes
+    results: Vec<Item>,
+    /// Torrents in the download client
+    torrents: Vec<TorrentStatus>,
+    /// Download clients that can't be reached
+    failing: Vec<Client>,
 }
 
 pub struct TestConfig {
     config_path: PathBuf,
+    config: Config,
 }
 
 impl TestConfig {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         Self {
             config_path: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/config"),
+            config,
         }
     }
 }
@@ -56,12 +98,46 @@ type ResultsFn = fn(
 ) -> Results;
 
 pub struct EventBuilder {
-    events: Vec<Event>,
+    events: Vec<Step>,
+    /// Responses of each kind waited for so far
+    waits: [usize; 3],
+    results: Vec<Item>,
+    torrents: Vec<TorrentStatus>,
+    failing: Vec<Client>,
 }
 
 impl EventBuilder {
     pub fn new() -> Self {
-        EventBuilder { events: Vec::new() }
+        EventBuilder {
+            events: Vec::new(),
+            waits: [0; 3],
+            results: Vec::new(),
+            torrents: Vec::new(),
+            failing: Vec::new(),
+        }
+    }
+
+    pub fn failing(&mut self, clients: Vec<Client>) -> &mut Self {
+        self.failing = clients;
+        self
+    }
+
+    pub fn torrents(&mut self, torrents: Vec<TorrentStatus>) -> &mut Self {
+        self.torrents = torrents;
+        self
+    }
+
+    pub fn results(&mut self, items: Vec<Item>) -> &mut Self {
+        self.results = items;
+        self
+    }
+
+    /// Let the app handle another response of this kind before the next event
+    pub fn wait_for(&mut self, response: Response) -> &mut Self {
+        self.waits[response as usize] += 1;
+        self.events
+            .push(Step::Wait(response, self.waits[response as usize]));
+        self
     }
 
     pub fn string<S: Into<String>>(&mut self, string: S) -> &mut Self {
@@ -72,9 +148,9 @@ impl EventBuilder {
                     true => KeyModifiers::SHIFT,
                     false => KeyModifiers::NONE,
                 };
-                Event::Key(KeyEvent::new(KeyCode::Char(c), modif))
+                Step::Event(Event::Key(KeyEvent::new(KeyCode::Char(c), modif)))
             })
-            .collect::<Vec<Event>>();
+            .collect::<Vec<Step>>();
         self.events.extend(evts);
         self
     }
@@ -100,7 +176,7 @@ impl EventBuilder {
     }
 
     pub fn push(&mut self, evt: Event) -> &mut Self {
-        self.events.push(evt);
+        self.events.push(Step::Event(evt));
         self
     }
 
@@ -109,13 +185,30 @@ impl EventBuilder {
     }
 
     pub fn key_mod(&mut self, key: KeyCode, modifier: KeyModifiers) -> &mut Self {
-        self.events.push(Event::Key(KeyEvent::new(key, modifier)));
-        self
+        self.push(Event::Key(KeyEvent::new(key, modifier)))
+    }
+
+    pub fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) -> &mut Self {
+        self.push(Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }))
+    }
+
+    pub fn click(&mut self, column: u16, row: u16) -> &mut Self {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row)
+            .mouse(MouseEventKind::Up(MouseButton::Left), column, row)
     }
 
     pub fn build(&mut self) -> TestSync {
         TestSync {
             events: self.events.clone(),
+            handled: Arc::new(Default::default()),
+            results: self.results.clone(),
+            torrents: self.torrents.clone(),
+            failing: self.failing.clone(),
         }
     }
 }
@@ -124,6 +217,15 @@ pub async fn run_app<S: EventSync + Clone>(
     sync: S,
     w: u16,
     h: u16,
+) -> Result<Terminal<TestBackend>, Box<dyn Error>> {
+    run_app_with_config(sync, Config::default(), w, h).await
+}
+
+pub async fn run_app_with_config<S: EventSync + Clone>(
+    sync: S,
+    config: Config,
+    w: u16,
+    h: u16,
 ) -> Result<Terminal<TestBackend>, Box<dyn Error>> {
     let mut backend = TestBackend::new(w, h);
     let _ = backend.clear();
@@ -131,7 +233,7 @@ pub async fn run_app<S: EventSync + Clone>(
     let _ = terminal.clear();
 
     let mut app = App::default();
-    let config = TestConfig::new();
+    let config = TestConfig::new(config);
 
     app.run_app::<_, S, TestConfig, true>(&mut terminal, sync, config)
         .await?;
@@ -145,6 +247,25 @@ pub fn reset_buffer(terminal: &Terminal<TestBackend>) -> Buffer {
     buf
 }
 
+/// Each row of the buffer as text, for checking rows whose content changes between runs
+pub fn buffer_lines(buf: &Buffer) -> Vec<String> {
+    buf.content
+        .chunks(buf.area.width as usize)
+        .map(|row| row.iter().map(|c| c.symbol()).collect())
+        .collect()
+}
+
+impl TestSync {
+    /// Send a response, and count it as handled once the app takes it from the channel
+    async fn respond<T>(&self, tx: &Sender<T>, response: Response, value: T) {
+        let _ = tx.send(value).await;
+        while tx.capacity() < tx.max_capacity() && !tx.is_closed() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        self.handled[response as usize].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
 pub fn print_buffer(buf: &Buffer) {
     println!();
     let mut len = 0;
@@ -163,41 +284,163 @@ pub fn print_buffer(buf: &Buffer) {
 impl EventSync for TestSync {
     async fn load_results(
         self,
-        _tx_res: Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
+        tx_res: Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
         _loadtype: nyaa::app::LoadType,
+        src: nyaa::source::Sources,
+        _client: reqwest::Client,
+        query: nyaa::sync::SearchQuery,
+        config: nyaa::source::SourceConfig,
+        theme: nyaa::theme::Theme,
+        _extra: SourceExtraConfig,
+    ) {
+        if self.results.is_empty() {
+            return;
+        }
+        let table = src.format_table(&self.results, &query, &config, &theme);
+        let response = ResultResponse {
+            total_results: self.results.len(),
+            items: self.results.clone(),
+            last_page: 1,
+        };
+        let results = Results::new(query, response, table);
+        self.respond(
+            &tx_res,
+            Response::Results,
+            Ok(SourceResults::Results(results)),
+        )
+        .await;
+    }
+
+    async fn load_details(
+        self,
+        _tx_details: Sender<Result<ItemDetails, Box<dyn Error + Send + Sync>>>,
         _src: nyaa::source::Sources,
         _client: reqwest::Client,
-        _query: nyaa::sync::SearchQuery,
+        _item: Item,
         _config: nyaa::source::SourceConfig,
-        _theme: nyaa::theme::Theme,
-        _extra: SourceExtraConfig,
     ) {
     }
 
     async fn read_event_loop(self, tx_evt: Sender<crossterm::event::Event>) {
-        for evt in self.events.into_iter() {
-            let _ = tx_evt.send(evt).await;
+        for step in self.events.iter() {
+            match step {
+                Step::Event(evt) => {
+                    let _ = tx_evt.send(evt.clone()).await;
+                }
+                Step::Wait(response, count) => {
+                    let start = Instant::now();
+                    while self.handled[*response as usize].load(Ordering::SeqCst) < *count
+                        && start.elapsed() < Duration::from_millis(WAIT_TIMEOUT_MILLIS)
+                    {
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                }
+            }
         }
         let _ = tx_evt.send(Event::FocusLost).await;
     }
 
     async fn download(
         self,
-        _tx_dl: Sender<DownloadClientResult>,
-        _batch: bool,
-        _items: Vec<Item>,
+        tx_dl: Sender<DownloadResponse>,
+        batch: bool,
+        items: Vec<Item>,
+        _config: ClientConfig,
+        _rq_client: reqwest::Client,
+        clients: Vec<Client>,
+    ) {
+        let ids = items.iter().map(|i| i.id.clone()).collect::<Vec<String>>();
+        let outcomes = clients
+            .into_iter()
+            .map(|client| match self.failing.contains(&client) {
+                true => ClientOutcome {
+                    client,
+                    ids: vec![],
+                    failed: ids
+                        .iter()
+                        .map(|id| (id.clone(), "Connection refused".to_owned()))
+                        .collect(),
+                },
+                false => ClientOutcome {
+                    client,
+                    ids: ids.clone(),
+                    failed: vec![],
+                },
+            })
+            .collect::<Vec<ClientOutcome>>();
+        let sent = outcomes.iter().any(|o| !o.ids.is_empty());
+        let result = match (batch, sent) {
+            (true, _) => DownloadClientResult::Batch(BatchDownloadResult {
+                msg: Notification::success(format!("Downloaded {} torrents", ids.len())),
+                errors: vec![],
+                ids: if sent { ids.clone() } else { vec![] },
+                failed: vec![],
+            }),
+            (false, true) => DownloadClientResult::Single(SingleDownloadResult::success(
+                "Downloaded torrent",
+                ids.first().cloned().unwrap_or_default(),
+            )),
+            (false, false) => {
+                DownloadClientResult::Single(SingleDownloadResult::error("Connection refused"))
+            }
+        };
+        let response = DownloadResponse {
+            ids,
+            result,
+            outcomes,
+        };
+        self.respond(&tx_dl, Response::Download, response).await;
+    }
+
+    async fn torrent_status(
+        self,
+        tx_status: Sender<Result<Vec<TorrentStatus>, Box<dyn Error + Send + Sync>>>,
+        _config: ClientConfig,
+        _rq_client: reqwest::Client,
+        _client: Client,
+    ) {
+        let torrents = Ok(self.torrents.clone());
+        self.respond(&tx_status, Response::Status, torrents).await;
+    }
+
+    async fn torrent_action(
+        self,
+        _tx_action: Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        _action: TorrentAction,
+        _hashes: Vec<String>,
         _config: ClientConfig,
         _rq_client: reqwest::Client,
         _client: Client,
     ) {
     }
 
+    async fn export_torrents(
+        self,
+        _tx_export: Sender<Result<String, Box<dyn Error + Send + Sync>>>,
+        _items: Vec<Item>,
+        _dir: String,
+        _rq_client: reqwest::Client,
+    ) {
+    }
+
+    async fn federated_search(
+        self,
+        _tx_res: Sender<Result<SourceResults, Box<dyn Error + Send + Sync>>>,
+        _client: reqwest::Client,
+        _search: nyaa::sync::SearchQuery,
+        _config: nyaa::source::SourceConfig,
+        _theme: nyaa::theme::Theme,
+        _extra: SourceExtraConfig,
+        _federated: FederatedConfig,
+    ) {
+    }
+
     async fn watch_config_loop(self, _tx_evt: Sender<ReloadType>) {}
 }
 
 impl ConfigManager for TestConfig {
     fn load(&self) -> Result<Config, Box<dyn Error>> {
-        Ok(Config::default())
+        Ok(self.config.clone())
     }
 
     fn store(&self, _cfg: &Config) -> Result<(), Box<dyn Error>> {
@@ -208,3 +451,23 @@ impl ConfigManager for TestConfig {
         self.config_path.clone()
     }
 }
+
+/// Two nyaa results, each with its own info hash
+pub fn test_items() -> Vec<Item> {
+    ["Frieren - 01", "Frieren - 02"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, title)| Item {
+            id: format!("nyaa-{}", i + 1),
+            date: "2024-01-01 00:00".to_owned(),
+            seeders: 10,
+            leechers: 1,
+            downloads: 100,
+            size: "1.4 GiB".to_owned(),
+            title: title.to_owned(),
+            magnet_link: format!("magnet:?xt=urn:btih:{:040}&dn={}", i + 1, title),
+            file_name: format!("nyaa-{}.torrent", i + 1),
+            ..Default::default()
+        })
+        .collect()
+}
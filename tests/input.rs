@@ -1,4 +1,4 @@
-use crossterm::event::{Event, KeyCode};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
 use ratatui::buffer::Buffer;
 
 use crate::common::{reset_buffer, run_app, EventBuilder};
@@ -49,3 +49,85 @@ async fn test_search() {
         ])
     );
 }
+
+#[tokio::test]
+async fn test_search_history() {
+    let sync = EventBuilder::new()
+        .string("/one")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("two")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("draft")
+        .key(KeyCode::Up)
+        .key(KeyCode::Up)
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 5).await.unwrap()),
+        Buffer::with_lines([
+            "┌Search──────────────────────────────Press F1 or ? for help┐",
+            "│one                                                       │",
+            "└──────────────────────────────────────────────────────────┘",
+            "┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐",
+            "└──────────────────────────────────────────────────────<Up>┘",
+        ])
+    );
+
+    let sync = EventBuilder::new()
+        .string("/one")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("two")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("draft")
+        .key_mod(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        .string("n")
+        .quit()
+        .build();
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 5).await.unwrap()),
+        Buffer::with_lines([
+            "┌Search (reverse-i-search: n)────────Press F1 or ? for help┐",
+            "│one                                                       │",
+            "└──────────────────────────────────────────────────────────┘",
+            "┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐",
+            "└─────────────────────────────────────────────────────────n┘",
+        ])
+    );
+
+    // Edits to a recalled query are kept while moving through the history
+    let sync = EventBuilder::new()
+        .string("/one")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("two")
+        .enter()
+        .string('/')
+        .key_mod(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .string("draft")
+        .key(KeyCode::Up)
+        .string(" edited")
+        .key(KeyCode::Up)
+        .key(KeyCode::Down)
+        .quit()
+        .build();
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 5).await.unwrap()),
+        Buffer::with_lines([
+            "┌Search──────────────────────────────Press F1 or ? for help┐",
+            "│two edited                                                │",
+            "└──────────────────────────────────────────────────────────┘",
+            "┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐",
+            "└────────────────────────────────────────────────────<Down>┘",
+        ])
+    );
+}
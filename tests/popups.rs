@@ -1,7 +1,13 @@
-use crossterm::event::{KeyCode, KeyModifiers};
-use ratatui::buffer::Buffer;
+use crossterm::event::{KeyCode, KeyModifiers, MouseEventKind};
+use nyaa::{
+    client::{Client, TorrentStatus},
+    config::Config,
+};
+use ratatui::{buffer::Buffer, style::Modifier};
 
-use crate::common::{reset_buffer, run_app, EventBuilder};
+use crate::common::{
+    buffer_lines, reset_buffer, run_app, run_app_with_config, test_items, EventBuilder, Response,
+};
 
 #[allow(dead_code)]
 mod common;
@@ -127,6 +133,45 @@ async fn test_sort() {
     );
 }
 
+#[tokio::test]
+async fn test_sort_mouse() {
+    let sync = EventBuilder::new()
+        .string('s')
+        .mouse(MouseEventKind::ScrollDown, 30, 9)
+        .click(30, 10)
+        .string('s')
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 22).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Search──────────────────────│Sort by "Seeders" Descending│┐"#,
+            r#"│                            └────────────────────────────┘│"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│              ┌Sort Descending─────────────┐              │"#,
+            r#"│              │   Date                     │              │"#,
+            r#"│              │   Downloads                │              │"#,
+            r#"│              │  Seeders                  │              │"#,
+            r#"│              │   Leechers                 │              │"#,
+            r#"│              │   Size                     │              │"#,
+            r#"│              └────────────────────────────┘              │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"└─────────────────────────────────────────────────────────s┘"#,
+        ])
+    );
+}
+
 #[tokio::test]
 async fn test_sort_reverse() {
     let sync = EventBuilder::new()
@@ -209,7 +254,7 @@ async fn test_themes() {
 async fn test_download_client() {
     let sync = EventBuilder::new()
         .string('d')
-        .string("jjjj")
+        .string("jjjjjj")
         .enter()
         .string('d')
         .quit()
@@ -224,7 +269,6 @@ async fn test_download_client() {
             r#"┌Results 1-0 (0 total): Page 1/0─dl: Default App, src: Nyaa┐"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
-            r#"│                                                          │"#,
             r#"│              ┌Download Client─────────────┐              │"#,
             r#"│              │   qBittorrent              │              │"#,
             r#"│              │   Transmission             │              │"#,
@@ -232,13 +276,14 @@ async fn test_download_client() {
             r#"│              │  Default App              │              │"#,
             r#"│              │   Download Torrent File    │              │"#,
             r#"│              │   Run Command              │              │"#,
+            r#"│              │   Deluge                   │              │"#,
+            r#"│              │   aria2                    │              │"#,
             r#"│              └────────────────────────────┘              │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
-            r#"│                                                          │"#,
             r#"└─────────────────────────────────────────────────────────d┘"#,
         ])
     );
@@ -264,11 +309,12 @@ async fn test_source() {
             r#"│                                                          │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
-            r#"│                                                          │"#,
             r#"│              ┌Source──────────────────────┐              │"#,
             r#"│              │   Nyaa                     │              │"#,
             r#"│              │  Sukebei                  │              │"#,
             r#"│              │   TorrentGalaxy            │              │"#,
+            r#"│              │   Custom                   │              │"#,
+            r#"│              │   Torznab                  │              │"#,
             r#"│              └────────────────────────────┘              │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
@@ -277,7 +323,6 @@ async fn test_source() {
             r#"│                                                          │"#,
             r#"│                                                          │"#,
             r#"│                                                          │"#,
-            r#"│                                                          │"#,
             r#"└─────────────────────────────────────────────────────<C-s>┘"#,
         ])
     );
@@ -373,3 +418,368 @@ async fn test_page() {
         ])
     );
 }
+
+#[tokio::test]
+async fn test_saved_searches() {
+    let sync = EventBuilder::new()
+        .string('b')
+        .string('a')
+        .string("daily")
+        .enter()
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 12).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Search──────────────────────────────│Saved search "daily"│┐"#,
+            r#"│                                    └────────────────────┘│"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐"#,
+            r#"┌Saved Searches────────────────────────────────────────────┐"#,
+            r#"│ 1  daily          Nyaa                                   │"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"│                       Searching…                         │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"└──────────────────────────────────────────────────────<CR>┘"#,
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_saved_search_custom_source() {
+    let mut config: Config = toml::from_str(
+        r#"
+default_source = "Custom"
+
+[source]
+custom_source = "Example"
+
+[[source.custom]]
+name = "Example"
+base_url = "https://example.org/"
+
+[[source.custom]]
+name = "Other"
+base_url = "https://other.example.net/"
+
+[[saved_search]]
+name = "daily"
+source = "Custom"
+custom = "Other"
+query = ""
+category = ""
+filter = ""
+sort = ""
+sort_dir = "Desc"
+"#,
+    )
+    .unwrap();
+    config.save_config_on_change = false;
+    let sync = EventBuilder::new()
+        .string('b')
+        .string('a')
+        .string("before")
+        .enter()
+        // Recall "daily", then save the search it ran
+        .string("g")
+        .enter()
+        .string('b')
+        .string('a')
+        .string("after")
+        .enter()
+        .quit()
+        .build();
+
+    let lines = buffer_lines(&reset_buffer(
+        &run_app_with_config(sync, config, 90, 16).await.unwrap(),
+    ));
+    let row = |name: &str| {
+        let name = format!(" {}  ", name);
+        lines.iter().find(|l| l.contains(&name)).unwrap().clone()
+    };
+    assert!(row("daily").contains("Other"));
+    assert!(row("before").contains("Example"));
+    assert!(row("after").contains("Other"));
+}
+
+#[tokio::test]
+async fn test_downloaded_results() {
+    let sync = EventBuilder::new()
+        .results(test_items())
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        .key(KeyCode::Char('j'))
+        .quit()
+        .build();
+
+    let terminal = run_app(sync, 120, 12).await.unwrap();
+    let buf = terminal.backend().buffer();
+    let lines = buffer_lines(buf);
+    assert!(lines[5].contains("Frieren - 01"));
+    assert!(lines[6].contains("Frieren - 02"));
+    let dimmed = Modifier::DIM | Modifier::ITALIC;
+    assert!(buf[(5, 5)].modifier.contains(dimmed));
+    assert!(!buf[(5, 6)].modifier.intersects(dimmed));
+}
+
+#[tokio::test]
+async fn test_download_history() {
+    let sync = EventBuilder::new()
+        .results(test_items())
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        // Download the first item again from the history
+        .key_mod(KeyCode::Char('D'), KeyModifiers::SHIFT)
+        .enter()
+        .wait_for(Response::Download)
+        .esc()
+        .key_mod(KeyCode::Char('D'), KeyModifiers::SHIFT)
+        .quit()
+        .build();
+
+    let lines = buffer_lines(&reset_buffer(&run_app(sync, 120, 12).await.unwrap()));
+    assert!(lines[0].starts_with("┌Sear┌Download History──"));
+    assert!(lines[1].starts_with("│    │Date             Source        Size       Title "));
+    for line in &lines[2..4] {
+        assert!(line.contains("Nyaa          1.4 GiB    Frieren - 01 "));
+    }
+    assert!(!lines.iter().any(|l| l.contains("Frieren - 02")));
+}
+
+#[tokio::test]
+async fn test_torrents() {
+    let items = test_items();
+    let torrent = |name: &str, hash: String| TorrentStatus {
+        hash,
+        name: name.to_owned(),
+        progress: 0.5,
+        state: "Downloading".to_owned(),
+        ..Default::default()
+    };
+    let torrents = vec![
+        torrent("Other torrent", "f".repeat(40)),
+        torrent("Frieren - 01", format!("{:040}", 1)),
+    ];
+    let sync = EventBuilder::new()
+        .results(items)
+        .torrents(torrents)
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        .key_mod(KeyCode::Char('T'), KeyModifiers::SHIFT)
+        .wait_for(Response::Status)
+        .quit()
+        .build();
+
+    // Only torrents downloaded through nyaa are shown
+    let lines = buffer_lines(&reset_buffer(&run_app(sync, 120, 12).await.unwrap()));
+    assert!(lines[1].contains("Name"));
+    assert!(lines[2].contains("Frieren - 01"));
+    assert!(lines[2].contains("50.0%"));
+    assert!(!lines.iter().any(|l| l.contains("Other torrent")));
+}
+
+#[tokio::test]
+async fn test_torrents_show_all() {
+    let sync = EventBuilder::new()
+        .torrents(vec![TorrentStatus {
+            name: "Other torrent".to_owned(),
+            ..Default::default()
+        }])
+        .key_mod(KeyCode::Char('T'), KeyModifiers::SHIFT)
+        .wait_for(Response::Status)
+        .key(KeyCode::Char('a'))
+        .quit()
+        .build();
+
+    let lines = buffer_lines(&reset_buffer(&run_app(sync, 120, 12).await.unwrap()));
+    assert!(lines[2].contains("Other torrent"));
+}
+
+#[tokio::test]
+async fn test_pending_downloads() {
+    let config = Config {
+        download_client: vec![Client::Qbit, Client::Transmission].into(),
+        ..Default::default()
+    };
+    let sync = EventBuilder::new()
+        .results(test_items())
+        .failing(vec![Client::Transmission])
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        .key_mod(KeyCode::Char('R'), KeyModifiers::SHIFT)
+        // Retry now, which only goes to the client that failed
+        .string("r")
+        .wait_for(Response::Download)
+        .quit()
+        .build();
+
+    let lines = buffer_lines(&reset_buffer(
+        &run_app_with_config(sync, config.clone(), 160, 30)
+            .await
+            .unwrap(),
+    ));
+    let row = lines.iter().find(|l| l.contains("Frieren - 01")).unwrap();
+    // Only the client that failed is queued, and it was tried again
+    assert!(row.contains("Transmission 2 "));
+    assert!(row.contains("Transmission: Connection"));
+    assert!(!lines.iter().any(|l| l.contains("Frieren - 02")));
+
+    // Stop retrying the download
+    let sync = EventBuilder::new()
+        .results(test_items())
+        .failing(vec![Client::Transmission])
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        .key_mod(KeyCode::Char('R'), KeyModifiers::SHIFT)
+        .string("x")
+        .quit()
+        .build();
+    let lines = buffer_lines(&reset_buffer(
+        &run_app_with_config(sync, config, 120, 12).await.unwrap(),
+    ));
+    assert!(lines
+        .iter()
+        .any(|l| l.contains("Stopped retrying \"Frieren - 01\"")));
+    assert!(!lines.iter().any(|l| l.contains("Transmission: Connection")));
+}
+
+#[tokio::test]
+async fn test_pending_download_again() {
+    let config = Config {
+        download_client: vec![Client::Transmission].into(),
+        ..Default::default()
+    };
+    // Downloading a queued item by hand still reports when it fails
+    let sync = EventBuilder::new()
+        .results(test_items())
+        .failing(vec![Client::Transmission])
+        .wait_for(Response::Results)
+        .enter()
+        .wait_for(Response::Download)
+        .enter()
+        .wait_for(Response::Download)
+        .quit()
+        .build();
+    let lines = buffer_lines(&reset_buffer(
+        &run_app_with_config(sync, config, 160, 30).await.unwrap(),
+    ));
+    let errors = lines
+        .iter()
+        .filter(|l| l.contains("Connection refused"))
+        .count();
+    assert_eq!(errors, 2);
+}
+
+#[tokio::test]
+async fn test_download_options() {
+    let sync = EventBuilder::new()
+        .string("dg")
+        .enter()
+        .key_mod(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        .string("~/Downloads")
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 12).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Search──────────│Updated download client to "qBittorrent"│┐"#,
+            r#"│                └────────────────────────────────────────┘│"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"┌Resu┌Download with qBittorrent───────────────────────┐Nyaa┐"#,
+            r#"│    │Save path  ~/Downloads                          │    │"#,
+            r#"│    │Category                                        │    │"#,
+            r#"│    │Tags                                            │    │"#,
+            r#"│    │Paused     [ ]                                  │    │"#,
+            r#"│    └────────────────────────────────────────────────┘    │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"└─────────────────────────────────────────────────────────s┘"#,
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_local_filter() {
+    let sync = EventBuilder::new()
+        .key_mod(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        .string("seeders>ten")
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 12).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Search──────────────────────────────Press F1 or ? for help┐"#,
+            r#"│                                                          │"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐"#,
+            r#"│Cat Name                    Size     Date              │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                       Searching…                         │"#,
+            r#"│                                                          │"#,
+            r#"┌Filter Results────────────────────────────────────────────┐"#,
+            r#"│seeders>ten                                               │"#,
+            r#"└"ten" is not a valid number───────────────────────────────┘"#,
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_help_keymap() {
+    let sync = EventBuilder::new().key(KeyCode::Char('?')).quit().build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 12).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Help: Normal──────────────────────────────────────────────┐"#,
+            r#"│      Key                          Action                 │"#,
+            r#"│           <CR> ⇒ Download                                █"#,
+            r#"│          <Esc> ⇒ Dismiss notification or exit visual mode█"#,
+            r#"│              q ⇒ Exit app                                │"#,
+            r#"│              g ⇒ Goto top                                │"#,
+            r#"│              G ⇒ Goto bottom                             │"#,
+            r#"│        k, <Up> ⇒ Up                                      │"#,
+            r#"│      j, <Down> ⇒ Down                                    │"#,
+            r#"│              K ⇒ Up 4 items                              │"#,
+            r#"│              J ⇒ Down 4 items                            │"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_command() {
+    let sync = EventBuilder::new()
+        .string(":sort s")
+        .key(KeyCode::Tab)
+        .quit()
+        .build();
+
+    assert_eq!(
+        reset_buffer(&run_app(sync, 60, 12).await.unwrap()),
+        Buffer::with_lines([
+            r#"┌Search──────────────────────────────Press F1 or ? for help┐"#,
+            r#"│                                                          │"#,
+            r#"└──────────────────────────────────────────────────────────┘"#,
+            r#"┌Results 1-0 (0 total): Page 1/0─dl: Run Command, src: Nyaa┐"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                                                          │"#,
+            r#"│                       Searching…                         │"#,
+            r#"│                                                          │"#,
+            r#"┌Command───────────────────────────────────────────────────┐"#,
+            r#"│sort Seeders                                              │"#,
+            r#"└ Seeders  Size ───────────────────────────────────────────┘"#,
+        ])
+    );
+}